@@ -18,7 +18,7 @@ use tempfile::TempDir;
 
 use machine_setup::config;
 use machine_setup::config::types::CommandEntry;
-use machine_setup::engine::commands::copy::should_skip_copy;
+use machine_setup::engine::commands::copy::{same_content, should_skip_copy};
 use machine_setup::engine::commands::create_executor;
 use machine_setup::engine::commands::fs_ops::{self, DirectFs, FileOps};
 use machine_setup::engine::commands::tree::{
@@ -190,6 +190,38 @@ fn bench_mtime_skip(c: &mut Criterion) {
     group.finish();
 }
 
+/// `compare: hash` on an already-synced tree: every file is read on both
+/// sides, none is rewritten.
+fn bench_hash_skip(c: &mut Criterion) {
+    let fixture = fixture_1k();
+    let ops = DirectFs;
+    let synced = tempfile::tempdir().expect("synced");
+    copy_tree(&ops, fixture.src(), synced.path());
+
+    let mut group = c.benchmark_group("hash_skip");
+    group.warm_up_time(Duration::from_secs(1));
+    group.measurement_time(Duration::from_secs(3));
+    group.bench_function("1k_already_synced", |b| {
+        b.iter(|| {
+            install_tree_with_pool(
+                fixture.src(),
+                synced.path(),
+                &[],
+                Some(bench_pool()),
+                |dir| ops.mkdir_p(dir),
+                |src, dest| {
+                    if same_content(src, dest) {
+                        return Ok(());
+                    }
+                    ops.copy_file(src, dest)
+                },
+            )
+            .expect("hash skip walk");
+        });
+    });
+    group.finish();
+}
+
 fn bench_symlink_tree(c: &mut Criterion) {
     let fixture = fixture_1k();
     let ops = DirectFs;
@@ -360,7 +392,7 @@ fn bench_runner_smoke(c: &mut Criterion) {
     group.bench_function("single_copy_1k_null_sink", |b| {
         b.to_async(&rt).iter_batched(
             || prepare_runner_case(fixture.src(), false),
-            |case| run_case(case),
+            run_case,
             BatchSize::LargeInput,
         );
     });
@@ -368,17 +400,14 @@ fn bench_runner_smoke(c: &mut Criterion) {
     group.bench_function("parallel_two_copy_tasks_1k", |b| {
         b.to_async(&rt).iter_batched(
             || prepare_runner_case(fixture.src(), true),
-            |case| run_case(case),
+            run_case,
             BatchSize::LargeInput,
         );
     });
 
     group.bench_function("empty_task", |b| {
-        b.to_async(&rt).iter_batched(
-            prepare_empty_runner_case,
-            |case| run_case(case),
-            BatchSize::SmallInput,
-        );
+        b.to_async(&rt)
+            .iter_batched(prepare_empty_runner_case, run_case, BatchSize::SmallInput);
     });
 
     group.finish();
@@ -429,6 +458,7 @@ criterion_group!(
     bench_tree_install_direct,
     bench_tree_install_sudo,
     bench_mtime_skip,
+    bench_hash_skip,
     bench_symlink_tree,
     bench_uninstall_tree,
     bench_runner_smoke,
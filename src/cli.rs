@@ -11,9 +11,14 @@ pub struct Cli {
     #[command(subcommand)]
     pub command: Command,
 
-    /// Path or URL to config file (YAML/JSON). When omitted, searches cwd then git root.
+    /// Path, URL or git source of a config file (YAML/JSON); repeat to run several
+    /// documents together. When omitted, searches cwd then git root.
     #[arg(short, long, global = true)]
-    pub config: Option<String>,
+    pub config: Vec<String>,
+
+    /// Expected sha256 of the config fetched from the `-c` URL (same as `<url>#sha256=<hex>`)
+    #[arg(long, global = true, value_name = "HEX")]
+    pub config_sha256: Option<String>,
 
     /// Run only a specific task by name
     #[arg(short, long, global = true)]
@@ -27,10 +32,18 @@ pub struct Cli {
     #[arg(long, global = true)]
     pub with_deps: bool,
 
+    /// Run remote configs that changed since their last run without asking
+    #[arg(short = 'y', long, global = true)]
+    pub yes: bool,
+
     /// Force execution (bypass history checks)
     #[arg(short, long, global = true)]
     pub force: bool,
 
+    /// Wait for another run on the same temp_dir to finish instead of exiting
+    #[arg(long, global = true)]
+    pub wait: bool,
+
     /// Disable TUI (plain log output)
     #[arg(long, global = true)]
     pub no_tui: bool,
@@ -71,6 +84,34 @@ pub enum Command {
         #[command(subcommand)]
         target: AddTarget,
     },
+    /// Move a file into the source tree of a `symlink` entry and link it back
+    Adopt {
+        /// File to adopt (e.g. `~/.config/starship.toml`)
+        file: String,
+        /// Symlink source directory to move it into
+        #[arg(long)]
+        into: String,
+        /// Target of a new `symlink` Task, when no entry maps `--into` above the file
+        #[arg(long, default_value = "~")]
+        target: String,
+        /// Name of that new Task
+        #[arg(long, default_value = "adopted")]
+        name: String,
+    },
+    /// Append Tasks describing this machine (dotfile links, git checkouts, packages)
+    Import {
+        /// Dotfiles repo: symlinks in the home directory that point into it
+        #[arg(long)]
+        repo: Option<String>,
+        /// Directory to search for git checkouts (repeatable)
+        #[arg(long = "git", value_name = "DIR")]
+        git_dirs: Vec<String>,
+        /// Also record packages installed on request with brew / apt
+        #[arg(long)]
+        packages: bool,
+    },
+    /// List files that `symlink` and `copy` entries with `backup` moved aside
+    Backups,
     /// Manage OS-timer auto-update schedules
     Schedule {
         #[command(subcommand)]
@@ -185,6 +226,9 @@ impl std::fmt::Display for Command {
             Command::Init => write!(f, "init"),
             Command::Wizard => write!(f, "wizard"),
             Command::Add { .. } => write!(f, "add"),
+            Command::Adopt { .. } => write!(f, "adopt"),
+            Command::Import { .. } => write!(f, "import"),
+            Command::Backups => write!(f, "backups"),
             Command::Schedule { .. } => write!(f, "schedule"),
             Command::Schema => write!(f, "schema"),
             Command::Completions { .. } => write!(f, "completions"),
@@ -0,0 +1,302 @@
+//! `machine_setup adopt` — move a file from the machine into the source tree
+//! of a `symlink` entry and link it back.
+//!
+//! The file lands where Tree materialization would map it from: for an entry
+//! with source directory `src` and `target` `T`, the file `T/rel` moves to
+//! `src/rel`, so the existing entry places it on other machines too. Only when
+//! no `symlink` entry maps `--into` onto a directory above the file is a new
+//! Task appended (append-only, ADR-0008).
+
+use std::path::{Path, PathBuf};
+
+use crate::engine::commands::fs_ops::{DirectFs, FileOps};
+use crate::engine::commands::symlink::link_value;
+use crate::error::{Error, Result};
+use crate::utils::ignore_rules::IgnoreRules;
+use crate::utils::path::expand_path;
+
+use super::document;
+use super::recipes::{self, AdoptParams};
+use super::types::{AppConfig, CommandEntry, SymlinkArgs};
+
+/// What the user asked for, paths as typed.
+#[derive(Debug, Clone)]
+pub struct AdoptRequest<'a> {
+    /// The file to adopt (relative to `cwd`).
+    pub file: &'a str,
+    /// Symlink source directory to move it into (relative to `cwd`).
+    pub into: &'a str,
+    /// `target` of an appended Task (relative to the Config document).
+    pub target: &'a str,
+    /// Name of an appended Task.
+    pub name: &'a str,
+}
+
+/// Where the file went.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Adopted {
+    /// The file's new home inside the source tree.
+    pub stored: PathBuf,
+    /// The link left at the old location.
+    pub link: PathBuf,
+    /// Task whose `symlink` entry maps the file.
+    pub task: String,
+    /// Whether that Task was appended to the Config document.
+    pub appended: bool,
+}
+
+/// A `symlink` entry that would place `stored` at the file's location.
+struct Placement {
+    task: String,
+    stored: PathBuf,
+    relative: bool,
+    /// `None` when an existing entry already maps the file.
+    append: Option<recipes::EmittedTask>,
+}
+
+/// Adopt `request.file` into the Config document at `path`: append a Task if
+/// needed, move the file, and link it back.
+pub fn adopt(path: &Path, request: &AdoptRequest<'_>, cwd: &Path) -> Result<Adopted> {
+    let config = document::load_after_write(path)?;
+    let config_dir = path
+        .canonicalize()?
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| cwd.to_path_buf());
+    let file = expand_path(request.file, Some(cwd));
+    let into = canonical(&expand_path(request.into, Some(cwd)))?;
+    check_file(&file, &into)?;
+
+    let placement = place(&config, &config_dir, &file, &into, request)?;
+    if placement.stored.symlink_metadata().is_ok() {
+        return Err(Error::PathError(format!(
+            "{} already exists in the source tree",
+            placement.stored.display()
+        )));
+    }
+    let appended = placement.append.is_some();
+    if let Some(emitted) = &placement.append {
+        document::append_emitted(path, emitted)?;
+    }
+
+    move_file(&file, &placement.stored)?;
+    DirectFs.create_symlink(
+        &link_value(&placement.stored, &file, placement.relative),
+        &file,
+    )?;
+    Ok(Adopted {
+        stored: placement.stored,
+        link: file,
+        task: placement.task,
+        appended,
+    })
+}
+
+/// Only plain files outside the source tree can be adopted.
+fn check_file(file: &Path, into: &Path) -> Result<()> {
+    let meta = file
+        .symlink_metadata()
+        .map_err(|_| Error::PathError(format!("{} does not exist", file.display())))?;
+    if meta.file_type().is_symlink() {
+        return Err(Error::PathError(format!(
+            "{} is already a symlink",
+            file.display()
+        )));
+    }
+    if !meta.is_file() {
+        return Err(Error::PathError(format!(
+            "{} is not a file; adopt the files inside it one by one",
+            file.display()
+        )));
+    }
+    if canonical(file)?.starts_with(into) {
+        return Err(Error::PathError(format!(
+            "{} is already inside {}",
+            file.display(),
+            into.display()
+        )));
+    }
+    Ok(())
+}
+
+/// The first `symlink` entry with source `into` whose target is above `file`,
+/// or a new Task mapping `into` onto `request.target`.
+fn place(
+    config: &AppConfig,
+    config_dir: &Path,
+    file: &Path,
+    into: &Path,
+    request: &AdoptRequest<'_>,
+) -> Result<Placement> {
+    let file = canonical(file)?;
+    for (task, task_config) in &config.tasks {
+        for entry in &task_config.commands {
+            let CommandEntry::Symlink(args) = entry else {
+                continue;
+            };
+            let src = expand_path(&args.src, Some(config_dir));
+            if src.canonicalize().ok().as_deref() != Some(into) {
+                continue;
+            }
+            let Ok(target) = canonical(&expand_path(&args.target, Some(config_dir))) else {
+                continue;
+            };
+            if let Ok(rel) = file.strip_prefix(&target) {
+                check_not_ignored(task, args, into, rel)?;
+                return Ok(Placement {
+                    task: task.clone(),
+                    stored: into.join(rel),
+                    relative: args.relative,
+                    append: None,
+                });
+            }
+        }
+    }
+
+    let target = canonical(&expand_path(request.target, Some(config_dir)))?;
+    let rel = file.strip_prefix(&target).map_err(|_| {
+        Error::PathError(format!(
+            "{} is not below target {}; pass --target",
+            file.display(),
+            request.target
+        ))
+    })?;
+    let src = document::path_for_document(into, config_dir, dirs::home_dir().as_deref());
+    let emitted = recipes::emit_adopt(&AdoptParams {
+        name: request.name,
+        src: &src,
+        target: request.target,
+    })?;
+    Ok(Placement {
+        task: request.name.to_string(),
+        stored: into.join(rel),
+        relative: false,
+        append: Some(emitted),
+    })
+}
+
+/// An ignored file would be moved out of reach of the entry that should link it.
+fn check_not_ignored(task: &str, args: &SymlinkArgs, into: &Path, rel: &Path) -> Result<()> {
+    let rules = IgnoreRules::load(into, &args.ignore)?;
+    let mut path = PathBuf::new();
+    let parts: Vec<_> = rel.components().collect();
+    for (i, part) in parts.iter().enumerate() {
+        path.push(part);
+        if rules.is_ignored(&path, i + 1 < parts.len()) {
+            return Err(Error::PathError(format!(
+                "{} is ignored by the symlink entry of task `{task}`",
+                rel.display()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// `path` canonicalized, with a readable error.
+fn canonical(path: &Path) -> Result<PathBuf> {
+    path.canonicalize()
+        .map_err(|e| Error::PathError(format!("{}: {e}", path.display())))
+}
+
+/// Rename, or copy and delete when `to` is on another filesystem.
+fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    if std::fs::rename(from, to).is_err() {
+        std::fs::copy(from, to)?;
+        std::fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn setup(tasks: &str) -> (tempfile::TempDir, PathBuf) {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("repo/home")).unwrap();
+        std::fs::create_dir_all(dir.path().join("machine/.config")).unwrap();
+        let path = dir.path().join("repo/machine_setup.yaml");
+        std::fs::write(&path, format!("tasks:\n{tasks}")).unwrap();
+        (dir, path)
+    }
+
+    fn request<'a>(file: &'a str) -> AdoptRequest<'a> {
+        AdoptRequest {
+            file,
+            into: "repo/home",
+            target: "../machine",
+            name: recipes::DEFAULT_ADOPT_NAME,
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn moves_file_under_existing_entry_and_links_it_back() {
+        let (dir, path) = setup(
+            "  dotfiles:\n    commands:\n      - symlink:\n          src: ./home\n          target: ../machine\n",
+        );
+        let file = dir.path().join("machine/.config/starship.toml");
+        std::fs::write(&file, "format = 1\n").unwrap();
+        let before = std::fs::read_to_string(&path).unwrap();
+
+        let adopted = adopt(&path, &request("machine/.config/starship.toml"), dir.path()).unwrap();
+        assert_eq!(adopted.task, "dotfiles");
+        assert!(!adopted.appended);
+        let stored = dir.path().join("repo/home/.config/starship.toml");
+        assert_eq!(std::fs::read_to_string(&stored).unwrap(), "format = 1\n");
+        assert_eq!(
+            std::fs::read_link(&file).unwrap().canonicalize().unwrap(),
+            stored.canonicalize().unwrap()
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), before);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn appends_a_task_when_no_entry_maps_the_file() {
+        let (dir, path) = setup("  other:\n    commands: []\n");
+        std::fs::write(dir.path().join("machine/.vimrc"), "set nu\n").unwrap();
+
+        let adopted = adopt(&path, &request("machine/.vimrc"), dir.path()).unwrap();
+        assert!(adopted.appended);
+        let config = document::load_after_write(&path).unwrap();
+        let CommandEntry::Symlink(args) = &config.tasks["adopted"].commands[0] else {
+            panic!("expected a symlink entry");
+        };
+        assert_eq!(args.src, "./home");
+        assert_eq!(args.target, "../machine");
+        assert!(dir.path().join("repo/home/.vimrc").is_file());
+    }
+
+    #[test]
+    fn refuses_a_file_the_entry_ignores() {
+        let (dir, path) = setup(
+            "  dotfiles:\n    commands:\n      - symlink:\n          src: ./home\n          target: ../machine\n          ignore: [\".config\"]\n",
+        );
+        let file = dir.path().join("machine/.config/starship.toml");
+        std::fs::write(&file, "x").unwrap();
+
+        let err = adopt(&path, &request("machine/.config/starship.toml"), dir.path());
+        assert!(err.unwrap_err().to_string().contains("ignored"));
+        assert!(file.is_file());
+    }
+
+    #[test]
+    fn refuses_to_overwrite_a_source_file() {
+        let (dir, path) = setup(
+            "  dotfiles:\n    commands:\n      - symlink:\n          src: ./home\n          target: ../machine\n",
+        );
+        std::fs::write(dir.path().join("machine/.vimrc"), "new").unwrap();
+        std::fs::write(dir.path().join("repo/home/.vimrc"), "old").unwrap();
+
+        assert!(adopt(&path, &request("machine/.vimrc"), dir.path()).is_err());
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("repo/home/.vimrc")).unwrap(),
+            "old"
+        );
+    }
+}
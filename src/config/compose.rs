@@ -0,0 +1,412 @@
+//! Load-time composition of Config documents — `extends` and `include`
+//! (ADR-0011).
+//!
+//! Both build one [`AppConfig`] — one run, one History — before it is
+//! deserialized, working on the YAML values:
+//!
+//! - `extends: ./base.yaml` starts from the base document. Top-level settings
+//!   written here replace the base's. A Task named like a base Task overrides
+//!   it field by field: each field it writes (`commands`, `depends_on`, …)
+//!   replaces the base's value, the others are inherited.
+//! - `include: [./a.yaml]` adds the Tasks of other documents as they are.
+//!   Their top-level settings are ignored, and a Task name defined twice —
+//!   by two includes, or by an include and the extended base or this
+//!   document — is an error.
+//!
+//! Composed Tasks keep their provenance in [`AppConfig::origins`]: relative
+//! paths resolve against the document that wrote the Task's `commands`, and
+//! `validate` names that document.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use indexmap::IndexMap;
+use serde_yaml::{Mapping, Value};
+
+use crate::error::{Error, Result};
+use crate::utils::path::expand_path;
+
+use super::types::{AppConfig, TaskOrigin, Via};
+
+pub const EXTENDS: &str = "extends";
+pub const INCLUDE: &str = "include";
+const TASKS: &str = "tasks";
+
+/// A document with its `extends` / `include` applied.
+struct Composed {
+    /// Top-level settings, without `tasks`.
+    settings: Mapping,
+    tasks: Mapping,
+    /// Where each Task's `commands` was written; `via` is `None` for Tasks
+    /// of the composed document itself.
+    sources: IndexMap<String, (PathBuf, Option<Via>)>,
+}
+
+/// Load the local document at `path`, composing `extends` / `include`.
+pub fn load(path: &Path) -> Result<AppConfig> {
+    let content = std::fs::read_to_string(path)?;
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let value = parse_value(&content, ext)?;
+    if !composes(&value) {
+        return super::parse_config(&content, ext);
+    }
+
+    let composed = compose(&path.canonicalize()?, value, &mut Vec::new())?;
+    let mut root = composed.settings;
+    root.insert(TASKS.into(), Value::Mapping(composed.tasks));
+    let mut config: AppConfig = serde_yaml::from_value(Value::Mapping(root))?;
+    for (name, (document, via)) in composed.sources {
+        let (Some(via), Some(dir)) = (via, document.parent()) else {
+            continue;
+        };
+        config.origins.insert(
+            name,
+            TaskOrigin {
+                config_dir: dir.to_path_buf(),
+                document,
+                default_shell: config.default_shell.clone(),
+                via,
+            },
+        );
+    }
+    Ok(config)
+}
+
+/// Refuse `extends` / `include` where there is no local directory to resolve
+/// them against (a document fetched from a URL).
+pub fn reject_remote(content: &str, ext: &str) -> Result<()> {
+    if composes(&parse_value(content, ext)?) {
+        return Err(Error::Other(format!(
+            "`{EXTENDS}` / `{INCLUDE}` require a local Config document"
+        )));
+    }
+    Ok(())
+}
+
+fn composes(value: &Value) -> bool {
+    value
+        .as_mapping()
+        .is_some_and(|m| m.contains_key(EXTENDS) || m.contains_key(INCLUDE))
+}
+
+fn parse_value(content: &str, ext: &str) -> Result<Value> {
+    match ext {
+        "yaml" | "yml" => Ok(serde_yaml::from_str(content)?),
+        "json" => Ok(serde_json::from_str(content)?),
+        other => Err(Error::UnsupportedFormat(other.to_string())),
+    }
+}
+
+fn compose_file(document: &Path, stack: &mut Vec<PathBuf>) -> Result<Composed> {
+    let content = std::fs::read_to_string(document)?;
+    let ext = document.extension().and_then(|e| e.to_str()).unwrap_or("");
+    compose(document, parse_value(&content, ext)?, stack)
+}
+
+/// Compose the canonical `document` with contents `value`; `stack` holds
+/// the documents being composed, to catch cycles.
+fn compose(document: &Path, value: Value, stack: &mut Vec<PathBuf>) -> Result<Composed> {
+    if let Some(start) = stack.iter().position(|d| d == document) {
+        let chain: Vec<String> = stack[start..]
+            .iter()
+            .chain(std::iter::once(&document.to_path_buf()))
+            .map(|d| d.display().to_string())
+            .collect();
+        return Err(Error::CyclicInclude(chain.join(" -> ")));
+    }
+    let Value::Mapping(mut settings) = value else {
+        return Err(Error::Other(format!(
+            "{}: expected a mapping at the top level",
+            document.display()
+        )));
+    };
+    let dir = document.parent().unwrap_or(Path::new("."));
+    let extends = settings.remove(EXTENDS);
+    let include = settings.remove(INCLUDE);
+    let own_tasks = match settings.remove(TASKS) {
+        Some(Value::Mapping(tasks)) => tasks,
+        None | Some(Value::Null) => Mapping::new(),
+        Some(_) => {
+            return Err(Error::Other(format!(
+                "{}: `{TASKS}` must be a mapping",
+                document.display()
+            )))
+        }
+    };
+
+    stack.push(document.to_path_buf());
+    let mut composed = match extends {
+        Some(base) => {
+            let mut base = compose_file(&reference(&base, dir, EXTENDS)?, stack)?;
+            base.settings.extend(settings);
+            for (_, via) in base.sources.values_mut() {
+                via.get_or_insert(Via::Extends);
+            }
+            base
+        }
+        None => Composed {
+            settings,
+            tasks: Mapping::new(),
+            sources: IndexMap::new(),
+        },
+    };
+
+    let mut included = HashSet::new();
+    for path in references(include, dir)? {
+        let other = compose_file(&path, stack)?;
+        for (name, task) in other.tasks {
+            let key = task_name(&name);
+            let (source, via) = other.sources[&key].clone();
+            // A diamond (two includes sharing a document) brings the same
+            // Task twice; it is defined once.
+            let same = composed.tasks.get(&name) == Some(&task)
+                && composed
+                    .sources
+                    .get(&key)
+                    .is_some_and(|(first, _)| *first == source);
+            if same {
+                included.insert(key);
+                continue;
+            }
+            check_unique(&composed, &key, &source)?;
+            composed.tasks.insert(name, task);
+            composed
+                .sources
+                .insert(key.clone(), (source, via.or(Some(Via::Include))));
+            included.insert(key);
+        }
+    }
+    stack.pop();
+
+    for (name, task) in own_tasks {
+        let key = task_name(&name);
+        if included.contains(&key) {
+            check_unique(&composed, &key, document)?;
+        }
+        let base = composed.tasks.get(&name);
+        let (exists, overrides) = (base.is_some(), matches!(base, Some(Value::Mapping(_))));
+        match task {
+            Value::Mapping(fields) if overrides => {
+                if fields.contains_key("commands") {
+                    composed.sources.insert(key, (document.to_path_buf(), None));
+                }
+                if let Some(Value::Mapping(base)) = composed.tasks.get_mut(&name) {
+                    base.extend(fields);
+                }
+            }
+            // `name:` with nothing under it keeps the base Task as is.
+            Value::Null if exists => {}
+            task => {
+                composed.tasks.insert(name, task);
+                composed.sources.insert(key, (document.to_path_buf(), None));
+            }
+        }
+    }
+    Ok(composed)
+}
+
+/// Error when `name` is already defined.
+fn check_unique(composed: &Composed, name: &str, source: &Path) -> Result<()> {
+    match composed.sources.get(name) {
+        Some((first, _)) => Err(Error::TaskConflict(
+            name.to_string(),
+            first.display().to_string(),
+            source.display().to_string(),
+        )),
+        None => Ok(()),
+    }
+}
+
+fn task_name(key: &Value) -> String {
+    match key {
+        Value::String(name) => name.clone(),
+        other => serde_yaml::to_string(other)
+            .unwrap_or_default()
+            .trim()
+            .to_string(),
+    }
+}
+
+/// The canonical document a `key:` value names, relative to `dir`.
+fn reference(value: &Value, dir: &Path, key: &str) -> Result<PathBuf> {
+    let Some(raw) = value.as_str() else {
+        return Err(Error::Other(format!("`{key}` must be a path")));
+    };
+    if super::is_remote(raw) {
+        return Err(Error::Other(format!(
+            "`{key}: {raw}`: only local Config documents can be composed"
+        )));
+    }
+    Ok(super::resolve_config_path(&expand_path(raw, Some(dir)))?.canonicalize()?)
+}
+
+/// `include` as a path or a list of paths.
+fn references(include: Option<Value>, dir: &Path) -> Result<Vec<PathBuf>> {
+    match include {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(Value::Sequence(items)) => items
+            .iter()
+            .map(|item| reference(item, dir, INCLUDE))
+            .collect(),
+        Some(item) => Ok(vec![reference(&item, dir, INCLUDE)?]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::types::CommandEntry;
+    use tempfile::tempdir;
+
+    fn write(dir: &Path, name: &str, yaml: &str) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, yaml).unwrap();
+        path
+    }
+
+    const BASE: &str = r#"
+temp_dir: /tmp/base
+default_shell: zsh
+tasks:
+  git:
+    commands:
+      - run:
+          commands: "echo base"
+    retry: 2
+  editor:
+    depends_on: [git]
+    commands: []
+"#;
+
+    #[test]
+    fn extends_overrides_settings_and_task_fields() {
+        let dir = tempdir().unwrap();
+        write(dir.path(), "base.yaml", BASE);
+        let team = write(
+            dir.path(),
+            "team.yaml",
+            r#"
+extends: ./base.yaml
+temp_dir: /tmp/team
+tasks:
+  git:
+    commands:
+      - run:
+          commands: "echo team"
+  tools:
+    commands: []
+"#,
+        );
+
+        let config = load(&team).unwrap();
+        assert_eq!(config.temp_dir, "/tmp/team");
+        assert_eq!(config.default_shell, crate::config::types::Shell::Zsh);
+        let names: Vec<&str> = config.tasks.keys().map(String::as_str).collect();
+        assert_eq!(names, vec!["git", "editor", "tools"]);
+        let git = &config.tasks["git"];
+        assert_eq!(git.retry, 2);
+        let CommandEntry::Run(run) = &git.commands[0] else {
+            panic!("expected a run entry");
+        };
+        assert_eq!(run.commands.as_slice(), ["echo team"]);
+        // `git` now runs the team's commands; `editor` is still the base's.
+        assert!(!config.origins.contains_key("git"));
+        let origin = &config.origins["editor"];
+        assert_eq!(origin.via, Via::Extends);
+        assert!(origin.document.ends_with("base.yaml"));
+    }
+
+    #[test]
+    fn include_adds_tasks_and_rejects_duplicate_names() {
+        let dir = tempdir().unwrap();
+        write(dir.path(), "base.yaml", BASE);
+        write(
+            dir.path(),
+            "langs.yaml",
+            "default_shell: bash\ntasks:\n  rust:\n    commands: []\n",
+        );
+        let main = write(
+            dir.path(),
+            "main.yaml",
+            "include: [./base.yaml, ./langs.yaml]\ntasks:\n  app:\n    depends_on: [rust, git]\n    commands: []\n",
+        );
+
+        let config = load(&main).unwrap();
+        let names: Vec<&str> = config.tasks.keys().map(String::as_str).collect();
+        assert_eq!(names, vec!["git", "editor", "rust", "app"]);
+        assert_eq!(config.tasks["app"].depends_on, vec!["rust", "git"]);
+        assert_eq!(config.origins["rust"].via, Via::Include);
+        // Settings of included documents are ignored.
+        assert_eq!(config.default_shell, crate::config::types::Shell::Bash);
+
+        let clash = write(
+            dir.path(),
+            "clash.yaml",
+            "include: ./langs.yaml\ntasks:\n  rust:\n    commands: []\n",
+        );
+        let err = load(&clash).unwrap_err();
+        assert!(matches!(err, Error::TaskConflict(ref name, _, _) if name == "rust"));
+    }
+
+    #[test]
+    fn diamond_include_defines_shared_tasks_once() {
+        let dir = tempdir().unwrap();
+        write(
+            dir.path(),
+            "d.yaml",
+            "tasks:\n  shared:\n    commands: []\n",
+        );
+        write(
+            dir.path(),
+            "b.yaml",
+            "include: ./d.yaml\ntasks:\n  b:\n    commands: []\n",
+        );
+        write(
+            dir.path(),
+            "c.yaml",
+            "include: ./d.yaml\ntasks:\n  c:\n    commands: []\n",
+        );
+        let a = write(
+            dir.path(),
+            "a.yaml",
+            "include: [./b.yaml, ./c.yaml]\ntasks:\n  a:\n    depends_on: [shared]\n    commands: []\n",
+        );
+
+        let config = load(&a).unwrap();
+        let names: Vec<&str> = config.tasks.keys().map(String::as_str).collect();
+        assert_eq!(names, vec!["shared", "b", "c", "a"]);
+        assert!(config.origins["shared"].document.ends_with("d.yaml"));
+
+        // Defining the shared Task again is still a conflict.
+        let clash = write(
+            dir.path(),
+            "clash.yaml",
+            "include: [./b.yaml, ./c.yaml]\ntasks:\n  shared:\n    commands: []\n",
+        );
+        let err = load(&clash).unwrap_err();
+        assert!(matches!(err, Error::TaskConflict(ref name, _, _) if name == "shared"));
+    }
+
+    #[test]
+    fn extends_cycle_is_an_error() {
+        let dir = tempdir().unwrap();
+        write(dir.path(), "a.yaml", "extends: ./b.yaml\ntasks: {}\n");
+        let b = write(dir.path(), "b.yaml", "extends: ./a.yaml\ntasks: {}\n");
+
+        assert!(matches!(load(&b), Err(Error::CyclicInclude(_))));
+    }
+
+    #[test]
+    fn document_without_composition_parses_as_before() {
+        let dir = tempdir().unwrap();
+        let path = write(
+            dir.path(),
+            "plain.json",
+            r#"{"tasks": {"a": {"commands": []}}}"#,
+        );
+
+        let config = load(&path).unwrap();
+        assert_eq!(config.tasks.len(), 1);
+        assert!(config.origins.is_empty());
+    }
+}
@@ -156,6 +156,20 @@ fn open_empty_tasks_map(content: &str) -> Option<String> {
     None
 }
 
+/// How an authoring verb writes a machine path into the Config document:
+/// `./rel` below `config_dir` (so the document moves with its repo), `~/rel`
+/// below `home`, absolute otherwise.
+pub fn path_for_document(path: &Path, config_dir: &Path, home: Option<&Path>) -> String {
+    if let Ok(rel) = path.strip_prefix(config_dir) {
+        return format!("./{}", rel.display());
+    }
+    match home.and_then(|home| path.strip_prefix(home).ok()) {
+        Some(rel) if rel.as_os_str().is_empty() => "~".to_string(),
+        Some(rel) => format!("~/{}", rel.display()),
+        None => path.display().to_string(),
+    }
+}
+
 /// Re-parse helper used by tests / callers that need the loaded config after write.
 pub fn load_after_write(path: &Path) -> Result<AppConfig> {
     load_config(
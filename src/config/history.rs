@@ -1,43 +1,169 @@
+//! History — which Tasks are installed, per Config document.
+//!
+//! `history.json` in `temp_dir` holds one map of Task entries per document,
+//! keyed by the document's identity: its `id:` root field, else its canonical
+//! path or URL. A run loads the entries of its own documents (see
+//! [`HistoryScope`]) and saves only those back, so two documents with a Task
+//! named `tools` keep separate state in one `temp_dir`.
+//!
+//! A file from before documents were tracked is a flat map of Task names. Its
+//! entries stay `unassigned` until a document with a Task of the same name
+//! loads them, which moves them into that document's map on the next save.
+//!
+//! A save re-reads the file under a lock and writes back only the entries
+//! this History changed since it was loaded, so two processes that load,
+//! modify and save concurrently keep each other's changes.
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 
+use super::types::{AppConfig, HistoryScope};
 use crate::error::{Error, Result};
+use crate::utils::lock::{write_atomic, FileLock};
+
+const VERSION: u32 = 2;
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct TaskHistory {
     pub installed_at: Option<DateTime<Utc>>,
     pub updated_at: Option<DateTime<Utc>>,
     pub uninstalled_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+type Entries = BTreeMap<String, TaskHistory>;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct HistoryFile {
+    version: u32,
+    #[serde(default)]
+    documents: BTreeMap<String, Entries>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    unassigned: Entries,
+}
+
+/// The History of one loaded config.
+#[derive(Debug, Clone, Default)]
 pub struct History {
-    #[serde(flatten)]
+    /// Entries of the config's documents, by Task name as the config names
+    /// it (including entries of Tasks no longer defined there).
     pub tasks: HashMap<String, TaskHistory>,
+    /// Entries of other documents, by document.
+    pub others: BTreeMap<String, Entries>,
+    /// Entries of a file from before documents were tracked that no document
+    /// claimed yet.
+    pub unassigned: Entries,
+    scopes: Vec<HistoryScope>,
+    /// Unassigned entries this config's Tasks claimed.
+    claimed: Vec<String>,
+    /// `tasks` as loaded from the documents, to tell what changed since.
+    loaded: HashMap<String, TaskHistory>,
 }
 
 impl History {
-    /// Load history from the given directory. Returns empty history if file doesn't exist.
+    /// Load history from the given directory for a config without a
+    /// document. Returns empty history if file doesn't exist.
     pub fn load(history_dir: &Path) -> Result<Self> {
-        let path = Self::file_path(history_dir);
-        if !path.exists() {
-            return Ok(Self::default());
+        Self::load_scoped(history_dir, Vec::new(), None)
+    }
+
+    /// Load the entries of `config`'s documents from the given directory.
+    pub fn load_for(history_dir: &Path, config: &AppConfig) -> Result<Self> {
+        let names: Vec<&str> = config.tasks.keys().map(String::as_str).collect();
+        Self::load_scoped(history_dir, config.history_scopes.clone(), Some(&names))
+    }
+
+    /// `claim`: Task names that take over unassigned entries (`None`: all).
+    fn load_scoped(
+        history_dir: &Path,
+        scopes: Vec<HistoryScope>,
+        claim: Option<&[&str]>,
+    ) -> Result<Self> {
+        let mut file = read_file(&Self::file_path(history_dir))?;
+        let mut history = Self {
+            scopes,
+            ..Self::default()
+        };
+        for scope in history.scopes() {
+            for (name, entry) in file.documents.remove(&scope.document).unwrap_or_default() {
+                history
+                    .tasks
+                    .insert(format!("{}{name}", scope.prefix), entry);
+            }
         }
-        let content = std::fs::read_to_string(&path)?;
-        serde_json::from_str(&content).map_err(|e| Error::HistoryError(e.to_string()))
+        let claimable: Vec<String> = match claim {
+            Some(names) => names.iter().map(|name| name.to_string()).collect(),
+            None => file
+                .unassigned
+                .keys()
+                .map(|name| format!("{}{name}", history.scopes()[0].prefix))
+                .collect(),
+        };
+        for name in claimable {
+            if history.tasks.contains_key(&name) {
+                continue;
+            }
+            let (_, inner) = history.scope_of(&name);
+            if let Some(entry) = file.unassigned.remove(&inner) {
+                history.claimed.push(inner);
+                history.tasks.insert(name, entry);
+            }
+        }
+        history.loaded = history
+            .tasks
+            .iter()
+            .filter(|(name, _)| {
+                let (_, inner) = history.scope_of(name);
+                !history.claimed.contains(&inner)
+            })
+            .map(|(name, entry)| (name.clone(), entry.clone()))
+            .collect();
+        history.others = file.documents;
+        history.unassigned = file.unassigned;
+        Ok(history)
     }
 
-    /// Save history to the given directory.
+    /// Save the entries this History changed since it was loaded (set,
+    /// updated or removed) to the given directory. Everything else — other
+    /// documents, and entries of these documents another process saved in
+    /// the meantime — stays as it is on disk now: the file is re-read and
+    /// replaced under a lock.
     pub fn save(&self, history_dir: &Path) -> Result<()> {
         let path = Self::file_path(history_dir);
-        if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent)?;
+        std::fs::create_dir_all(history_dir)?;
+        let _lock = FileLock::exclusive(&history_dir.join("history.json.lock"))?;
+        let mut file = read_file(&path)?;
+        for (name, entry) in &self.tasks {
+            if self.loaded.get(name) == Some(entry) {
+                continue;
+            }
+            let (document, inner) = self.scope_of(name);
+            file.documents
+                .entry(document)
+                .or_default()
+                .insert(inner, entry.clone());
+        }
+        for name in self.loaded.keys() {
+            if self.tasks.contains_key(name) {
+                continue;
+            }
+            let (document, inner) = self.scope_of(name);
+            if let Some(entries) = file.documents.get_mut(&document) {
+                entries.remove(&inner);
+                if entries.is_empty() {
+                    file.documents.remove(&document);
+                }
+            }
         }
+        for name in &self.claimed {
+            file.unassigned.remove(name);
+        }
+        file.version = VERSION;
+
         let content =
-            serde_json::to_string(&self).map_err(|e| Error::HistoryError(e.to_string()))?;
-        std::fs::write(&path, content)?;
+            serde_json::to_string(&file).map_err(|e| Error::HistoryError(e.to_string()))?;
+        write_atomic(&path, content.as_bytes())?;
         Ok(())
     }
 
@@ -68,11 +194,56 @@ impl History {
             .is_some_and(|h| h.installed_at.is_some())
     }
 
+    fn scopes(&self) -> Vec<HistoryScope> {
+        if self.scopes.is_empty() {
+            return vec![HistoryScope {
+                prefix: String::new(),
+                document: String::new(),
+            }];
+        }
+        self.scopes.clone()
+    }
+
+    /// `(document, name in the document)` of Task `name`: the scope with the
+    /// longest matching prefix.
+    pub fn scope_of(&self, name: &str) -> (String, String) {
+        let scopes = self.scopes();
+        let scope = scopes
+            .iter()
+            .filter(|scope| name.starts_with(&scope.prefix))
+            .max_by_key(|scope| scope.prefix.len())
+            .unwrap_or(&scopes[0]);
+        let inner = name.strip_prefix(&scope.prefix).unwrap_or(name);
+        (scope.document.clone(), inner.to_string())
+    }
+
     fn file_path(dir: &Path) -> PathBuf {
         dir.join("history.json")
     }
 }
 
+/// The file at `path`; a flat file from before documents were tracked has
+/// all its entries unassigned. A file that does not parse is an error, never
+/// an empty History a save would write over it.
+fn read_file(path: &Path) -> Result<HistoryFile> {
+    if !path.exists() {
+        return Ok(HistoryFile::default());
+    }
+    let content = std::fs::read_to_string(path)?;
+    let invalid = |e: serde_json::Error| Error::HistoryError(format!("{}: {e}", path.display()));
+    let value: serde_json::Value = serde_json::from_str(&content).map_err(invalid)?;
+    let versioned = value.get("version").is_some_and(serde_json::Value::is_u64);
+    let file = if versioned {
+        serde_json::from_value(value)
+    } else {
+        serde_json::from_value(value).map(|unassigned| HistoryFile {
+            unassigned,
+            ..HistoryFile::default()
+        })
+    };
+    file.map_err(invalid)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -138,6 +309,116 @@ mod tests {
         assert!(loaded.is_installed("task_a"));
     }
 
+    /// A config of document `document` with Tasks `names`, prefixed by `prefix`.
+    fn config(document: &str, prefix: &str, names: &[&str]) -> AppConfig {
+        let tasks: Vec<String> = names
+            .iter()
+            .map(|name| format!("{prefix}{name}: {{commands: []}}"))
+            .collect();
+        let mut config: AppConfig =
+            serde_yaml::from_str(&format!("tasks: {{{}}}", tasks.join(", "))).unwrap();
+        config.history_scopes = vec![HistoryScope {
+            prefix: prefix.into(),
+            document: document.into(),
+        }];
+        config
+    }
+
+    #[test]
+    fn documents_keep_separate_entries_for_the_same_task_name() {
+        let dir = tempdir().unwrap();
+        let personal = config("/home/me/personal.yaml", "", &["tools"]);
+        let work = config("/home/me/work.yaml", "", &["tools"]);
+
+        let mut history = History::load_for(dir.path(), &personal).unwrap();
+        history.mark_installed("tools");
+        history.save(dir.path()).unwrap();
+
+        let mut history = History::load_for(dir.path(), &work).unwrap();
+        assert!(!history.is_installed("tools"));
+        assert!(history.others["/home/me/personal.yaml"].contains_key("tools"));
+        history.mark_uninstalled("tools");
+        history.save(dir.path()).unwrap();
+
+        assert!(History::load_for(dir.path(), &personal)
+            .unwrap()
+            .is_installed("tools"));
+        // A workspace member reads the same entries under its namespace.
+        let member = config("/home/me/personal.yaml", "personal/", &["tools"]);
+        let history = History::load_for(dir.path(), &member).unwrap();
+        assert!(history.is_installed("personal/tools"));
+    }
+
+    #[test]
+    fn flat_history_is_claimed_by_the_documents_that_define_its_tasks() {
+        let dir = tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("history.json"),
+            r#"{"tools":{"installed_at":"2020-01-01T00:00:00Z","updated_at":null,"uninstalled_at":null},
+                "fonts":{"installed_at":"2020-01-01T00:00:00Z","updated_at":null,"uninstalled_at":null}}"#,
+        )
+        .unwrap();
+
+        let work = config("/home/me/work.yaml", "", &["tools"]);
+        let history = History::load_for(dir.path(), &work).unwrap();
+        assert!(history.is_installed("tools"));
+        assert!(!history.tasks.contains_key("fonts"));
+        assert!(history.unassigned.contains_key("fonts"));
+        history.save(dir.path()).unwrap();
+
+        let raw = std::fs::read_to_string(dir.path().join("history.json")).unwrap();
+        let file: serde_json::Value = serde_json::from_str(&raw).unwrap();
+        assert_eq!(file["version"], VERSION);
+        assert!(file["documents"]["/home/me/work.yaml"]["tools"].is_object());
+        assert!(file["unassigned"]["fonts"].is_object());
+        assert!(file["unassigned"].get("tools").is_none());
+    }
+
+    #[test]
+    fn concurrent_saves_keep_every_document() {
+        let dir = tempdir().unwrap();
+        let saves: Vec<_> = (0..8)
+            .map(|n| {
+                let dir = dir.path().to_path_buf();
+                std::thread::spawn(move || {
+                    let document = config(&format!("/doc{n}.yaml"), "", &["tools"]);
+                    let mut history = History::load_for(&dir, &document).unwrap();
+                    history.mark_installed("tools");
+                    history.save(&dir).unwrap();
+                })
+            })
+            .collect();
+        for save in saves {
+            save.join().unwrap();
+        }
+
+        let history = History::load(dir.path()).unwrap();
+        assert_eq!(history.others.len(), 8);
+    }
+
+    #[test]
+    fn interleaved_saves_of_one_document_keep_both_changes() {
+        let dir = tempdir().unwrap();
+        let document = config("/doc.yaml", "", &["a", "b", "old"]);
+        let mut seed = History::load_for(dir.path(), &document).unwrap();
+        seed.mark_installed("old");
+        seed.save(dir.path()).unwrap();
+
+        // Both load before either saves.
+        let mut first = History::load_for(dir.path(), &document).unwrap();
+        let mut second = History::load_for(dir.path(), &document).unwrap();
+        first.mark_installed("a");
+        second.mark_installed("b");
+        second.tasks.remove("old");
+        first.save(dir.path()).unwrap();
+        second.save(dir.path()).unwrap();
+
+        let history = History::load_for(dir.path(), &document).unwrap();
+        assert!(history.is_installed("a"));
+        assert!(history.is_installed("b"));
+        assert!(!history.tasks.contains_key("old"));
+    }
+
     #[test]
     fn test_install_clears_uninstalled() {
         let mut history = History::default();
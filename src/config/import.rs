@@ -0,0 +1,362 @@
+//! `machine_setup import` — describe what is already on this machine as Tasks.
+//!
+//! Scans for symlinks in the home directory that point into a dotfiles repo,
+//! git checkouts (with their `origin`) below chosen directories, and
+//! optionally packages installed on request with brew or apt. Each finding
+//! kind becomes one Task of existing Command entry kinds (`symlink`, `clone`,
+//! `package`), appended via the Config document module (ADR-0006, ADR-0008).
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::error::{Error, Result};
+use crate::utils::path::expand_path;
+
+use super::document;
+use super::recipes::{self, EmittedTask};
+
+/// How deep below the home directory to look for symlinks.
+const HOME_SCAN_DEPTH: usize = 4;
+/// How deep below each `--git` directory to look for checkouts.
+const GIT_SCAN_DEPTH: usize = 3;
+
+/// What to scan, paths as typed (relative to `cwd`).
+#[derive(Debug, Clone, Default)]
+pub struct ImportRequest<'a> {
+    /// Dotfiles repo whose links in the home directory become `symlink` entries.
+    pub repo: Option<&'a str>,
+    /// Directories to search for git checkouts.
+    pub git_dirs: &'a [String],
+    /// Record packages installed on request with brew / apt.
+    pub packages: bool,
+}
+
+/// Scan the machine and emit one Task per kind of finding, with paths below
+/// `config_dir` written relative to it.
+pub fn scan(
+    request: &ImportRequest<'_>,
+    config_dir: &Path,
+    cwd: &Path,
+) -> Result<Vec<EmittedTask>> {
+    let home = dirs::home_dir()
+        .ok_or_else(|| Error::PathError("cannot determine the home directory".into()))?;
+
+    let mut emitted = Vec::new();
+    if let Some(repo) = request.repo {
+        let repo = expand_path(repo, Some(cwd));
+        let links = scan_links(&home, &repo)?;
+        if !links.is_empty() {
+            emitted.push(emit_links(&links, &home, config_dir)?);
+        }
+    }
+    let dirs: Vec<PathBuf> = request
+        .git_dirs
+        .iter()
+        .map(|dir| expand_path(dir, Some(cwd)))
+        .collect();
+    let repos: Vec<(String, String)> = scan_git(&dirs)
+        .into_iter()
+        .map(|(dir, url)| {
+            (
+                url,
+                document::path_for_document(&dir, config_dir, Some(&home)),
+            )
+        })
+        .collect();
+    if !repos.is_empty() {
+        emitted.push(recipes::emit_clones(
+            recipes::IMPORT_GIT_REPOS_NAME,
+            &repos,
+        )?);
+    }
+    if request.packages {
+        let families = installed_packages();
+        if !families.is_empty() {
+            emitted.push(recipes::emit_packages(
+                recipes::IMPORT_PACKAGES_NAME,
+                &families,
+            )?);
+        }
+    }
+    Ok(emitted)
+}
+
+/// Append `tasks` to the Config document at `path`. Checks every Task name
+/// first, so a clash writes nothing.
+pub fn append(path: &Path, tasks: &[EmittedTask]) -> Result<()> {
+    let config = document::load_after_write(path)?;
+    if let Some(task) = tasks.iter().find(|t| config.tasks.contains_key(&t.name)) {
+        return Err(Error::TaskAlreadyExists(task.name.clone()));
+    }
+    for task in tasks {
+        document::append_emitted(path, task)?;
+    }
+    Ok(())
+}
+
+/// Symlinks below `home` (not inside `repo`) that resolve into `repo`, as
+/// canonical `(link, source)` pairs sorted by link. Does not follow directory
+/// links.
+pub fn scan_links(home: &Path, repo: &Path) -> Result<Vec<(PathBuf, PathBuf)>> {
+    let repo = canonical(repo)?;
+    let home = canonical(home)?;
+    let mut links = Vec::new();
+    let walker = walkdir::WalkDir::new(&home)
+        .min_depth(1)
+        .max_depth(HOME_SCAN_DEPTH)
+        .into_iter()
+        .filter_entry(|entry| entry.path() != repo);
+    for entry in walker.filter_map(|entry| entry.ok()) {
+        if !entry.path_is_symlink() {
+            continue;
+        }
+        if let Ok(source) = entry.path().canonicalize() {
+            if source.starts_with(&repo) {
+                links.push((entry.path().to_path_buf(), source));
+            }
+        }
+    }
+    links.sort();
+    Ok(links)
+}
+
+/// Turn links into `symlink` entries. A link whose source ends in the same
+/// home-relative path (`~/.config/x` → `repo/home/.config/x`) folds into one
+/// directory entry per source root (`repo/home` → `~`); others stay single-file
+/// entries.
+fn emit_links(links: &[(PathBuf, PathBuf)], home: &Path, config_dir: &Path) -> Result<EmittedTask> {
+    let home = canonical(home)?;
+    let config_dir = &config_dir
+        .canonicalize()
+        .unwrap_or_else(|_| config_dir.to_path_buf());
+    let mut roots = BTreeSet::new();
+    let mut singles = Vec::new();
+    for (link, source) in links {
+        let rel = link.strip_prefix(&home).unwrap_or(link);
+        match source_root(source, rel) {
+            Some(root) => {
+                roots.insert(root);
+            }
+            None => singles.push((source.clone(), link.clone())),
+        }
+    }
+    let mut pairs: Vec<(String, String)> = roots
+        .iter()
+        .map(|root| {
+            (
+                document::path_for_document(root, config_dir, Some(&home)),
+                "~".to_string(),
+            )
+        })
+        .collect();
+    pairs.extend(singles.iter().map(|(source, link)| {
+        (
+            document::path_for_document(source, config_dir, Some(&home)),
+            document::path_for_document(link, config_dir, Some(&home)),
+        )
+    }));
+    recipes::emit_symlinks(recipes::IMPORT_DOTFILES_NAME, &pairs)
+}
+
+/// `source` minus the trailing `rel`, when it ends with it.
+fn source_root(source: &Path, rel: &Path) -> Option<PathBuf> {
+    let mut root = source;
+    for part in rel.components().rev() {
+        if root.file_name() != Some(part.as_os_str()) {
+            return None;
+        }
+        root = root.parent()?;
+    }
+    Some(root.to_path_buf())
+}
+
+fn canonical(path: &Path) -> Result<PathBuf> {
+    path.canonicalize()
+        .map_err(|e| Error::PathError(format!("{}: {e}", path.display())))
+}
+
+/// Git checkouts below `dirs` with an `origin` (or first) remote, as
+/// `(checkout, url)` pairs. Nested checkouts of a found one are not searched.
+pub fn scan_git(dirs: &[PathBuf]) -> Vec<(PathBuf, String)> {
+    let mut found = Vec::new();
+    for dir in dirs {
+        let mut walker = walkdir::WalkDir::new(dir)
+            .max_depth(GIT_SCAN_DEPTH)
+            .sort_by_file_name()
+            .into_iter();
+        while let Some(entry) = walker.next() {
+            let Ok(entry) = entry else {
+                continue;
+            };
+            if !entry.file_type().is_dir() || !entry.path().join(".git").exists() {
+                continue;
+            }
+            walker.skip_current_dir();
+            if let Some(url) = remote_url(entry.path()) {
+                found.push((entry.path().to_path_buf(), url));
+            }
+        }
+    }
+    found
+}
+
+fn remote_url(checkout: &Path) -> Option<String> {
+    let remotes = git(checkout, &["remote"])?;
+    let remote = remotes
+        .lines()
+        .find(|r| *r == "origin")
+        .or_else(|| remotes.lines().next())?
+        .to_string();
+    git(checkout, &["remote", "get-url", &remote])
+}
+
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// Packages installed on request, per manager family found on `PATH`.
+fn installed_packages() -> Vec<(&'static str, Vec<String>)> {
+    [
+        ("apt", "apt-mark", &["showmanual"][..]),
+        ("brew", "brew", &["leaves", "--installed-on-request"][..]),
+    ]
+    .into_iter()
+    .filter_map(|(family, program, args)| {
+        let output = Command::new(program).args(args).output().ok()?;
+        let packages = package_lines(&String::from_utf8_lossy(&output.stdout));
+        (output.status.success() && !packages.is_empty()).then_some((family, packages))
+    })
+    .collect()
+}
+
+/// One package per non-empty line, sorted and deduplicated.
+fn package_lines(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::types::CommandEntry;
+    use tempfile::tempdir;
+
+    #[cfg(unix)]
+    #[test]
+    fn links_into_one_source_root_become_one_directory_entry() {
+        let dir = tempdir().unwrap();
+        let home = dir.path().join("home");
+        let repo = dir.path().join("repo");
+        std::fs::create_dir_all(home.join(".config")).unwrap();
+        std::fs::create_dir_all(repo.join("home/.config")).unwrap();
+        std::fs::write(repo.join("home/.bashrc"), "").unwrap();
+        std::fs::write(repo.join("home/.config/starship.toml"), "").unwrap();
+        std::fs::write(repo.join("vimrc"), "").unwrap();
+        std::os::unix::fs::symlink(repo.join("home/.bashrc"), home.join(".bashrc")).unwrap();
+        std::os::unix::fs::symlink(
+            repo.join("home/.config/starship.toml"),
+            home.join(".config/starship.toml"),
+        )
+        .unwrap();
+        std::os::unix::fs::symlink(repo.join("vimrc"), home.join(".vimrc")).unwrap();
+        std::os::unix::fs::symlink("/etc/hostname", home.join("other")).unwrap();
+
+        let links = scan_links(&home, &repo).unwrap();
+        assert_eq!(links.len(), 3);
+
+        let path = repo.join("machine_setup.yaml");
+        document::init(&path).unwrap();
+        let config_dir = repo.canonicalize().unwrap();
+        let emitted = emit_links(&links, &home, &config_dir).unwrap();
+        document::append_emitted(&path, &emitted).unwrap();
+        let config = document::load_after_write(&path).unwrap();
+        let entries: Vec<(String, String)> = config.tasks["dotfiles"]
+            .commands
+            .iter()
+            .map(|entry| match entry {
+                CommandEntry::Symlink(args) => (args.src.clone(), args.target.clone()),
+                other => panic!("unexpected entry {other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                ("./home".to_string(), "~".to_string()),
+                ("./vimrc".to_string(), "~/.vimrc".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn git_checkouts_are_found_with_their_origin() {
+        let dir = tempdir().unwrap();
+        let checkout = dir.path().join("projects/tool");
+        std::fs::create_dir_all(checkout.join("vendor/nested")).unwrap();
+        let git_ok = |dir: &Path, args: &[&str]| {
+            Command::new("git")
+                .arg("-C")
+                .arg(dir)
+                .args(args)
+                .output()
+                .is_ok_and(|o| o.status.success())
+        };
+        if !git_ok(&checkout, &["init", "-q"]) {
+            return; // git not installed
+        }
+        assert!(git_ok(
+            &checkout,
+            &["remote", "add", "origin", "https://example.com/tool.git"]
+        ));
+        assert!(git_ok(&checkout.join("vendor/nested"), &["init", "-q"]));
+
+        let found = scan_git(&[dir.path().join("projects")]);
+        assert_eq!(
+            found,
+            vec![(checkout, "https://example.com/tool.git".to_string())]
+        );
+    }
+
+    #[test]
+    fn package_lines_are_sorted_and_deduplicated() {
+        assert_eq!(
+            package_lines("ripgrep\n\ngit\nripgrep\n"),
+            vec!["git".to_string(), "ripgrep".to_string()]
+        );
+    }
+
+    #[test]
+    fn packages_task_parses_as_package_entry() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("machine_setup.yaml");
+        document::init(&path).unwrap();
+        let emitted = recipes::emit_packages(
+            recipes::IMPORT_PACKAGES_NAME,
+            &[("apt", vec!["git".into()]), ("brew", vec!["jq".into()])],
+        )
+        .unwrap();
+        document::append_emitted(&path, &emitted).unwrap();
+        let config = document::load_after_write(&path).unwrap();
+        let CommandEntry::Package(args) = &config.tasks["packages"].commands[0] else {
+            panic!("expected a package entry");
+        };
+        assert_eq!(args.apt, vec!["git".to_string()]);
+        assert_eq!(args.brew, vec!["jq".to_string()]);
+    }
+}
@@ -0,0 +1,254 @@
+//! Cross-document `depends_on` — `./langs.yaml#rust` names Task `rust` of
+//! another Config document.
+//!
+//! Linking pulls every Task reachable through such references into the
+//! loading [`AppConfig`], keyed by its qualified name (the document path
+//! relative to the root document's directory, `#`, the Task name), and
+//! rewrites `depends_on` to those keys. The Task graph, cycle detection,
+//! selection and validation then span documents without knowing about them.
+//! Each referenced document is read once per load; a reference back into the
+//! root document becomes the plain Task name. References that do not resolve
+//! are left as written, so validation reports them as unknown Tasks.
+//!
+//! A linked Task keeps its own document's History (one [`HistoryScope`] per
+//! linked document), so it is the same Task a `machine_setup` entry loading
+//! that document runs, and a run executes it once.
+
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+
+use crate::utils::path::{expand_path, relative_to};
+
+use super::types::{AppConfig, HistoryScope, TaskOrigin, Via};
+
+/// Separates the document from the Task name in a `depends_on` reference.
+pub const SEPARATOR: char = '#';
+
+/// `(document, task)` of a cross-document reference.
+pub fn split_ref(dep: &str) -> Option<(&str, &str)> {
+    dep.split_once(SEPARATOR)
+        .filter(|(doc, task)| !doc.is_empty() && !task.is_empty())
+}
+
+/// Link the Tasks `config` (loaded from the canonical `document`) reaches
+/// through cross-document `depends_on`, recording each in its document's
+/// History scope.
+pub fn link(config: &mut AppConfig, document: &Path) {
+    let Some(root_dir) = document.parent() else {
+        return;
+    };
+    let mut linker = Linker {
+        root: document.to_path_buf(),
+        root_dir: root_dir.to_path_buf(),
+        documents: HashMap::new(),
+    };
+    let mut queue: VecDeque<String> = config.tasks.keys().cloned().collect();
+    while let Some(key) = queue.pop_front() {
+        // Plain names of linked Tasks stay in their document; those of
+        // composed Tasks (`extends`, `include`) name Tasks of this config.
+        let (here, scoped) = match config.origins.get(&key) {
+            Some(origin) => (origin.document.clone(), origin.via == Via::Dependency),
+            None => (linker.root.clone(), false),
+        };
+        let deps = config.tasks[&key].depends_on.clone();
+        let mut linked = Vec::with_capacity(deps.len());
+        for dep in deps {
+            let Some((target_doc, task)) = linker.resolve(&here, &dep, scoped) else {
+                linked.push(dep);
+                continue;
+            };
+            let qualified = linker.qualify(&target_doc, &task);
+            if target_doc != linker.root && !config.tasks.contains_key(&qualified) {
+                let Some((task_config, origin)) = linker.task(&target_doc, &task) else {
+                    linked.push(dep);
+                    continue;
+                };
+                let prefix = linker.qualify(&target_doc, "");
+                if !config.history_scopes.iter().any(|s| s.prefix == prefix) {
+                    config.history_scopes.push(HistoryScope {
+                        prefix,
+                        document: linker.identity(&target_doc),
+                    });
+                }
+                config.tasks.insert(qualified.clone(), task_config);
+                config.origins.insert(qualified.clone(), origin);
+                queue.push_back(qualified.clone());
+            }
+            linked.push(qualified);
+        }
+        config.tasks[&key].depends_on = linked;
+    }
+}
+
+struct Linker {
+    root: PathBuf,
+    root_dir: PathBuf,
+    /// Referenced documents by canonical path; `None` when unreadable.
+    documents: HashMap<PathBuf, Option<AppConfig>>,
+}
+
+impl Linker {
+    /// Canonical document and Task name `dep` (written in `here`) points to.
+    /// Plain names stay in `here` when `scoped` to a linked document.
+    fn resolve(&self, here: &Path, dep: &str, scoped: bool) -> Option<(PathBuf, String)> {
+        match split_ref(dep) {
+            Some((doc, task)) => {
+                let dir = here.parent()?;
+                let path = super::resolve_config_path(&expand_path(doc, Some(dir))).ok()?;
+                Some((path.canonicalize().ok()?, task.to_string()))
+            }
+            None if scoped => Some((here.to_path_buf(), dep.to_string())),
+            None => None,
+        }
+    }
+
+    /// Key of `task` of `document` in the linked config.
+    fn qualify(&self, document: &Path, task: &str) -> String {
+        if document == self.root {
+            return task.to_string();
+        }
+        let path = match relative_to(document, &self.root_dir) {
+            Some(rel) if rel.starts_with("..") => rel.display().to_string(),
+            Some(rel) => format!("./{}", rel.display()),
+            None => document.display().to_string(),
+        };
+        format!("{path}{SEPARATOR}{task}")
+    }
+
+    /// History identity of a linked `document` — its `id:`, else its
+    /// canonical path — as when it is loaded on its own.
+    fn identity(&self, document: &Path) -> String {
+        self.documents
+            .get(document)
+            .and_then(|config| config.as_ref()?.id.clone())
+            .unwrap_or_else(|| document.to_string_lossy().into_owned())
+    }
+
+    fn task(
+        &mut self,
+        document: &Path,
+        task: &str,
+    ) -> Option<(super::types::TaskConfig, TaskOrigin)> {
+        let config = self
+            .documents
+            .entry(document.to_path_buf())
+            .or_insert_with(|| super::compose::load(document).ok())
+            .as_ref()?;
+        let task_config = config.tasks.get(task)?.clone();
+        let config_dir = match config.origins.get(task) {
+            Some(origin) => origin.config_dir.clone(),
+            None => document.parent()?.to_path_buf(),
+        };
+        let origin = TaskOrigin {
+            document: document.to_path_buf(),
+            config_dir,
+            default_shell: config.default_shell.clone(),
+            via: Via::Dependency,
+        };
+        Some((task_config, origin))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::load_config;
+    use tempfile::tempdir;
+
+    fn write(dir: &Path, name: &str, yaml: &str) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, yaml).unwrap();
+        path
+    }
+
+    #[test]
+    fn links_task_of_another_document_with_its_dependencies() {
+        let dir = tempdir().unwrap();
+        write(
+            dir.path(),
+            "langs.yaml",
+            "default_shell: zsh\ntasks:\n  rustup:\n    commands: []\n  rust:\n    depends_on: [rustup]\n    commands: []\n  go:\n    commands: []\n",
+        );
+        let root = write(
+            dir.path(),
+            "main.yaml",
+            "tasks:\n  editor:\n    depends_on: [\"./langs.yaml#rust\"]\n    commands: []\n",
+        );
+
+        let config = load_config(root.to_str().unwrap()).unwrap();
+        let names: Vec<&str> = config.tasks.keys().map(String::as_str).collect();
+        assert_eq!(
+            names,
+            vec!["editor", "./langs.yaml#rust", "./langs.yaml#rustup"]
+        );
+        assert_eq!(config.tasks["editor"].depends_on, vec!["./langs.yaml#rust"]);
+        assert_eq!(
+            config.tasks["./langs.yaml#rust"].depends_on,
+            vec!["./langs.yaml#rustup"]
+        );
+        let origin = &config.origins["./langs.yaml#rust"];
+        assert_eq!(origin.config_dir, dir.path().canonicalize().unwrap());
+        assert_eq!(origin.default_shell, crate::config::types::Shell::Zsh);
+    }
+
+    #[test]
+    fn linked_tasks_keep_the_history_of_their_document() {
+        let dir = tempdir().unwrap();
+        let langs = write(
+            dir.path(),
+            "langs.yaml",
+            "tasks:\n  rust:\n    commands: []\n",
+        );
+        let root = write(
+            dir.path(),
+            "main.yaml",
+            "tasks:\n  editor:\n    depends_on: [\"./langs.yaml#rust\"]\n    commands: []\n",
+        );
+
+        let config = load_config(root.to_str().unwrap()).unwrap();
+        let own = load_config(langs.to_str().unwrap()).unwrap();
+        let scope = config
+            .history_scopes
+            .iter()
+            .find(|s| s.prefix == "./langs.yaml#")
+            .unwrap();
+        assert_eq!(scope.document, own.history_scopes[0].document);
+    }
+
+    #[test]
+    fn reference_back_into_the_root_document_closes_a_cycle() {
+        let dir = tempdir().unwrap();
+        write(
+            dir.path(),
+            "b.yaml",
+            "tasks:\n  y:\n    depends_on: [\"./a.yaml#x\"]\n    commands: []\n",
+        );
+        let root = write(
+            dir.path(),
+            "a.yaml",
+            "tasks:\n  x:\n    depends_on: [\"./b.yaml#y\"]\n    commands: []\n",
+        );
+
+        let config = load_config(root.to_str().unwrap()).unwrap();
+        assert_eq!(config.tasks["./b.yaml#y"].depends_on, vec!["x"]);
+        let graph = crate::config::graph::TaskGraph::new(&config.tasks);
+        assert!(graph.find_cycle().is_some());
+    }
+
+    #[test]
+    fn unresolved_reference_is_left_for_validation() {
+        let dir = tempdir().unwrap();
+        let root = write(
+            dir.path(),
+            "main.yaml",
+            "tasks:\n  a:\n    depends_on: [\"./missing.yaml#x\"]\n    commands: []\n",
+        );
+
+        let config = load_config(root.to_str().unwrap()).unwrap();
+        assert_eq!(config.tasks["a"].depends_on, vec!["./missing.yaml#x"]);
+        let issues = crate::config::validate::validate_config(&config, dir.path());
+        assert!(issues
+            .iter()
+            .any(|i| i.message.contains("./missing.yaml#x")));
+    }
+}
@@ -1,26 +1,40 @@
+pub mod adopt;
+pub mod compose;
 pub mod document;
 pub mod graph;
 pub mod history;
+pub mod import;
+pub mod link;
 pub mod locator;
 pub mod os;
 pub mod recipes;
+pub mod remote;
+pub mod repo;
 pub mod schema;
 pub mod selection;
 pub mod status;
 pub mod types;
 pub mod validate;
+pub mod verify;
 pub mod wizard;
+pub mod workspace;
 
 use std::path::{Path, PathBuf};
 
 use crate::error::{Error, Result};
+use crate::utils::http;
 use types::AppConfig;
 
 /// Return the directory that relative paths inside a config file should be
 /// resolved against. For local configs this is the config file's parent
-/// directory (after canonicalizing); for URLs and unresolvable paths we fall
-/// back to `fallback`.
+/// directory (after canonicalizing); for git sources it is the document's
+/// directory in the checkout under the default `temp_dir`; for URLs and
+/// unresolvable paths we fall back to `fallback`.
 pub fn resolve_config_dir(path_or_url: &str, fallback: &Path) -> PathBuf {
+    if repo::is_git_source(path_or_url) {
+        return repo::config_dir(path_or_url, &remote::default_dir())
+            .unwrap_or_else(|_| fallback.to_path_buf());
+    }
     if is_url(path_or_url) {
         return fallback.to_path_buf();
     }
@@ -33,11 +47,12 @@ pub fn resolve_config_dir(path_or_url: &str, fallback: &Path) -> PathBuf {
 
 /// Resolve which Config document to load.
 ///
-/// - `Some(path_or_url)` — explicit `-c` (local extension probe or URL).
+/// - `Some(path_or_url)` — explicit `-c` (local extension probe, URL or git
+///   source).
 /// - `None` — Config locator (cwd, then git root).
 pub fn resolve_config_source(config_arg: Option<&str>, cwd: &Path) -> Result<String> {
     match config_arg {
-        Some(raw) if is_url(raw) => Ok(raw.to_string()),
+        Some(raw) if is_remote(raw) => Ok(raw.to_string()),
         Some(raw) => {
             let resolved = resolve_config_path(Path::new(raw))?;
             Ok(resolved.to_string_lossy().into_owned())
@@ -56,11 +71,44 @@ pub fn resolve_config_source(config_arg: Option<&str>, cwd: &Path) -> Result<Str
 /// - `https://github.com/user/repo/blob/branch/file.yaml` (auto-converted to raw URL)
 /// - `https://raw.githubusercontent.com/user/repo/branch/file.yaml`
 /// - Any other URL returning YAML/JSON content
+/// - `git+<url>[@<ref>][#<path>]` — a document in a git repository (see [`repo`])
+///
+/// URLs are verified against their `#sha256=` pin and, when the default
+/// `temp_dir` holds a trusted key, their signature (see [`verify`]), and are
+/// cached there for offline use (see [`remote`]).
 pub fn load_config(path_or_url: &str) -> Result<AppConfig> {
-    if is_url(path_or_url) {
-        load_config_from_url(path_or_url)
+    load_config_remote(
+        path_or_url,
+        &remote::Remote {
+            temp_dir: &remote::default_dir(),
+            on_change: remote::OnChange::Read,
+        },
+    )
+}
+
+/// [`load_config`] with the trusted key and cache in `remote.temp_dir`, and
+/// changes of a URL document approved per `remote.on_change`. A git source
+/// is checked out under `remote.temp_dir`.
+pub fn load_config_remote(path_or_url: &str, remote: &remote::Remote<'_>) -> Result<AppConfig> {
+    if repo::is_git_source(path_or_url) {
+        load_local(&repo::checkout(path_or_url, remote.temp_dir)?, remote)
+    } else if is_url(path_or_url) {
+        load_config_from_url(path_or_url, remote)
     } else {
-        load_config_from_path(Path::new(path_or_url))
+        load_local(Path::new(path_or_url), remote)
+    }
+}
+
+/// Load the local document at `path`, or the documents its `configs:` lists
+/// (see [`workspace`]).
+fn load_local(path: &Path, remote: &remote::Remote<'_>) -> Result<AppConfig> {
+    let resolved = resolve_config_path(path)?;
+    match workspace::read(&resolved)? {
+        Some(found) => {
+            let dir = resolved.parent().unwrap_or(Path::new("."));
+            workspace::load(Some(found.settings), &found.members, remote, dir)
+        }
+        None => load_config_from_path(&resolved),
     }
 }
 
@@ -68,23 +116,40 @@ pub fn is_url(s: &str) -> bool {
     s.starts_with("http://") || s.starts_with("https://")
 }
 
-fn load_config_from_url(url: &str) -> Result<AppConfig> {
+/// A URL or a git source: not a path on this machine.
+pub fn is_remote(s: &str) -> bool {
+    is_url(s) || repo::is_git_source(s)
+}
+
+fn load_config_from_url(url: &str, remote: &remote::Remote<'_>) -> Result<AppConfig> {
+    let (url, pin) = verify::split_pin(url)?;
     let raw_url = to_raw_url(url);
 
     eprintln!("Fetching config from {}...", raw_url);
 
-    let response = ureq::get(&raw_url)
+    let content = remote::fetch(&raw_url, pin.as_deref(), remote)?;
+
+    let ext = url_extension(&raw_url);
+    compose::reject_remote(&content, &ext)?;
+
+    let mut config = parse_config(&content, &ext)?;
+    set_history_document(&mut config, url);
+    config.remote_documents = vec![types::RemoteDocument {
+        url: raw_url,
+        content,
+    }];
+    Ok(config)
+}
+
+fn fetch(url: &str) -> Result<String> {
+    let response = http::get_config(url)?
         .call()
-        .map_err(|e| Error::Other(format!("Failed to fetch config: {e}")))?;
+        .map_err(|e| Error::Other(format!("Failed to fetch {url}: {e}")))?;
 
-    let content = response
+    response
         .into_body()
         .read_to_string()
-        .map_err(|e| Error::Other(format!("Failed to read response: {e}")))?;
-
-    let ext = url_extension(&raw_url);
-
-    parse_config(&content, &ext)
+        .map_err(|e| Error::Other(format!("Failed to read response: {e}")))
 }
 
 fn load_config_from_path(path: &Path) -> Result<AppConfig> {
@@ -93,10 +158,23 @@ fn load_config_from_path(path: &Path) -> Result<AppConfig> {
     } else {
         resolve_config_path(path)?
     };
-    let content = std::fs::read_to_string(&resolved)?;
-    let ext = resolved.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let mut config = compose::load(&resolved)?;
+    let document = resolved.canonicalize();
+    let identity = document.as_ref().unwrap_or(&resolved);
+    set_history_document(&mut config, &identity.to_string_lossy());
+    if let Ok(document) = &document {
+        link::link(&mut config, document);
+    }
+    Ok(config)
+}
 
-    parse_config(&content, ext)
+/// Record `config`'s Tasks in History under its `id:`, else `identity` (its
+/// canonical path or URL).
+fn set_history_document(config: &mut AppConfig, identity: &str) {
+    config.history_scopes = vec![types::HistoryScope {
+        prefix: String::new(),
+        document: config.id.clone().unwrap_or_else(|| identity.to_string()),
+    }];
 }
 
 fn parse_config(content: &str, ext: &str) -> Result<AppConfig> {
@@ -113,18 +191,40 @@ fn parse_config(content: &str, ext: &str) -> Result<AppConfig> {
     }
 }
 
-/// Convert GitHub blob URLs to raw.githubusercontent.com URLs.
+/// Convert the page URL of a file on a code host to its raw content URL:
+/// GitHub blobs and gists, GitLab (`/-/blob/`), Gitea and Forgejo
+/// (`/src/branch/`, `/src/tag/`, `/src/commit/`) and Bitbucket (`/src/`).
 /// Other URLs are returned as-is.
 fn to_raw_url(url: &str) -> String {
-    if let Some(rest) = url
-        .strip_prefix("https://github.com/")
-        .or_else(|| url.strip_prefix("http://github.com/"))
-    {
-        if let Some(after_blob) = rest.split_once("/blob/") {
-            return format!(
-                "https://raw.githubusercontent.com/{}/{}",
-                after_blob.0, after_blob.1
-            );
+    let (scheme, rest) = url.split_once("://").unwrap_or(("https", url));
+    let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+    match host {
+        "github.com" => {
+            if let Some((repo, file)) = path.split_once("/blob/") {
+                return format!("https://raw.githubusercontent.com/{repo}/{file}");
+            }
+        }
+        "gist.github.com" => {
+            let gist = path.split(['#', '?']).next().unwrap_or(path);
+            let gist = gist.trim_end_matches('/');
+            if gist.split('/').count() == 2 {
+                return format!("https://gist.githubusercontent.com/{gist}/raw");
+            }
+        }
+        "bitbucket.org" => {
+            if let Some((repo, file)) = path.split_once("/src/") {
+                return format!("{scheme}://{host}/{repo}/raw/{file}");
+            }
+        }
+        _ => {
+            if let Some((repo, file)) = path.split_once("/-/blob/") {
+                return format!("{scheme}://{host}/{repo}/-/raw/{file}");
+            }
+            for kind in ["branch", "tag", "commit"] {
+                if let Some((repo, file)) = path.split_once(&format!("/src/{kind}/")) {
+                    return format!("{scheme}://{host}/{repo}/raw/{kind}/{file}");
+                }
+            }
         }
     }
     url.to_string()
@@ -253,6 +353,28 @@ tasks:
         assert_eq!(to_raw_url(url), url);
     }
 
+    #[test]
+    fn test_to_raw_url_other_hosts() {
+        assert_eq!(
+            to_raw_url("https://gitlab.com/me/dots/-/blob/main/setup.yaml"),
+            "https://gitlab.com/me/dots/-/raw/main/setup.yaml"
+        );
+        assert_eq!(
+            to_raw_url("https://codeberg.org/me/dots/src/branch/main/setup.yaml"),
+            "https://codeberg.org/me/dots/raw/branch/main/setup.yaml"
+        );
+        assert_eq!(
+            to_raw_url("https://bitbucket.org/me/dots/src/main/setup.yaml"),
+            "https://bitbucket.org/me/dots/raw/main/setup.yaml"
+        );
+        assert_eq!(
+            to_raw_url("https://gist.github.com/me/0123abcd"),
+            "https://gist.githubusercontent.com/me/0123abcd/raw"
+        );
+        let raw = "https://gist.githubusercontent.com/me/0123abcd/raw/setup.yaml";
+        assert_eq!(to_raw_url(raw), raw);
+    }
+
     #[test]
     fn test_url_extension() {
         assert_eq!(url_extension("https://example.com/config.yaml"), "yaml");
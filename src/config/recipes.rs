@@ -40,9 +40,22 @@ pub struct BrewBundleParams<'a> {
     pub file: &'a str,
 }
 
+/// Parameters for the Task `machine_setup adopt` appends.
+#[derive(Debug, Clone)]
+pub struct AdoptParams<'a> {
+    pub name: &'a str,
+    /// Symlink source directory the adopted file moved into.
+    pub src: &'a str,
+    pub target: &'a str,
+}
+
 pub const DEFAULT_DOTFILES_NAME: &str = "dotfiles";
 pub const DEFAULT_GIT_REPO_NAME: &str = "git-repo";
 pub const DEFAULT_BREW_BUNDLE_NAME: &str = "brew-bundle";
+pub const DEFAULT_ADOPT_NAME: &str = "adopted";
+pub const IMPORT_DOTFILES_NAME: &str = "dotfiles";
+pub const IMPORT_GIT_REPOS_NAME: &str = "git-repos";
+pub const IMPORT_PACKAGES_NAME: &str = "packages";
 pub const DEFAULT_DOTFILES_SRC: &str = "./home";
 pub const DEFAULT_DOTFILES_TARGET: &str = "~";
 pub const DEFAULT_DOTFILES_IGNORE: &str = ".cursor";
@@ -100,6 +113,73 @@ pub fn emit_brew_bundle(p: &BrewBundleParams<'_>) -> Result<EmittedTask> {
     })
 }
 
+/// Emit a single `symlink` Command entry for a source tree `adopt` filled.
+pub fn emit_adopt(p: &AdoptParams<'_>) -> Result<EmittedTask> {
+    crate::config::document::validate_task_name(p.name)?;
+    let yaml = format!(
+        "\n  # machine_setup adopt\n  {name}:\n    commands:\n      - symlink:\n          src: {src}\n          target: {target}\n",
+        name = p.name,
+        src = quote_yaml(p.src),
+        target = quote_yaml(p.target),
+    );
+    Ok(EmittedTask {
+        name: p.name.to_string(),
+        yaml,
+    })
+}
+
+/// Emit one `symlink` Command entry per `(src, target)` pair.
+pub fn emit_symlinks(name: &str, links: &[(String, String)]) -> Result<EmittedTask> {
+    crate::config::document::validate_task_name(name)?;
+    let entries: String = links
+        .iter()
+        .map(|(src, target)| {
+            format!(
+                "      - symlink:\n          src: {}\n          target: {}\n",
+                quote_yaml(src),
+                quote_yaml(target)
+            )
+        })
+        .collect();
+    Ok(import_task(name, &entries))
+}
+
+/// Emit one `clone` Command entry per `(url, target)` pair.
+pub fn emit_clones(name: &str, repos: &[(String, String)]) -> Result<EmittedTask> {
+    crate::config::document::validate_task_name(name)?;
+    let entries: String = repos
+        .iter()
+        .map(|(url, target)| {
+            format!(
+                "      - clone:\n          url: {}\n          target: {}\n",
+                quote_yaml(url),
+                quote_yaml(target)
+            )
+        })
+        .collect();
+    Ok(import_task(name, &entries))
+}
+
+/// Emit one `package` Command entry with `(family, packages)` lists.
+pub fn emit_packages(name: &str, families: &[(&str, Vec<String>)]) -> Result<EmittedTask> {
+    crate::config::document::validate_task_name(name)?;
+    let mut entry = String::from("      - package:\n");
+    for (family, packages) in families {
+        entry.push_str(&format!("          {family}:\n"));
+        for package in packages {
+            entry.push_str(&format!("            - {}\n", quote_yaml(package)));
+        }
+    }
+    Ok(import_task(name, &entry))
+}
+
+fn import_task(name: &str, entries: &str) -> EmittedTask {
+    EmittedTask {
+        name: name.to_string(),
+        yaml: format!("\n  # machine_setup import\n  {name}:\n    commands:\n{entries}"),
+    }
+}
+
 fn format_ignore_list(items: &[&str]) -> String {
     items
         .iter()
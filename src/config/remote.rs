@@ -0,0 +1,401 @@
+//! Remote Config documents — fetched through a cache under
+//! `remote_cache/` in the run's [`default_dir`].
+//!
+//! Each URL keeps one entry: the last fetched (and verified) content with its
+//! `ETag` / `Last-Modified` validators, and the content that last ran. A
+//! fetch revalidates the entry; when the server cannot be reached at all the
+//! cached copy is used with a warning naming its age, so `schedule run` keeps
+//! working offline. An answer that is an error (HTTP 4xx/5xx, a failed TLS
+//! handshake) is an error: the cache does not paper over a moved or
+//! intercepted document.
+//! Before a run executes a document whose content changed since it last ran,
+//! [`OnChange`] decides with the unified diff — ask, accept (`--yes`), or
+//! refuse. The content counts as run only once the run succeeded
+//! ([`record_run`]); after a failed run the change is asked about again.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::engine::conflict::diff_text;
+use crate::error::{Error, Result};
+use crate::utils::http;
+
+use super::types::RemoteDocument;
+use super::verify;
+
+/// Cache directory inside `temp_dir`.
+pub const CACHE_DIR: &str = "remote_cache";
+
+/// The directory a run caches remote documents in and reads the trusted key
+/// from: the default `temp_dir`. It is fixed before anything is fetched, so a
+/// fetched document cannot pick the key that verifies it, and the root
+/// Config and its Sub-configs share one cache.
+pub fn default_dir() -> PathBuf {
+    crate::utils::path::expand_path(&super::types::default_temp_dir(), None)
+}
+
+/// What loading does when the document changed since it last ran.
+#[derive(Clone, Copy)]
+pub enum OnChange<'a> {
+    /// Not running it (`list`, `validate`): nothing to approve.
+    Read,
+    /// Run it (`--yes`).
+    Accept,
+    /// Refuse, with the diff in the error (no one to ask mid-run).
+    Refuse,
+    /// Ask with `(url, diff)`; `true` runs it.
+    Ask(&'a dyn Fn(&str, &str) -> bool),
+}
+
+/// Where the cache and the trusted key live, and how changes are approved.
+#[derive(Clone, Copy)]
+pub struct Remote<'a> {
+    pub temp_dir: &'a Path,
+    pub on_change: OnChange<'a>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Entry {
+    url: String,
+    #[serde(default)]
+    etag: Option<String>,
+    #[serde(default)]
+    last_modified: Option<String>,
+    /// RFC 3339 time of the last successful fetch.
+    fetched: String,
+    content: String,
+    #[serde(default)]
+    signature: Option<String>,
+    /// Content of the last run, if any.
+    #[serde(default)]
+    ran: Option<String>,
+}
+
+/// Fetch the document at `url`, verify it against `pin` and the trusted key,
+/// and approve a change per `remote.on_change`. Returns the content to load.
+pub fn fetch(url: &str, pin: Option<&str>, remote: &Remote<'_>) -> Result<String> {
+    let key = verify::trusted_key(remote.temp_dir)?;
+    let path = entry_path(remote.temp_dir, url);
+    let cached = load_entry(&path).filter(|entry| entry.url == url);
+
+    let entry = match revalidate(url, cached.as_ref(), key.is_some())? {
+        Revalidated::Fresh(fresh) => Entry {
+            ran: cached.and_then(|entry| entry.ran),
+            ..fresh
+        },
+        Revalidated::Current => cached.expect("304 only answers a conditional request"),
+        Revalidated::Unreachable(reason) => match cached {
+            Some(entry) => {
+                eprintln!(
+                    "warning: cannot reach {url} ({reason}); using the copy cached {}",
+                    age(&entry.fetched)
+                );
+                entry
+            }
+            None => return Err(Error::Other(format!("Failed to fetch {url}: {reason}"))),
+        },
+    };
+
+    if let Some(expected) = pin {
+        verify::check_sha256(url, entry.content.as_bytes(), expected)?;
+    }
+    if let Some(key) = &key {
+        let signature = entry.signature.as_deref().ok_or_else(|| {
+            Error::Verification(format!("{url}: no signature cached for offline use"))
+        })?;
+        verify::check_signature(url, entry.content.as_bytes(), key, signature)?;
+    }
+
+    approve(&entry, remote.on_change)?;
+    save_entry(&path, &entry)?;
+    Ok(entry.content)
+}
+
+/// Record that `document` ran to success: a later change of it is diffed
+/// against this content.
+pub fn record_run(temp_dir: &Path, document: &RemoteDocument) -> Result<()> {
+    let path = entry_path(temp_dir, &document.url);
+    let Some(mut entry) = load_entry(&path).filter(|entry| entry.url == document.url) else {
+        return Ok(());
+    };
+    entry.ran = Some(document.content.clone());
+    save_entry(&path, &entry)
+}
+
+/// Approve running `entry` when it changed since its last run.
+fn approve(entry: &Entry, on_change: OnChange<'_>) -> Result<()> {
+    let changed = match &entry.ran {
+        Some(ran) if *ran != entry.content => {
+            diff_text(ran, &entry.content, "last run", &entry.url)
+        }
+        // Unchanged, or the first run of a URL: the user's own choice.
+        _ => return Ok(()),
+    };
+    let accepted = match on_change {
+        OnChange::Read => return Ok(()),
+        OnChange::Accept => true,
+        OnChange::Ask(ask) => ask(&entry.url, &changed),
+        OnChange::Refuse => {
+            return Err(Error::Verification(format!(
+                "{} changed since it last ran; review and rerun with --yes:\n{changed}",
+                entry.url
+            )))
+        }
+    };
+    if !accepted {
+        return Err(Error::Verification(format!(
+            "{} changed since it last ran; not running it",
+            entry.url
+        )));
+    }
+    Ok(())
+}
+
+/// How the server answered a revalidation.
+enum Revalidated {
+    Fresh(Entry),
+    /// `304 Not Modified`: the cached entry is current.
+    Current,
+    /// No answer at all (connection refused, DNS, timeout), with the reason.
+    Unreachable(String),
+}
+
+/// Whether `error` means the server was never reached, as opposed to an
+/// answer that is itself an error.
+fn is_unreachable(error: &ureq::Error) -> bool {
+    matches!(
+        error,
+        ureq::Error::Io(_)
+            | ureq::Error::Timeout(_)
+            | ureq::Error::HostNotFound
+            | ureq::Error::ConnectionFailed
+    )
+}
+
+/// `3 days ago` for the RFC 3339 time `fetched`.
+fn age(fetched: &str) -> String {
+    let Ok(then) = chrono::DateTime::parse_from_rfc3339(fetched) else {
+        return format!("at {fetched}");
+    };
+    let minutes = (chrono::Utc::now() - then.with_timezone(&chrono::Utc)).num_minutes();
+    let (count, unit) = match minutes {
+        ..=0 => return "just now".to_string(),
+        1..60 => (minutes, "minute"),
+        60..1440 => (minutes / 60, "hour"),
+        _ => (minutes / 1440, "day"),
+    };
+    let plural = if count == 1 { "" } else { "s" };
+    format!("{count} {unit}{plural} ago")
+}
+
+fn revalidate(url: &str, cached: Option<&Entry>, signed: bool) -> Result<Revalidated> {
+    let mut request = http::get_config(url)?;
+    if let Some(cached) = cached {
+        if let Some(etag) = &cached.etag {
+            request = request.header("If-None-Match", etag);
+        }
+        if let Some(modified) = &cached.last_modified {
+            request = request.header("If-Modified-Since", modified);
+        }
+    }
+    let response = match request.config().http_status_as_error(false).build().call() {
+        Ok(response) => response,
+        Err(e) if is_unreachable(&e) => return Ok(Revalidated::Unreachable(e.to_string())),
+        Err(e) => return Err(Error::Other(format!("Failed to fetch {url}: {e}"))),
+    };
+    let status = response.status().as_u16();
+    if status == 304 && cached.is_some() {
+        return Ok(Revalidated::Current);
+    }
+    if !(200..300).contains(&status) {
+        return Err(Error::Other(format!(
+            "Failed to fetch {url}: HTTP {status}"
+        )));
+    }
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    };
+    let etag = header("ETag");
+    let last_modified = header("Last-Modified");
+    let content = response
+        .into_body()
+        .read_to_string()
+        .map_err(|e| Error::Other(format!("Failed to read response: {e}")))?;
+    let signature = if signed {
+        Some(super::fetch(&verify::signature_url(url))?)
+    } else {
+        None
+    };
+    Ok(Revalidated::Fresh(Entry {
+        url: url.to_string(),
+        etag,
+        last_modified,
+        fetched: chrono::Local::now().to_rfc3339(),
+        content,
+        signature,
+        ran: None,
+    }))
+}
+
+fn entry_path(temp_dir: &Path, url: &str) -> PathBuf {
+    let digest = format!("{:x}", Sha256::digest(url.as_bytes()));
+    temp_dir
+        .join(CACHE_DIR)
+        .join(format!("{}.json", &digest[..16]))
+}
+
+fn load_entry(path: &Path) -> Option<Entry> {
+    let text = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+fn save_entry(path: &Path, entry: &Entry) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use tempfile::tempdir;
+
+    fn entry(ran: Option<&str>, content: &str) -> Entry {
+        Entry {
+            url: "https://example.com/setup.yaml".into(),
+            content: content.into(),
+            ran: ran.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn first_run_is_not_asked_about() {
+        approve(&entry(None, "a\n"), OnChange::Refuse).unwrap();
+        approve(&entry(None, "a\n"), OnChange::Read).unwrap();
+    }
+
+    #[test]
+    fn only_a_recorded_run_settles_a_change() {
+        let dir = tempdir().unwrap();
+        let changed = entry(Some("a\n"), "b\n");
+        let path = entry_path(dir.path(), &changed.url);
+        save_entry(&path, &changed).unwrap();
+        let document = RemoteDocument {
+            url: changed.url.clone(),
+            content: "b\n".into(),
+        };
+
+        // Approved but not yet run: still a change.
+        approve(&changed, OnChange::Accept).unwrap();
+        let kept = load_entry(&path).unwrap();
+        assert!(approve(&kept, OnChange::Refuse).is_err());
+
+        record_run(dir.path(), &document).unwrap();
+        let ran = load_entry(&path).unwrap();
+        assert_eq!(ran.ran.as_deref(), Some("b\n"));
+        approve(&ran, OnChange::Refuse).unwrap();
+    }
+
+    #[test]
+    fn change_is_asked_with_a_diff() {
+        let seen = RefCell::new(String::new());
+        let ask = |_: &str, diff: &str| {
+            *seen.borrow_mut() = diff.to_string();
+            false
+        };
+        let changed = entry(Some("a\nb\n"), "a\nc\n");
+        assert!(approve(&changed, OnChange::Ask(&ask)).is_err());
+        assert!(seen.borrow().contains("-b\n"));
+        assert!(seen.borrow().contains("+c\n"));
+
+        approve(&changed, OnChange::Accept).unwrap();
+    }
+
+    #[test]
+    fn change_is_refused_mid_run_and_kept_when_reading() {
+        let changed = entry(Some("a\n"), "b\n");
+        let err = approve(&changed, OnChange::Refuse).unwrap_err();
+        assert!(err.to_string().contains("--yes"));
+        approve(&changed, OnChange::Read).unwrap();
+    }
+
+    #[test]
+    fn offline_fetch_falls_back_to_the_cached_copy() {
+        let dir = tempdir().unwrap();
+        // Nothing listens on port 9 of localhost (discard), so the fetch fails.
+        let url = "http://127.0.0.1:9/setup.yaml";
+        let remote = Remote {
+            temp_dir: dir.path(),
+            on_change: OnChange::Refuse,
+        };
+        assert!(fetch(url, None, &remote).is_err());
+
+        let cached = Entry {
+            url: url.into(),
+            fetched: "2026-01-01T00:00:00+00:00".into(),
+            content: "tasks: {}\n".into(),
+            ran: Some("tasks: {}\n".into()),
+            ..Default::default()
+        };
+        save_entry(&entry_path(dir.path(), url), &cached).unwrap();
+        assert_eq!(fetch(url, None, &remote).unwrap(), "tasks: {}\n");
+    }
+
+    #[test]
+    fn error_answers_do_not_fall_back_to_the_cache() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/setup.yaml", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 0 && line != "\r\n" {
+                line.clear();
+            }
+            let mut stream = reader.into_inner();
+            write!(
+                stream,
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            )
+            .unwrap();
+        });
+
+        let dir = tempdir().unwrap();
+        let cached = Entry {
+            url: url.clone(),
+            fetched: "2026-01-01T00:00:00+00:00".into(),
+            content: "tasks: {}\n".into(),
+            ..Default::default()
+        };
+        save_entry(&entry_path(dir.path(), &url), &cached).unwrap();
+        let remote = Remote {
+            temp_dir: dir.path(),
+            on_change: OnChange::Read,
+        };
+        let err = fetch(&url, None, &remote).unwrap_err();
+        assert!(err.to_string().contains("HTTP 404"), "{err}");
+    }
+
+    #[test]
+    fn cache_age_is_rounded_to_the_largest_unit() {
+        let ago =
+            |minutes: i64| (chrono::Local::now() - chrono::Duration::minutes(minutes)).to_rfc3339();
+        assert_eq!(age(&ago(0)), "just now");
+        assert_eq!(age(&ago(1)), "1 minute ago");
+        assert_eq!(age(&ago(150)), "2 hours ago");
+        assert_eq!(age(&ago(3 * 1440 + 5)), "3 days ago");
+        assert_eq!(age("yesterday"), "at yesterday");
+    }
+}
@@ -0,0 +1,312 @@
+//! Config documents in git repositories —
+//! `git+<url>[@<ref>][#<path in the repository>]`.
+//!
+//! `-c git+ssh://git@host/dots.git@main#setup/machine.yaml` clones the
+//! repository into `temp_dir/git_cache/` (or fetches an existing checkout;
+//! there is one per repository and ref), checks out `<ref>` (a branch such
+//! as `feature/x`, a tag or a commit; default: the remote's default branch)
+//! and loads the document at `<path>` (default:
+//! `machine_setup.{yaml,yml,json}` at the root). The document then is a
+//! local file: its `config_dir` is its directory inside the checkout, so
+//! relative `copy` / `symlink` sources and `extends` / `include` work. When
+//! the remote is unreachable an existing checkout is used with a warning.
+//!
+//! Git sources are not checked against `#sha256=` pins or minisign
+//! signatures. A full commit id as `<ref>` pins the content instead: it is
+//! checked out as is (never as a branch of that name) and `HEAD` is verified
+//! to be that commit.
+
+use std::path::{Component, Path, PathBuf};
+use std::process::Command as StdCommand;
+
+use sha2::{Digest, Sha256};
+
+use crate::error::{Error, Result};
+
+/// Prefix marking a git repository Config source.
+pub const PREFIX: &str = "git+";
+/// Checkout directory inside `temp_dir`.
+pub const CACHE_DIR: &str = "git_cache";
+const DEFAULT_DOCUMENT: &str = "machine_setup";
+
+/// `git+<url>[@<ref>][#<path>]`, split.
+#[derive(Debug, PartialEq, Eq)]
+struct Source<'a> {
+    url: &'a str,
+    rev: Option<&'a str>,
+    path: Option<&'a str>,
+}
+
+pub fn is_git_source(s: &str) -> bool {
+    s.strip_prefix(PREFIX)
+        .is_some_and(|rest| rest.contains("://"))
+}
+
+fn parse(source: &str) -> Result<Source<'_>> {
+    let rest = source
+        .strip_prefix(PREFIX)
+        .ok_or_else(|| Error::Other(format!("`{source}` is not a `{PREFIX}` source")))?;
+    let (repo, path) = match rest.split_once('#') {
+        Some((repo, path)) => (repo, Some(path).filter(|p| !p.is_empty())),
+        None => (rest, None),
+    };
+    // The first `@` of the repository path separates the ref, which may
+    // contain `/`; one before the path is the user (`ssh://git@host/…`).
+    let path_start = repo
+        .find("://")
+        .and_then(|scheme| {
+            let host = scheme + 3;
+            repo[host..].find('/').map(|slash| host + slash)
+        })
+        .unwrap_or(repo.len());
+    let (url, rev) = match repo[path_start..].find('@') {
+        Some(at) => {
+            let at = path_start + at;
+            (&repo[..at], Some(&repo[at + 1..]))
+        }
+        None => (repo, None),
+    };
+    if url.starts_with('-') || rev.is_some_and(|r| r.starts_with('-')) {
+        return Err(Error::Other(format!(
+            "`{source}`: the URL and ref must not start with `-`"
+        )));
+    }
+    if let Some(path) = path {
+        let escapes = Path::new(path)
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir));
+        if escapes {
+            return Err(Error::Other(format!(
+                "`{source}`: the document path must stay inside the repository"
+            )));
+        }
+    }
+    Ok(Source {
+        url,
+        rev: rev.filter(|r| !r.is_empty()),
+        path,
+    })
+}
+
+/// Where `source` is checked out under `temp_dir`: one checkout per
+/// repository and ref, so documents at two refs can be loaded side by side.
+fn checkout_dir(source: &Source<'_>, temp_dir: &Path) -> PathBuf {
+    let mut hasher = Sha256::new();
+    hasher.update(source.url.as_bytes());
+    hasher.update([0]);
+    hasher.update(source.rev.unwrap_or_default().as_bytes());
+    let digest = format!("{:x}", hasher.finalize());
+    temp_dir.join(CACHE_DIR).join(&digest[..16])
+}
+
+/// The directory the document of `source` resolves relative paths against,
+/// without touching the network.
+pub fn config_dir(source: &str, temp_dir: &Path) -> Result<PathBuf> {
+    let parsed = parse(source)?;
+    let checkout = checkout_dir(&parsed, temp_dir);
+    Ok(match parsed.path.and_then(|p| Path::new(p).parent()) {
+        Some(dir) => checkout.join(dir),
+        None => checkout,
+    })
+}
+
+/// Clone or fetch `source` under `temp_dir`, check out its ref, and return
+/// the path of its document inside the checkout.
+pub fn checkout(source: &str, temp_dir: &Path) -> Result<PathBuf> {
+    let parsed = parse(source)?;
+    let dir = checkout_dir(&parsed, temp_dir);
+
+    if dir.join(".git").exists() {
+        if let Err(e) = git(&dir, &["fetch", "--quiet", "--tags", "--force", "origin"]) {
+            eprintln!(
+                "warning: {e}; using the checkout of {} in {}",
+                parsed.url,
+                dir.display()
+            );
+        }
+    } else {
+        if let Some(parent) = dir.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let target = dir.to_string_lossy();
+        git(
+            temp_dir,
+            &[
+                "clone",
+                "--quiet",
+                "--no-checkout",
+                "--",
+                parsed.url,
+                &target,
+            ],
+        )?;
+    }
+
+    let target = match parsed.rev {
+        Some(rev) if is_commit(rev) => rev.to_string(),
+        // A branch follows the remote; a tag or commit is taken as is.
+        Some(rev) => {
+            let branch = format!("origin/{rev}");
+            if git(&dir, &["rev-parse", "--verify", "--quiet", &branch]).is_ok() {
+                branch
+            } else {
+                rev.to_string()
+            }
+        }
+        None => "origin/HEAD".to_string(),
+    };
+    git(
+        &dir,
+        &["checkout", "--quiet", "--force", "--detach", &target],
+    )?;
+    if let Some(commit) = parsed.rev.filter(|rev| is_commit(rev)) {
+        let head = git(&dir, &["rev-parse", "HEAD"])?;
+        if !head.eq_ignore_ascii_case(commit) {
+            return Err(Error::Verification(format!(
+                "{}: checked out {head}, not the pinned commit {commit}",
+                parsed.url
+            )));
+        }
+    }
+
+    let document = dir.join(parsed.path.unwrap_or(DEFAULT_DOCUMENT));
+    super::resolve_config_path(&document)
+}
+
+/// A full commit id (SHA-1 or SHA-256), not a branch or tag name.
+fn is_commit(rev: &str) -> bool {
+    matches!(rev.len(), 40 | 64) && rev.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Run git in `cwd`; its trimmed stdout.
+fn git(cwd: &Path, args: &[&str]) -> Result<String> {
+    let output = StdCommand::new("git")
+        .args(args)
+        .current_dir(cwd)
+        .output()
+        .map_err(|e| Error::GitFailed(format!("Failed to spawn git: {e}")))?;
+    if !output.status.success() {
+        return Err(Error::GitFailed(format!(
+            "git {} exited with code {}: {}",
+            args.join(" "),
+            output.status.code().unwrap_or(-1),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn run_git(cwd: &Path, args: &[&str]) {
+        let status = StdCommand::new("git")
+            .args(args)
+            .current_dir(cwd)
+            .env("GIT_AUTHOR_NAME", "t")
+            .env("GIT_AUTHOR_EMAIL", "t@example.com")
+            .env("GIT_COMMITTER_NAME", "t")
+            .env("GIT_COMMITTER_EMAIL", "t@example.com")
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {args:?}");
+    }
+
+    /// A bare repository with `setup/machine.yaml` on `main`, tagged `v1`
+    /// and branched as `feature/x`, then changed; returns its `git+file://` URL.
+    fn bare_repo(root: &Path) -> String {
+        let work = root.join("work");
+        std::fs::create_dir_all(work.join("setup")).unwrap();
+        run_git(&work, &["init", "--quiet", "--initial-branch=main"]);
+        std::fs::write(
+            work.join("setup/machine.yaml"),
+            "tasks: {v1: {commands: []}}\n",
+        )
+        .unwrap();
+        run_git(&work, &["add", "-A"]);
+        run_git(&work, &["commit", "--quiet", "-m", "v1"]);
+        run_git(&work, &["tag", "v1"]);
+        run_git(&work, &["branch", "feature/x"]);
+        std::fs::write(
+            work.join("setup/machine.yaml"),
+            "tasks: {v2: {commands: []}}\n",
+        )
+        .unwrap();
+        run_git(&work, &["commit", "--quiet", "-am", "v2"]);
+        let bare = root.join("dots.git");
+        run_git(
+            root,
+            &[
+                "clone",
+                "--quiet",
+                "--bare",
+                &work.to_string_lossy(),
+                &bare.to_string_lossy(),
+            ],
+        );
+        format!("{PREFIX}file://{}", bare.display())
+    }
+
+    #[test]
+    fn source_is_split_into_url_ref_and_path() {
+        assert_eq!(
+            parse("git+ssh://git@host/dots.git@v1.2#setup/machine.yaml").unwrap(),
+            Source {
+                url: "ssh://git@host/dots.git",
+                rev: Some("v1.2"),
+                path: Some("setup/machine.yaml"),
+            }
+        );
+        assert_eq!(
+            parse("git+https://host/dots.git").unwrap(),
+            Source {
+                url: "https://host/dots.git",
+                rev: None,
+                path: None,
+            }
+        );
+        assert_eq!(
+            parse("git+ssh://git@host/dots.git@feature/x#machine.yaml").unwrap(),
+            Source {
+                url: "ssh://git@host/dots.git",
+                rev: Some("feature/x"),
+                path: Some("machine.yaml"),
+            }
+        );
+        assert!(parse("git+https://host/dots.git#../../etc/passwd").is_err());
+        assert!(parse("git+https://host/dots.git@--upload-pack=evil").is_err());
+        assert!(parse("git+--upload-pack=x://host/dots.git").is_err());
+        assert!(is_git_source("git+file:///tmp/dots.git"));
+        assert!(!is_git_source("https://host/dots.git"));
+    }
+
+    #[test]
+    fn checkout_follows_the_branch_and_pins_a_tag() {
+        let dir = tempdir().unwrap();
+        let url = bare_repo(dir.path());
+        let temp = dir.path().join("temp");
+
+        let latest = checkout(&format!("{url}#setup/machine.yaml"), &temp).unwrap();
+        assert!(std::fs::read_to_string(&latest).unwrap().contains("v2"));
+        assert_eq!(
+            latest.parent().unwrap(),
+            config_dir(&format!("{url}#setup/machine.yaml"), &temp).unwrap()
+        );
+
+        let tagged = checkout(&format!("{url}@v1#setup/machine"), &temp).unwrap();
+        assert!(std::fs::read_to_string(&tagged).unwrap().contains("v1"));
+        // Each ref has its own checkout; the latest one is left as it was.
+        assert_ne!(tagged.parent(), latest.parent());
+        assert!(std::fs::read_to_string(&latest).unwrap().contains("v2"));
+
+        let branch = checkout(&format!("{url}@feature/x#setup/machine.yaml"), &temp).unwrap();
+        assert!(std::fs::read_to_string(&branch).unwrap().contains("v1"));
+
+        let v1 = git(&dir.path().join("work"), &["rev-parse", "v1"]).unwrap();
+        let pinned = checkout(&format!("{url}@{v1}#setup/machine.yaml"), &temp).unwrap();
+        assert!(std::fs::read_to_string(&pinned).unwrap().contains("v1"));
+        assert!(checkout(&format!("{url}@main#missing.yaml"), &temp).is_err());
+    }
+}
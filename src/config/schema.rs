@@ -46,6 +46,33 @@ pub fn generate() -> Value {
                 "type": "boolean",
                 "default": true,
                 "description": "When false, skip the post-command self update-check notice"
+            },
+            "id": {
+                "type": "string",
+                "description": "Identity of this Config document in History (default: its canonical path or URL); keeps its History when the file moves"
+            },
+            "extends": {
+                "type": "string",
+                "description": "Config document to start from; settings and Task fields written here override it"
+            },
+            "include": {
+                "$ref": "#/$defs/stringOrVec",
+                "description": "Config documents whose Tasks are added; a Task name defined twice is an error"
+            },
+            "configs": {
+                "type": "array",
+                "items": {
+                    "oneOf": [
+                        { "type": "string" },
+                        {
+                            "type": "object",
+                            "minProperties": 1,
+                            "maxProperties": 1,
+                            "additionalProperties": { "type": "string" }
+                        }
+                    ]
+                },
+                "description": "Workspace file: Config documents (path, URL or git source, optionally `name: source`) run together with namespaced Tasks; `tasks` stays empty"
             }
         },
         "$defs": {
@@ -63,7 +90,7 @@ pub fn generate() -> Value {
             },
             "task": {
                 "type": "object",
-                "required": ["commands"],
+                "description": "A Task; `commands` is required unless it overrides a Task of the `extends` base",
                 "additionalProperties": false,
                 "properties": {
                     "commands": {
@@ -76,7 +103,8 @@ pub fn generate() -> Value {
                     "skip_if": { "$ref": "#/$defs/stringOrVec" },
                     "depends_on": {
                         "type": "array",
-                        "items": { "type": "string" }
+                        "items": { "type": "string" },
+                        "description": "Tasks to run first: a Task name, or `./document.yaml#task` for a Task of another Config document."
                     },
                     "retry": { "type": "integer", "minimum": 0, "default": 0 },
                     "auto_update": { "$ref": "#/$defs/autoUpdate" }
@@ -132,7 +160,40 @@ fn kind_args_schema(kind: &str) -> Value {
                 "src": { "type": "string" },
                 "target": { "type": "string" },
                 "ignore": { "type": "array", "items": { "type": "string" } },
-                "sudo": { "type": "boolean", "default": false }
+                "sudo": { "type": "boolean", "default": false },
+                "mode": {
+                    "type": "string",
+                    "pattern": "^(0o)?[0-7]{3,4}$",
+                    "description": "Octal mode for copied files, e.g. \"0600\""
+                },
+                "dir_mode": {
+                    "type": "string",
+                    "pattern": "^(0o)?[0-7]{3,4}$",
+                    "description": "Octal mode for the directories of a copied tree, e.g. \"0700\""
+                },
+                "owner": { "type": "string", "description": "Owner for copied paths (requires sudo)" },
+                "group": { "type": "string", "description": "Group for copied paths (requires sudo)" },
+                "prune": {
+                    "type": "boolean",
+                    "default": false,
+                    "description": "On install/update, remove previously copied files whose source is gone"
+                },
+                "compare": {
+                    "type": "string",
+                    "enum": ["mtime", "hash"],
+                    "default": "mtime",
+                    "description": "Skip unchanged destinations by size and mtime, or by content"
+                },
+                "force": {
+                    "type": "boolean",
+                    "default": false,
+                    "description": "Replace changed destinations this entry did not copy"
+                },
+                "backup": {
+                    "type": "boolean",
+                    "default": false,
+                    "description": "With force, move replaced files into a backup under temp_dir; uninstall restores them"
+                }
             }
         }),
         "symlink" => json!({
@@ -144,7 +205,32 @@ fn kind_args_schema(kind: &str) -> Value {
                 "target": { "type": "string" },
                 "ignore": { "type": "array", "items": { "type": "string" } },
                 "force": { "type": "boolean", "default": false },
-                "sudo": { "type": "boolean", "default": false }
+                "backup": {
+                    "type": "boolean",
+                    "default": false,
+                    "description": "With force, move replaced files into a backup under temp_dir; uninstall restores them"
+                },
+                "fold": {
+                    "type": "boolean",
+                    "default": false,
+                    "description": "Link the highest directory missing on the target side (GNU stow folding)"
+                },
+                "relative": {
+                    "type": "boolean",
+                    "default": false,
+                    "description": "Store link targets relative to the link's directory instead of absolute"
+                },
+                "prune": {
+                    "type": "boolean",
+                    "default": false,
+                    "description": "On install/update, remove links this entry made whose source is gone"
+                },
+                "sudo": { "type": "boolean", "default": false },
+                "dir_mode": {
+                    "type": "string",
+                    "pattern": "^(0o)?[0-7]{3,4}$",
+                    "description": "Octal mode for the real directories the link tree creates"
+                }
             }
         }),
         "clone" => json!({
@@ -177,8 +263,122 @@ fn kind_args_schema(kind: &str) -> Value {
             "required": ["config"],
             "additionalProperties": false,
             "properties": {
-                "config": { "type": "string" },
-                "task": { "type": "string" }
+                "config": {
+                    "type": "string",
+                    "description": "Path, http(s) URL, or git+<url>[@<ref>][#<path>] of the sub-config"
+                },
+                "task": { "type": "string" },
+                "tasks": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Tasks to run, plus their dependencies (default: all)"
+                },
+                "force": {
+                    "oneOf": [
+                        { "type": "boolean" },
+                        { "type": "string", "enum": ["inherit"] }
+                    ],
+                    "default": "inherit",
+                    "description": "History bypass: the parent run's --force (inherit), or fixed"
+                },
+                "env": {
+                    "type": "object",
+                    "description": "Environment variables for the sub-config's run commands",
+                    "additionalProperties": { "type": "string" }
+                },
+                "vars": {
+                    "type": "object",
+                    "description": "Variables for the sub-config's template entries",
+                    "additionalProperties": { "type": "string" }
+                },
+                "optional": {
+                    "type": "boolean",
+                    "default": false,
+                    "description": "Skip when the local sub-config does not exist"
+                }
+            }
+        }),
+        "template" => json!({
+            "type": "object",
+            "required": ["src", "target"],
+            "additionalProperties": false,
+            "properties": {
+                "src": { "type": "string" },
+                "target": { "type": "string" },
+                "vars": {
+                    "type": "object",
+                    "description": "Template variables (override facts hostname, os, user, home)",
+                    "additionalProperties": { "type": "string" }
+                },
+                "mode": {
+                    "type": "string",
+                    "pattern": "^(0o)?[0-7]{3,4}$",
+                    "description": "Octal file mode for rendered files, e.g. \"0600\""
+                },
+                "owner": { "type": "string", "description": "Owner for rendered files (requires sudo)" },
+                "group": { "type": "string", "description": "Group for rendered files (requires sudo)" },
+                "sudo": { "type": "boolean", "default": false }
+            }
+        }),
+        "line_in_file" => json!({
+            "type": "object",
+            "required": ["path", "line"],
+            "additionalProperties": false,
+            "properties": {
+                "path": { "type": "string" },
+                "line": { "type": "string" },
+                "regex": {
+                    "type": "string",
+                    "description": "Replace the last matching line (present) or remove matches (absent)"
+                },
+                "state": { "type": "string", "enum": ["present", "absent"], "default": "present" },
+                "sudo": { "type": "boolean", "default": false }
+            }
+        }),
+        "block_in_file" => json!({
+            "type": "object",
+            "required": ["path", "name", "content"],
+            "additionalProperties": false,
+            "properties": {
+                "path": { "type": "string" },
+                "name": { "type": "string", "description": "Block name used in the marker lines" },
+                "content": { "type": "string" },
+                "comment": { "type": "string", "default": "#", "description": "Marker comment prefix" },
+                "sudo": { "type": "boolean", "default": false }
+            }
+        }),
+        "package" => json!({
+            "type": "object",
+            "description": "Package names per manager family; the first detected manager's list is used",
+            "additionalProperties": false,
+            "properties": {
+                "apt": { "type": "array", "items": { "type": "string" } },
+                "dnf": { "type": "array", "items": { "type": "string" } },
+                "pacman": { "type": "array", "items": { "type": "string" } },
+                "brew": { "type": "array", "items": { "type": "string" } },
+                "apk": { "type": "array", "items": { "type": "string" } },
+                "winget": { "type": "array", "items": { "type": "string" } },
+                "choco": { "type": "array", "items": { "type": "string" } }
+            }
+        }),
+        "archive" => json!({
+            "type": "object",
+            "required": ["src", "target"],
+            "additionalProperties": false,
+            "properties": {
+                "src": {
+                    "type": "string",
+                    "description": "Local path or http(s) URL of a .tar.gz, .tar.xz or .zip"
+                },
+                "target": { "type": "string" },
+                "strip_components": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "default": 0,
+                    "description": "Leading path components dropped from every archive member"
+                },
+                "ignore": { "type": "array", "items": { "type": "string" } },
+                "sudo": { "type": "boolean", "default": false }
             }
         }),
         other => panic!("KIND_KEYS out of sync with kind_args_schema: {other}"),
@@ -209,6 +409,14 @@ mod tests {
         }
     }
 
+    #[test]
+    fn schema_allows_extends_and_include() {
+        let schema = generate();
+        assert!(schema["properties"]["extends"].is_object());
+        assert!(schema["properties"]["include"].is_object());
+        assert!(schema["properties"]["configs"].is_object());
+    }
+
     #[test]
     fn schema_requires_tasks() {
         let schema = generate();
@@ -18,12 +18,14 @@ pub struct TaskStatusRow<'a> {
     pub history: Option<&'a TaskHistory>,
 }
 
-/// Full doctor report: status rows, validate issues, orphan History keys.
+/// Full doctor report: status rows, validate issues, orphan History keys,
+/// other documents' installed Tasks.
 #[derive(Debug)]
 pub struct DoctorReport<'a> {
     pub rows: Vec<TaskStatusRow<'a>>,
     pub issues: Vec<ValidationIssue>,
     pub orphans: Vec<String>,
+    pub others: Vec<String>,
 }
 
 impl DoctorReport<'_> {
@@ -64,6 +66,26 @@ pub fn orphan_history_names(config: &AppConfig, history: &History) -> Vec<String
     names
 }
 
+/// Installed Tasks other Config documents recorded in the same History, one
+/// `document: task, task` line per document.
+pub fn other_documents(history: &History) -> Vec<String> {
+    let unassigned = ("(no document)".to_string(), history.unassigned.clone());
+    history
+        .others
+        .iter()
+        .map(|(document, entries)| (document.clone(), entries.clone()))
+        .chain(std::iter::once(unassigned))
+        .filter_map(|(document, entries)| {
+            let installed: Vec<&str> = entries
+                .iter()
+                .filter(|(_, entry)| entry.installed_at.is_some())
+                .map(|(name, _)| name.as_str())
+                .collect();
+            (!installed.is_empty()).then(|| format!("{document}: {}", installed.join(", ")))
+        })
+        .collect()
+}
+
 /// Remove orphan History entries. Returns the names removed.
 pub fn prune_orphans(history: &mut History, config: &AppConfig) -> Vec<String> {
     let orphans = orphan_history_names(config, history);
@@ -83,6 +105,7 @@ pub fn doctor<'a>(
         rows: rows(config, history),
         issues: validate::validate_config(config, config_dir),
         orphans: orphan_history_names(config, history),
+        others: other_documents(history),
     }
 }
 
@@ -136,6 +159,10 @@ mod tests {
             parallel: false,
             num_threads: None,
             check_for_updates: true,
+            origins: Default::default(),
+            id: None,
+            history_scopes: Vec::new(),
+            remote_documents: Vec::new(),
         }
     }
 
@@ -2,6 +2,7 @@ use super::os::OsFilter;
 use indexmap::IndexMap;
 use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 /// Root configuration structure.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,9 +27,84 @@ pub struct AppConfig {
     /// When false, skip the post-command self update-check notice (default true).
     #[serde(default = "default_true")]
     pub check_for_updates: bool,
+
+    /// Stable identity of this document in History (default: its canonical
+    /// path or URL). Keeps install state when the document moves.
+    #[serde(default)]
+    pub id: Option<String>,
+
+    /// Where Tasks from other documents (`extends`, `include`,
+    /// `./langs.yaml#rust`) were defined; Tasks of this document have no entry.
+    #[serde(skip)]
+    pub origins: HashMap<String, TaskOrigin>,
+
+    /// Which History document each Task is recorded under; set by the
+    /// loader. Empty for a config that was not loaded from a document.
+    #[serde(skip)]
+    pub history_scopes: Vec<HistoryScope>,
+
+    /// URL documents this config was loaded from, recorded as run once a
+    /// run of them succeeds (see [`crate::config::remote::record_run`]).
+    #[serde(skip)]
+    pub remote_documents: Vec<RemoteDocument>,
+}
+
+impl AppConfig {
+    /// Directory relative paths of Task `name` resolve against: its own
+    /// document's for linked Tasks, `config_dir` otherwise.
+    pub fn task_config_dir<'a>(&'a self, name: &str, config_dir: &'a Path) -> &'a Path {
+        self.origins
+            .get(name)
+            .map_or(config_dir, |origin| origin.config_dir.as_path())
+    }
+
+    /// `default_shell` of the document Task `name` was defined in.
+    pub fn task_default_shell(&self, name: &str) -> &Shell {
+        self.origins
+            .get(name)
+            .map_or(&self.default_shell, |origin| &origin.default_shell)
+    }
+}
+
+/// The content of a URL document as it was fetched and approved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteDocument {
+    pub url: String,
+    pub content: String,
+}
+
+/// Tasks named `prefix` + `name` are `name` in History `document`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryScope {
+    pub prefix: String,
+    pub document: String,
+}
+
+/// The document a Task of another document comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskOrigin {
+    /// Canonical path of the document.
+    pub document: PathBuf,
+    pub config_dir: PathBuf,
+    pub default_shell: Shell,
+    pub via: Via,
+}
+
+/// How a Task of another document joined the loaded config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Via {
+    /// Named by a cross-document `depends_on` (`./langs.yaml#rust`).
+    Dependency,
+    /// Inherited through `extends`.
+    Extends,
+    /// Added by `include`.
+    Include,
+    /// A document of a workspace (`configs:`, repeated `-c`).
+    Workspace,
 }
 
-fn default_temp_dir() -> String {
+/// `temp_dir` when a document sets none.
+pub fn default_temp_dir() -> String {
     "~/.machine_setup".to_string()
 }
 
@@ -120,6 +196,11 @@ pub enum CommandEntry {
     Clone(CloneArgs),
     Run(RunArgs),
     MachineSetup(MachineSetupArgs),
+    Template(TemplateArgs),
+    LineInFile(LineInFileArgs),
+    BlockInFile(BlockInFileArgs),
+    Package(PackageArgs),
+    Archive(ArchiveArgs),
 }
 
 impl<'de> Deserialize<'de> for CommandEntry {
@@ -164,6 +245,31 @@ impl<'de> Deserialize<'de> for CommandEntry {
                     serde_yaml::from_value(value).map_err(serde::de::Error::custom)?;
                 Ok(CommandEntry::MachineSetup(args))
             }
+            "template" => {
+                let args: TemplateArgs =
+                    serde_yaml::from_value(value).map_err(serde::de::Error::custom)?;
+                Ok(CommandEntry::Template(args))
+            }
+            "line_in_file" => {
+                let args: LineInFileArgs =
+                    serde_yaml::from_value(value).map_err(serde::de::Error::custom)?;
+                Ok(CommandEntry::LineInFile(args))
+            }
+            "block_in_file" => {
+                let args: BlockInFileArgs =
+                    serde_yaml::from_value(value).map_err(serde::de::Error::custom)?;
+                Ok(CommandEntry::BlockInFile(args))
+            }
+            "package" => {
+                let args: PackageArgs =
+                    serde_yaml::from_value(value).map_err(serde::de::Error::custom)?;
+                Ok(CommandEntry::Package(args))
+            }
+            "archive" => {
+                let args: ArchiveArgs =
+                    serde_yaml::from_value(value).map_err(serde::de::Error::custom)?;
+                Ok(CommandEntry::Archive(args))
+            }
             other => Err(serde::de::Error::custom(format!(
                 "Unknown command type: {other}"
             ))),
@@ -216,14 +322,85 @@ impl std::fmt::Display for RunArgs {
 impl std::fmt::Display for MachineSetupArgs {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "machine_setup: {}", self.config)?;
-        if let Some(task) = &self.task {
-            write!(f, " (task: {task})")?;
+        let tasks: Vec<&str> = self
+            .task
+            .iter()
+            .chain(&self.tasks)
+            .map(String::as_str)
+            .collect();
+        match tasks.as_slice() {
+            [] => {}
+            [task] => write!(f, " (task: {task})")?,
+            tasks => write!(f, " (tasks: {})", tasks.join(", "))?,
         }
         Ok(())
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl std::fmt::Display for TemplateArgs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let prefix = if self.sudo {
+            "template (sudo)"
+        } else {
+            "template"
+        };
+        write!(f, "{prefix}: {} -> {}", self.src, self.target)
+    }
+}
+
+impl std::fmt::Display for LineInFileArgs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let prefix = if self.sudo {
+            "line_in_file (sudo)"
+        } else {
+            "line_in_file"
+        };
+        let verb = match self.state {
+            LineState::Present => "ensure",
+            LineState::Absent => "remove",
+        };
+        write!(f, "{prefix}: {verb} {:?} in {}", self.line, self.path)
+    }
+}
+
+impl std::fmt::Display for BlockInFileArgs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let prefix = if self.sudo {
+            "block_in_file (sudo)"
+        } else {
+            "block_in_file"
+        };
+        write!(f, "{prefix}: {} in {}", self.name, self.path)
+    }
+}
+
+impl std::fmt::Display for ArchiveArgs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let prefix = if self.sudo {
+            "archive (sudo)"
+        } else {
+            "archive"
+        };
+        write!(f, "{prefix}: {} -> {}", self.src, self.target)
+    }
+}
+
+impl std::fmt::Display for PackageArgs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let families: Vec<String> = self
+            .families()
+            .filter(|(_, packages)| !packages.is_empty())
+            .map(|(family, packages)| format!("{family}: {}", packages.join(" ")))
+            .collect();
+        if families.is_empty() {
+            write!(f, "package: (none)")
+        } else {
+            write!(f, "package: {}", families.join(" | "))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CopyArgs {
     pub src: String,
     pub target: String,
@@ -231,9 +408,53 @@ pub struct CopyArgs {
     pub ignore: Vec<String>,
     #[serde(default)]
     pub sudo: bool,
+
+    /// Octal mode for copied files, e.g. `"0600"`
+    #[serde(default)]
+    pub mode: Option<String>,
+
+    /// Octal mode for the directories of a copied tree, e.g. `"0700"`
+    #[serde(default)]
+    pub dir_mode: Option<String>,
+
+    /// Owner for copied files and directories (requires `sudo`)
+    #[serde(default)]
+    pub owner: Option<String>,
+
+    /// Group for copied files and directories (requires `sudo`)
+    #[serde(default)]
+    pub group: Option<String>,
+
+    /// On install/update, remove previously copied files whose source is gone
+    #[serde(default)]
+    pub prune: bool,
+
+    /// How an existing destination is judged unchanged (and skipped)
+    #[serde(default)]
+    pub compare: CopyCompare,
+
+    /// Replace changed destinations this entry did not copy
+    #[serde(default)]
+    pub force: bool,
+
+    /// With `force`, move replaced files into a backup under `temp_dir`
+    /// instead of overwriting them; uninstall restores them
+    #[serde(default)]
+    pub backup: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Change detection for `copy`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CopyCompare {
+    /// Same size and the destination is not older than the source.
+    #[default]
+    Mtime,
+    /// Same content, whatever the timestamps say.
+    Hash,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SymlinkArgs {
     pub src: String,
     pub target: String,
@@ -241,8 +462,26 @@ pub struct SymlinkArgs {
     pub ignore: Vec<String>,
     #[serde(default)]
     pub force: bool,
+    /// With `force`, move replaced files into a backup under `temp_dir`
+    /// instead of deleting them; uninstall restores them
+    #[serde(default)]
+    pub backup: bool,
+    /// Link the highest directory missing on the target side instead of every
+    /// file (GNU stow folding); unfolded when another source shares it
+    #[serde(default)]
+    pub fold: bool,
+    /// Store link targets relative to the link's directory instead of absolute
+    #[serde(default)]
+    pub relative: bool,
+    /// On install/update, remove links this entry made whose source is gone
+    #[serde(default)]
+    pub prune: bool,
     #[serde(default)]
     pub sudo: bool,
+
+    /// Octal mode for the real directories the link tree creates, e.g. `"0700"`
+    #[serde(default)]
+    pub dir_mode: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -326,10 +565,223 @@ impl RunArgs {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct MachineSetupArgs {
     pub config: String,
     pub task: Option<String>,
+
+    /// Tasks to run, plus their transitive dependencies; all Tasks when empty
+    #[serde(default)]
+    pub tasks: Vec<String>,
+
+    /// History bypass for the sub-config: the parent run's `--force`, or fixed
+    #[serde(default)]
+    pub force: SubConfigForce,
+
+    /// Environment variables for the sub-config's `run` commands
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+
+    /// Variables for the sub-config's `template` entries
+    #[serde(default)]
+    pub vars: IndexMap<String, String>,
+
+    /// Skip instead of failing when the (local) sub-config does not exist
+    #[serde(default)]
+    pub optional: bool,
+}
+
+/// `machine_setup` `force`: `inherit` (default), `true` or `false`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SubConfigForce {
+    #[default]
+    Inherit,
+    Always,
+    Never,
+}
+
+impl SubConfigForce {
+    /// The `force` the nested Runner runs with.
+    pub fn resolve(self, parent: bool) -> bool {
+        match self {
+            SubConfigForce::Inherit => parent,
+            SubConfigForce::Always => true,
+            SubConfigForce::Never => false,
+        }
+    }
+}
+
+impl Serialize for SubConfigForce {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            SubConfigForce::Inherit => serializer.serialize_str("inherit"),
+            SubConfigForce::Always => serializer.serialize_bool(true),
+            SubConfigForce::Never => serializer.serialize_bool(false),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for SubConfigForce {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Inner {
+            Bool(bool),
+            Word(String),
+        }
+
+        match Inner::deserialize(deserializer)? {
+            Inner::Bool(true) => Ok(SubConfigForce::Always),
+            Inner::Bool(false) => Ok(SubConfigForce::Never),
+            Inner::Word(word) if word == "inherit" => Ok(SubConfigForce::Inherit),
+            Inner::Word(word) => Err(serde::de::Error::custom(format!(
+                "force must be true, false or \"inherit\", got {word:?}"
+            ))),
+        }
+    }
+}
+
+/// Template command arguments: render `src` with variables into `target`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateArgs {
+    pub src: String,
+    pub target: String,
+
+    /// Template variables; override facts (`hostname`, `os`, `user`, `home`) of the same name
+    #[serde(default)]
+    pub vars: IndexMap<String, String>,
+
+    /// Octal file mode for the rendered file, e.g. `"0600"`
+    #[serde(default)]
+    pub mode: Option<String>,
+
+    /// Owner for rendered files (requires `sudo`)
+    #[serde(default)]
+    pub owner: Option<String>,
+
+    /// Group for rendered files (requires `sudo`)
+    #[serde(default)]
+    pub group: Option<String>,
+
+    #[serde(default)]
+    pub sudo: bool,
+}
+
+/// Whether a `line_in_file` line should exist in the file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineState {
+    #[default]
+    Present,
+    Absent,
+}
+
+/// Ensure a single line is present (optionally replacing a regex match) or absent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LineInFileArgs {
+    pub path: String,
+    pub line: String,
+
+    /// Lines matching this regex are replaced by `line` (present) or removed (absent)
+    #[serde(default)]
+    pub regex: Option<String>,
+
+    #[serde(default)]
+    pub state: LineState,
+
+    #[serde(default)]
+    pub sudo: bool,
+}
+
+/// Manage a named, marker-delimited block of lines in a file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockInFileArgs {
+    pub path: String,
+
+    /// Block name used in the begin/end marker lines
+    pub name: String,
+
+    /// Block body (without markers)
+    pub content: String,
+
+    /// Comment prefix for the marker lines (default `#`)
+    #[serde(default = "default_comment")]
+    pub comment: String,
+
+    #[serde(default)]
+    pub sudo: bool,
+}
+
+fn default_comment() -> String {
+    "#".to_string()
+}
+
+/// Package names per package-manager family; the detected manager's list is used.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PackageArgs {
+    #[serde(default)]
+    pub apt: Vec<String>,
+    #[serde(default)]
+    pub dnf: Vec<String>,
+    #[serde(default)]
+    pub pacman: Vec<String>,
+    #[serde(default)]
+    pub brew: Vec<String>,
+    #[serde(default)]
+    pub apk: Vec<String>,
+    #[serde(default)]
+    pub winget: Vec<String>,
+    #[serde(default)]
+    pub choco: Vec<String>,
+}
+
+impl PackageArgs {
+    /// `(family key, packages)` in the stable family order used for detection.
+    pub fn families(&self) -> impl Iterator<Item = (&'static str, &[String])> {
+        [
+            ("apt", self.apt.as_slice()),
+            ("brew", self.brew.as_slice()),
+            ("dnf", self.dnf.as_slice()),
+            ("pacman", self.pacman.as_slice()),
+            ("apk", self.apk.as_slice()),
+            ("winget", self.winget.as_slice()),
+            ("choco", self.choco.as_slice()),
+        ]
+        .into_iter()
+    }
+}
+
+/// Extract a `.tar.gz`, `.tar.xz` or `.zip` (local path or URL) into `target`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveArgs {
+    /// Local archive path or `http(s)://` URL
+    pub src: String,
+    pub target: String,
+
+    /// Leading path components dropped from every archive member
+    #[serde(default)]
+    pub strip_components: usize,
+
+    #[serde(default)]
+    pub ignore: Vec<String>,
+
+    #[serde(default)]
+    pub sudo: bool,
+}
+
+/// Parse an octal file mode such as `"0644"` or `"755"`.
+pub fn parse_file_mode(mode: &str) -> Option<u32> {
+    let digits = mode.trim().trim_start_matches("0o");
+    if digits.is_empty() || digits.len() > 4 {
+        return None;
+    }
+    u32::from_str_radix(digits, 8).ok().filter(|m| *m <= 0o7777)
 }
 
 /// A value that can be a single string or a list of strings.
@@ -433,6 +885,21 @@ tasks:
         assert!(!task.parallel);
     }
 
+    #[test]
+    fn test_machine_setup_force_accepts_bool_or_inherit() {
+        let parse = |force: &str| {
+            serde_yaml::from_str::<MachineSetupArgs>(&format!("config: sub.yaml\nforce: {force}"))
+                .map(|args| args.force)
+        };
+        assert_eq!(parse("true").unwrap(), SubConfigForce::Always);
+        assert_eq!(parse("false").unwrap(), SubConfigForce::Never);
+        assert_eq!(parse("inherit").unwrap(), SubConfigForce::Inherit);
+        assert!(parse("sometimes").is_err());
+        let args: MachineSetupArgs = serde_yaml::from_str("config: sub.yaml").unwrap();
+        assert_eq!(args.force, SubConfigForce::Inherit);
+        assert!(args.force.resolve(true));
+    }
+
     #[test]
     fn test_string_or_vec_single() {
         let val: StringOrVec = serde_yaml::from_str(r#""hello""#).unwrap();
@@ -509,7 +976,40 @@ commands: "echo $MY_VAR"
             target: "~/dest".to_string(),
             ignore: vec![],
             sudo: false,
+            ..Default::default()
         });
         assert_eq!(format!("{entry}"), "copy: ./src -> ~/dest");
     }
+
+    #[test]
+    fn test_parse_template_entry() {
+        let yaml = r#"
+template:
+  src: "./gitconfig.tmpl"
+  target: "~/.gitconfig"
+  vars:
+    email: "me@example.com"
+  mode: "0600"
+"#;
+        let entry: CommandEntry = serde_yaml::from_str(yaml).unwrap();
+        let CommandEntry::Template(args) = &entry else {
+            panic!("expected template entry");
+        };
+        assert_eq!(args.vars.get("email").unwrap(), "me@example.com");
+        assert_eq!(args.mode.as_deref(), Some("0600"));
+        assert!(!args.sudo);
+        assert_eq!(
+            format!("{entry}"),
+            "template: ./gitconfig.tmpl -> ~/.gitconfig"
+        );
+    }
+
+    #[test]
+    fn test_parse_file_mode() {
+        assert_eq!(parse_file_mode("0600"), Some(0o600));
+        assert_eq!(parse_file_mode("755"), Some(0o755));
+        assert_eq!(parse_file_mode("0o644"), Some(0o644));
+        assert_eq!(parse_file_mode("0999"), None);
+        assert_eq!(parse_file_mode(""), None);
+    }
 }
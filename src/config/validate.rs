@@ -1,7 +1,8 @@
 use std::path::Path;
 
+use super::document::path_for_document;
 use super::graph::TaskGraph;
-use super::types::AppConfig;
+use super::types::{AppConfig, Via};
 use crate::engine::commands::catalog::{self, KindSeverity};
 
 #[derive(Debug)]
@@ -85,8 +86,9 @@ pub fn validate_config(config: &AppConfig, config_dir: &Path) -> Vec<ValidationI
             });
         }
 
+        let task_dir = config.task_config_dir(name, config_dir);
         for cmd in &task.commands {
-            for kind_issue in catalog::validate_entry(cmd, config_dir) {
+            for kind_issue in catalog::validate_entry(cmd, task_dir) {
                 issues.push(ValidationIssue {
                     task_name: name.clone(),
                     message: kind_issue.message,
@@ -115,9 +117,27 @@ pub fn validate_config(config: &AppConfig, config_dir: &Path) -> Vec<ValidationI
         }
     }
 
+    name_origins(config, config_dir, &mut issues);
     issues
 }
 
+/// Say which document a composed Task (`extends` / `include`) came from.
+fn name_origins(config: &AppConfig, config_dir: &Path, issues: &mut [ValidationIssue]) {
+    let config_dir = config_dir
+        .canonicalize()
+        .unwrap_or_else(|_| config_dir.to_path_buf());
+    for issue in issues {
+        let Some(origin) = config.origins.get(&issue.task_name) else {
+            continue;
+        };
+        if origin.via != Via::Dependency {
+            let document =
+                path_for_document(&origin.document, &config_dir, dirs::home_dir().as_deref());
+            issue.message.push_str(&format!(" (from {document})"));
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -134,6 +154,10 @@ mod tests {
             parallel: false,
             num_threads: None,
             check_for_updates: true,
+            origins: Default::default(),
+            id: None,
+            history_scopes: Vec::new(),
+            remote_documents: Vec::new(),
         }
     }
 
@@ -201,7 +225,7 @@ mod tests {
             TaskConfig {
                 commands: vec![CommandEntry::MachineSetup(MachineSetupArgs {
                     config: "/nonexistent/config".to_string(),
-                    task: None,
+                    ..Default::default()
                 })],
                 os: Default::default(),
                 parallel: false,
@@ -231,6 +255,7 @@ mod tests {
                     target: "/tmp/target".to_string(),
                     ignore: vec![],
                     sudo: false,
+                    ..Default::default()
                 })],
                 os: Default::default(),
                 parallel: false,
@@ -263,6 +288,7 @@ mod tests {
                     target: "/tmp/target".to_string(),
                     ignore: vec![],
                     sudo: false,
+                    ..Default::default()
                 })],
                 os: Default::default(),
                 parallel: false,
@@ -325,6 +351,7 @@ mod tests {
                     target: "/tmp/target".to_string(),
                     ignore: vec![],
                     sudo: true,
+                    ..Default::default()
                 })],
                 os: Default::default(),
                 parallel: false,
@@ -351,4 +378,22 @@ mod tests {
                 && matches!(i.severity, Severity::Error)
         }));
     }
+
+    #[test]
+    fn test_validate_names_document_of_included_task() {
+        let dir = tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("base.yaml"),
+            "tasks:\n  shared:\n    commands: []\n",
+        )
+        .unwrap();
+        let main = dir.path().join("main.yaml");
+        std::fs::write(&main, "include: ./base.yaml\ntasks: {}\n").unwrap();
+
+        let config = crate::config::load_config(main.to_str().unwrap()).unwrap();
+        let issues = validate_config(&config, dir.path());
+        assert!(issues
+            .iter()
+            .any(|i| i.task_name == "shared" && i.message.ends_with("(from ./base.yaml)")));
+    }
 }
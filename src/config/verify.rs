@@ -0,0 +1,211 @@
+//! Verification of Config documents fetched from URLs.
+//!
+//! A URL may pin the document's content with a `#sha256=<hex>` fragment
+//! (`--config-sha256` adds one to `-c`). Independently, when `temp_dir`
+//! holds a trusted minisign public key (`trusted.pub`), every fetched
+//! document must come with a detached signature at `<url>.minisig` made by
+//! that key. The key lives on the machine, not in the document, so a
+//! compromised repository cannot vouch for itself. Both checks apply to
+//! `-c <url>` and to URL Sub-configs alike.
+
+use std::path::Path;
+
+use minisign_verify::{PublicKey, Signature};
+use sha2::{Digest, Sha256};
+
+use crate::error::{Error, Result};
+
+/// Fragment prefix pinning a URL's content.
+pub const SHA256_PIN: &str = "sha256=";
+/// Trusted minisign public key, inside `temp_dir`.
+pub const TRUSTED_KEY_FILE: &str = "trusted.pub";
+/// Suffix of the detached signature next to a document URL.
+pub const SIGNATURE_SUFFIX: &str = ".minisig";
+
+/// `url` without a `#sha256=` pin, and the pinned digest (lowercase hex).
+pub fn split_pin(url: &str) -> Result<(&str, Option<String>)> {
+    let Some((base, fragment)) = url.split_once('#') else {
+        return Ok((url, None));
+    };
+    let Some(hex) = fragment.strip_prefix(SHA256_PIN) else {
+        return Ok((url, None));
+    };
+    Ok((base, Some(parse_sha256(hex)?)))
+}
+
+/// Add a `--config-sha256` pin to the Config source `source`.
+pub fn pin(source: String, sha256: Option<&str>) -> Result<String> {
+    let Some(sha256) = sha256 else {
+        return Ok(source);
+    };
+    if super::repo::is_git_source(&source) {
+        return Err(Error::Verification(
+            "--config-sha256 does not apply to a git source; pin a commit with `@<commit id>`"
+                .to_string(),
+        ));
+    }
+    if !super::is_url(&source) {
+        return Err(Error::Verification(
+            "--config-sha256 applies to a URL passed with -c".to_string(),
+        ));
+    }
+    let sha256 = parse_sha256(sha256)?;
+    match split_pin(&source)? {
+        (_, Some(pinned)) if pinned != sha256 => Err(Error::Verification(format!(
+            "--config-sha256 {sha256} contradicts the pin in {source}"
+        ))),
+        (_, Some(_)) => Ok(source),
+        (base, None) => Ok(format!("{base}#{SHA256_PIN}{sha256}")),
+    }
+}
+
+fn parse_sha256(hex: &str) -> Result<String> {
+    if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(Error::Verification(format!(
+            "`{hex}` is not a sha256 digest (64 hex digits)"
+        )));
+    }
+    Ok(hex.to_ascii_lowercase())
+}
+
+/// Error unless `content` (fetched from `url`) hashes to `expected`.
+pub fn check_sha256(url: &str, content: &[u8], expected: &str) -> Result<()> {
+    let actual = format!("{:x}", Sha256::digest(content));
+    if actual != expected {
+        return Err(Error::Verification(format!(
+            "{url}: sha256 is {actual}, expected {expected}"
+        )));
+    }
+    Ok(())
+}
+
+/// Where the detached signature of the document at `url` lives.
+pub fn signature_url(url: &str) -> String {
+    match url.split_once('?') {
+        Some((path, query)) => format!("{path}{SIGNATURE_SUFFIX}?{query}"),
+        None => format!("{url}{SIGNATURE_SUFFIX}"),
+    }
+}
+
+/// The trusted key in `temp_dir`, if one was placed there.
+pub fn trusted_key(temp_dir: &Path) -> Result<Option<PublicKey>> {
+    let path = temp_dir.join(TRUSTED_KEY_FILE);
+    let Ok(text) = std::fs::read_to_string(&path) else {
+        return Ok(None);
+    };
+    PublicKey::decode(&text)
+        .map(Some)
+        .map_err(|e| Error::Verification(format!("{}: {e}", path.display())))
+}
+
+/// Error unless `signature` (minisign format) is `key`'s signature of
+/// `content` fetched from `url`.
+pub fn check_signature(url: &str, content: &[u8], key: &PublicKey, signature: &str) -> Result<()> {
+    Signature::decode(signature)
+        .and_then(|signature| key.verify(content, &signature, false))
+        .map_err(|e| Error::Verification(format!("{url}: signature: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use blake2::Blake2b512;
+    use ed25519_compact::{KeyPair, Seed};
+    use tempfile::tempdir;
+
+    const KEY_ID: [u8; 8] = *b"machine!";
+
+    fn key_pair() -> KeyPair {
+        KeyPair::from_seed(Seed::new([7; 32]))
+    }
+
+    /// `trusted.pub` contents, as written by `minisign -G`.
+    fn public_key_file(keys: &KeyPair) -> String {
+        let mut bin = b"Ed".to_vec();
+        bin.extend_from_slice(&KEY_ID);
+        bin.extend_from_slice(keys.pk.as_ref());
+        format!(
+            "untrusted comment: minisign public key\n{}\n",
+            STANDARD.encode(bin)
+        )
+    }
+
+    /// A prehashed signature, as written by `minisign -S`.
+    fn sign(keys: &KeyPair, content: &[u8]) -> String {
+        let signature = keys.sk.sign(Blake2b512::digest(content), None);
+        let trusted = "timestamp:0\tfile:machine_setup.yaml";
+        let mut global = signature.as_ref().to_vec();
+        global.extend_from_slice(trusted.as_bytes());
+        let mut bin = b"ED".to_vec();
+        bin.extend_from_slice(&KEY_ID);
+        bin.extend_from_slice(signature.as_ref());
+        format!(
+            "untrusted comment: signature\n{}\ntrusted comment: {trusted}\n{}\n",
+            STANDARD.encode(bin),
+            STANDARD.encode(keys.sk.sign(global, None))
+        )
+    }
+
+    #[test]
+    fn pin_is_split_off_the_url() {
+        let hex = "AB".repeat(32);
+        let url = format!("https://example.com/setup.yaml#sha256={hex}");
+        assert_eq!(
+            split_pin(&url).unwrap(),
+            ("https://example.com/setup.yaml", Some("ab".repeat(32)))
+        );
+        assert_eq!(
+            split_pin("https://example.com/setup.yaml#top").unwrap(),
+            ("https://example.com/setup.yaml#top", None)
+        );
+        assert!(split_pin("https://example.com/setup.yaml#sha256=abc").is_err());
+    }
+
+    #[test]
+    fn flag_adds_a_pin_only_to_urls() {
+        let hex = "0".repeat(64);
+        let pinned = pin("https://example.com/a.yaml".into(), Some(&hex)).unwrap();
+        assert_eq!(pinned, format!("https://example.com/a.yaml#sha256={hex}"));
+        assert_eq!(pin(pinned.clone(), Some(&hex)).unwrap(), pinned);
+        assert!(pin(pinned, Some(&"1".repeat(64))).is_err());
+        assert!(pin("./a.yaml".into(), Some(&hex)).is_err());
+    }
+
+    #[test]
+    fn sha256_mismatch_is_an_error() {
+        let content = b"tasks: {}\n";
+        let expected = format!("{:x}", Sha256::digest(content));
+        assert!(check_sha256("u", content, &expected).is_ok());
+        let err = check_sha256("u", b"tasks: {evil: {}}\n", &expected).unwrap_err();
+        assert!(err.to_string().contains("expected"));
+    }
+
+    #[test]
+    fn signature_by_the_trusted_key_is_required() {
+        let dir = tempdir().unwrap();
+        assert!(trusted_key(dir.path()).unwrap().is_none());
+
+        let keys = key_pair();
+        std::fs::write(dir.path().join(TRUSTED_KEY_FILE), public_key_file(&keys)).unwrap();
+        let key = trusted_key(dir.path()).unwrap().unwrap();
+        let content = b"tasks: {}\n";
+        let signature = sign(&keys, content);
+
+        assert!(check_signature("u", content, &key, &signature).is_ok());
+        assert!(check_signature("u", b"tasks: {evil: {}}\n", &key, &signature).is_err());
+        assert!(check_signature("u", content, &key, "not a signature").is_err());
+    }
+
+    #[test]
+    fn signature_url_keeps_the_query() {
+        assert_eq!(
+            signature_url("https://example.com/a.yaml?token=x"),
+            "https://example.com/a.yaml.minisig?token=x"
+        );
+        assert_eq!(
+            signature_url("https://example.com/a.yaml"),
+            "https://example.com/a.yaml.minisig"
+        );
+    }
+}
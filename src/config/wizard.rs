@@ -17,7 +17,7 @@ use super::recipes::{
     GitRepoParams, DEFAULT_BREW_BUNDLE_NAME, DEFAULT_DOTFILES_NAME, DEFAULT_DOTFILES_SRC,
     DEFAULT_DOTFILES_TARGET, DEFAULT_GIT_REPO_NAME,
 };
-use super::{is_url, resolve_config_path};
+use super::{is_remote, resolve_config_path};
 
 const MENU: &[&str] = &[
     "Add blank task",
@@ -99,7 +99,7 @@ fn ensure_tty() -> Result<()> {
 /// Resolve the path the wizard will use (may not exist yet).
 fn resolve_wizard_path(config_arg: Option<&str>, cwd: &Path) -> Result<PathBuf> {
     match config_arg {
-        Some(raw) if is_url(raw) => Err(Error::Other(
+        Some(raw) if is_remote(raw) => Err(Error::Other(
             "wizard requires a local Config document path, not a URL".into(),
         )),
         Some(raw) => {
@@ -0,0 +1,360 @@
+//! Several Config documents in one run — `-c personal.yaml -c work.yaml`, or
+//! a workspace file listing them under `configs:`.
+//!
+//! The documents are loaded one by one and merged into one [`AppConfig`], so
+//! they share a Runner: one Concurrency gate with its Exclusive lanes, one
+//! TUI, one summary, one History. Each document's Tasks are namespaced as
+//! `<member>/<task>` (their `depends_on` too), and keep the document's
+//! directory and `default_shell` through [`AppConfig::origins`] and their
+//! History entries through [`AppConfig::history_scopes`].
+//!
+//! A workspace file is a Config document with `configs:` instead of `tasks:`;
+//! its top-level settings (`temp_dir`, `parallel`, …) apply to the run:
+//!
+//! ```yaml
+//! parallel: true
+//! configs:
+//!   - ./personal/machine_setup.yaml   # member `personal`
+//!   - work: git+ssh://git@host/work-dots.git
+//! ```
+//!
+//! With repeated `-c` the first document's settings apply.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use indexmap::IndexMap;
+use serde_yaml::{Mapping, Value};
+
+use crate::error::{Error, Result};
+use crate::utils::path::expand_path;
+
+use super::remote::Remote;
+use super::types::{AppConfig, HistoryScope, TaskOrigin, Via};
+
+pub const CONFIGS: &str = "configs";
+/// Separates the member name from the Task name.
+pub const SEPARATOR: char = '/';
+
+/// One document of a workspace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Member {
+    /// Namespace of its Tasks.
+    pub name: String,
+    /// Path (absolute), URL or git source.
+    pub source: String,
+}
+
+/// A workspace file: the run's settings and its members.
+pub struct Workspace {
+    pub settings: AppConfig,
+    pub members: Vec<Member>,
+}
+
+/// The workspace at `path`, or `None` when it is a regular Config document.
+pub fn read(path: &Path) -> Result<Option<Workspace>> {
+    let content = std::fs::read_to_string(path)?;
+    if !content.contains(CONFIGS) {
+        return Ok(None);
+    }
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let value: Value = match ext {
+        "json" => serde_json::from_str(&content)?,
+        _ => serde_yaml::from_str(&content)?,
+    };
+    let Value::Mapping(mut settings) = value else {
+        return Ok(None);
+    };
+    let Some(configs) = settings.remove(CONFIGS) else {
+        return Ok(None);
+    };
+    // `tasks: {}` keeps the file valid against the Config schema.
+    let empty =
+        |tasks: &Value| tasks.is_null() || tasks.as_mapping().is_some_and(Mapping::is_empty);
+    if settings.get("tasks").is_some_and(|tasks| !empty(tasks)) {
+        return Err(Error::Other(format!(
+            "{}: a workspace file lists `{CONFIGS}`; define `tasks` in its documents",
+            path.display()
+        )));
+    }
+    settings.insert("tasks".into(), Value::Mapping(Mapping::new()));
+    let settings: AppConfig = serde_yaml::from_value(Value::Mapping(settings))?;
+
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let members = match configs {
+        Value::Sequence(items) => items
+            .into_iter()
+            .map(|item| match item {
+                Value::String(raw) => {
+                    let source = source_in(&raw, dir);
+                    Ok(Member {
+                        name: member_name(&source),
+                        source,
+                    })
+                }
+                Value::Mapping(named) if named.len() == 1 => {
+                    let (name, raw) = named.into_iter().next().expect("one entry");
+                    named_member(name, raw, dir)
+                }
+                _ => Err(Error::Other(format!(
+                    "`{CONFIGS}` items are paths or `name: path`"
+                ))),
+            })
+            .collect::<Result<Vec<_>>>()?,
+        Value::Mapping(named) => named
+            .into_iter()
+            .map(|(name, raw)| named_member(name, raw, dir))
+            .collect::<Result<Vec<_>>>()?,
+        _ => {
+            return Err(Error::Other(format!(
+                "`{CONFIGS}` must list Config documents"
+            )))
+        }
+    };
+    Ok(Some(Workspace { settings, members }))
+}
+
+fn named_member(name: Value, raw: Value, dir: &Path) -> Result<Member> {
+    match (name, raw) {
+        (Value::String(name), Value::String(raw)) => Ok(Member {
+            name,
+            source: source_in(&raw, dir),
+        }),
+        _ => Err(Error::Other(format!(
+            "`{CONFIGS}` items are paths or `name: path`"
+        ))),
+    }
+}
+
+/// `raw` as written in the workspace file in `dir`.
+fn source_in(raw: &str, dir: &Path) -> String {
+    if super::is_remote(raw) {
+        raw.to_string()
+    } else {
+        expand_path(raw, Some(dir)).to_string_lossy().into_owned()
+    }
+}
+
+/// Members for the documents of repeated `-c`.
+pub fn members(sources: &[String]) -> Vec<Member> {
+    sources
+        .iter()
+        .map(|source| Member {
+            name: member_name(source),
+            source: source.clone(),
+        })
+        .collect()
+}
+
+/// Default member name: the document's file stem, or its directory's name
+/// for a `machine_setup.yaml`; the repository's name for a git source
+/// without a path.
+fn member_name(source: &str) -> String {
+    let (repo, path) = match source.split_once('#') {
+        Some((repo, path)) if super::repo::is_git_source(source) => (repo, Some(path)),
+        Some((rest, _)) => (rest, None),
+        None => (source, None),
+    };
+    let path = path.unwrap_or_else(|| repo.split('?').next().unwrap_or(repo));
+    let path = Path::new(path.trim_end_matches('/'));
+    let stem = |p: &Path| {
+        p.file_stem()
+            .map(|s| s.to_string_lossy().trim_end_matches(".git").to_string())
+    };
+    match stem(path) {
+        Some(name) if name == "machine_setup" => path
+            .parent()
+            .and_then(stem)
+            .filter(|dir| !dir.is_empty())
+            .unwrap_or(name),
+        Some(name) => name,
+        None => source.to_string(),
+    }
+}
+
+/// Load `members` and merge them into one config with `settings` (default:
+/// the first member's).
+pub fn load(
+    settings: Option<AppConfig>,
+    members: &[Member],
+    remote: &Remote<'_>,
+    cwd: &Path,
+) -> Result<AppConfig> {
+    let mut seen = HashSet::new();
+    if let Some(member) = members.iter().find(|m| !seen.insert(m.name.as_str())) {
+        return Err(Error::Other(format!(
+            "two Config documents are named `{}`; name them in a workspace file (`{CONFIGS}: [{{name: path}}]`)",
+            member.name
+        )));
+    }
+
+    let mut merged = settings;
+    for member in members {
+        let config = super::load_config_remote(&member.source, remote)?;
+        let document = document_path(&member.source);
+        let config_dir = if super::repo::is_git_source(&member.source) {
+            super::repo::config_dir(&member.source, remote.temp_dir)?
+        } else {
+            super::resolve_config_dir(&member.source, cwd)
+        };
+        let target = merged.get_or_insert_with(|| AppConfig {
+            tasks: IndexMap::new(),
+            origins: Default::default(),
+            history_scopes: Vec::new(),
+            remote_documents: Vec::new(),
+            ..config.clone()
+        });
+        add(target, &member.name, config, &document, &config_dir);
+    }
+    merged.ok_or_else(|| Error::Other("no Config documents to run".to_string()))
+}
+
+/// Add the Tasks of `config` to `merged`, under `name`.
+fn add(merged: &mut AppConfig, name: &str, mut config: AppConfig, document: &Path, dir: &Path) {
+    let qualify = |task: &str| format!("{name}{SEPARATOR}{task}");
+    merged.remote_documents.extend(config.remote_documents);
+    for scope in config.history_scopes {
+        merged.history_scopes.push(HistoryScope {
+            prefix: qualify(&scope.prefix),
+            document: scope.document,
+        });
+    }
+    for (task, mut task_config) in config.tasks {
+        task_config.depends_on = task_config.depends_on.iter().map(|d| qualify(d)).collect();
+        let origin = config.origins.remove(&task).unwrap_or_else(|| TaskOrigin {
+            document: document.to_path_buf(),
+            config_dir: dir.to_path_buf(),
+            default_shell: config.default_shell.clone(),
+            via: Via::Workspace,
+        });
+        merged.origins.insert(qualify(&task), origin);
+        merged.tasks.insert(qualify(&task), task_config);
+    }
+}
+
+fn document_path(source: &str) -> PathBuf {
+    if super::is_remote(source) {
+        return PathBuf::from(source);
+    }
+    let path = Path::new(source);
+    super::resolve_config_path(path)
+        .and_then(|p| Ok(p.canonicalize()?))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::remote::OnChange;
+    use crate::config::types::Shell;
+    use tempfile::tempdir;
+
+    fn write(dir: &Path, name: &str, yaml: &str) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, yaml).unwrap();
+        path
+    }
+
+    #[test]
+    fn member_names_come_from_the_document() {
+        assert_eq!(member_name("/home/me/work.yaml"), "work");
+        assert_eq!(
+            member_name("/home/me/personal/machine_setup.yaml"),
+            "personal"
+        );
+        assert_eq!(
+            member_name("https://example.com/dots/team.yaml?x=1"),
+            "team"
+        );
+        assert_eq!(
+            member_name("git+ssh://git@host/work-dots.git@main"),
+            "work-dots"
+        );
+        assert_eq!(
+            member_name("git+https://host/dots.git#setup/laptop.yaml"),
+            "laptop"
+        );
+    }
+
+    #[test]
+    fn documents_merge_with_namespaced_tasks_and_their_own_dirs() {
+        let dir = tempdir().unwrap();
+        let personal = write(
+            dir.path(),
+            "personal/machine_setup.yaml",
+            "default_shell: zsh\ntasks:\n  git:\n    commands: []\n  shell:\n    depends_on: [git]\n    commands: []\n",
+        );
+        let work = write(
+            dir.path(),
+            "work.yaml",
+            "tasks:\n  git:\n    commands: []\n",
+        );
+        let sources: Vec<String> = [&personal, &work]
+            .iter()
+            .map(|p| p.to_string_lossy().into_owned())
+            .collect();
+        let remote = Remote {
+            temp_dir: dir.path(),
+            on_change: OnChange::Read,
+        };
+
+        let config = load(None, &members(&sources), &remote, dir.path()).unwrap();
+        let names: Vec<&str> = config.tasks.keys().map(String::as_str).collect();
+        assert_eq!(names, vec!["personal/git", "personal/shell", "work/git"]);
+        assert_eq!(
+            config.tasks["personal/shell"].depends_on,
+            vec!["personal/git"]
+        );
+        // Settings of the first document; Tasks keep their own.
+        assert_eq!(config.default_shell, Shell::Zsh);
+        assert_eq!(config.task_default_shell("work/git"), &Shell::Bash);
+        assert_eq!(
+            config.task_config_dir("work/git", Path::new("/elsewhere")),
+            dir.path().canonicalize().unwrap()
+        );
+
+        let clash = vec![sources[1].clone(), sources[1].clone()];
+        assert!(load(None, &members(&clash), &remote, dir.path()).is_err());
+    }
+
+    #[test]
+    fn workspace_file_names_members_and_sets_the_run() {
+        let dir = tempdir().unwrap();
+        write(
+            dir.path(),
+            "a/machine_setup.yaml",
+            "tasks:\n  x:\n    commands: []\n",
+        );
+        write(
+            dir.path(),
+            "b/machine_setup.yaml",
+            "tasks:\n  x:\n    commands: []\n",
+        );
+        let path = write(
+            dir.path(),
+            "workspace.yaml",
+            "parallel: true\ntasks: {}\nconfigs:\n  - ./a/machine_setup.yaml\n  - other: ./b/machine_setup.yaml\n",
+        );
+        assert!(read(&dir.path().join("a/machine_setup.yaml"))
+            .unwrap()
+            .is_none());
+
+        let workspace = read(&path).unwrap().unwrap();
+        let names: Vec<&str> = workspace.members.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["a", "other"]);
+        let remote = Remote {
+            temp_dir: dir.path(),
+            on_change: OnChange::Read,
+        };
+        let config = load(
+            Some(workspace.settings),
+            &workspace.members,
+            &remote,
+            dir.path(),
+        )
+        .unwrap();
+        assert!(config.parallel);
+        assert!(config.tasks.contains_key("other/x"));
+    }
+}
@@ -0,0 +1,343 @@
+//! `archive` Command executor — extract a tarball or zip into a target tree.
+//!
+//! The archive is fetched (URL) or read (local path) and extracted into an
+//! unprivileged staging directory under `temp_dir`. Staging is then placed
+//! onto `target` through Tree materialization and File ops, so `sudo`
+//! extraction into `/opt` batches exactly like `copy`. The placed files and
+//! the directories the entry created are recorded in a [`Manifest`];
+//! uninstall removes exactly those, and a reinstall from a newer archive
+//! removes files the new release no longer ships.
+
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::config::is_url;
+use crate::config::types::ArchiveArgs;
+use crate::engine::context::{display_path, CommandContext};
+use crate::engine::mode::Mode;
+use crate::error::{Error, Result};
+use crate::utils::archive::{self, Format};
+use crate::utils::http;
+use crate::utils::path::expand_path;
+
+use super::fs_ops::{self, FileOps};
+use super::manifest::{self, Manifest};
+use super::progress_log::FileProgress;
+use super::tree;
+use super::CommandExecutor;
+
+pub struct ArchiveCommand {
+    args: ArchiveArgs,
+}
+
+impl ArchiveCommand {
+    pub fn new(args: ArchiveArgs) -> Self {
+        Self { args }
+    }
+}
+
+#[async_trait]
+impl CommandExecutor for ArchiveCommand {
+    async fn execute(&self, ctx: &CommandContext) -> Result<()> {
+        let args = self.args.clone();
+        let ctx = ctx.clone();
+        tokio::task::spawn_blocking(move || match ctx.mode {
+            Mode::Install | Mode::Update => install(&args, &ctx),
+            Mode::Uninstall => uninstall(&args, &ctx),
+        })
+        .await
+        .map_err(|e| Error::Other(e.to_string()))?
+    }
+
+    fn description(&self) -> String {
+        self.args.to_string()
+    }
+}
+
+/// Keyed without `src`: a new release URL is the same entry, so its
+/// reinstall finds the previous release's manifest.
+fn manifest_path(args: &ArchiveArgs, ctx: &CommandContext) -> PathBuf {
+    let key = manifest::entry_key(&ctx.task_name, "archive", "", &args.target);
+    Manifest::path(&ctx.temp_dir, &key)
+}
+
+fn install(args: &ArchiveArgs, ctx: &CommandContext) -> Result<()> {
+    let format = Format::from_name(&args.src).ok_or_else(|| {
+        Error::Other(format!(
+            "archive: unsupported format for {} (expected .tar.gz, .tar.xz or .zip)",
+            args.src
+        ))
+    })?;
+    let target = expand_path(&args.target, Some(&ctx.config_dir));
+    let manifest_path = manifest_path(args, ctx);
+    let work = manifest_path.with_extension("work");
+    if work.exists() {
+        std::fs::remove_dir_all(&work)?;
+    }
+    std::fs::create_dir_all(&work)?;
+
+    let result = extract_and_place(args, format, &target, &work, &manifest_path, ctx);
+    let _ = std::fs::remove_dir_all(&work);
+    result
+}
+
+fn extract_and_place(
+    args: &ArchiveArgs,
+    format: Format,
+    target: &Path,
+    work: &Path,
+    manifest_path: &Path,
+    ctx: &CommandContext,
+) -> Result<()> {
+    let archive_path = fetch(&args.src, format, work, ctx)?;
+    let staging = work.join("tree");
+    ctx.log_progress(format!("extract {}", args.src));
+    archive::extract(&archive_path, format, &staging, args.strip_components)?;
+
+    let previous = Manifest::load(manifest_path)?.unwrap_or_default();
+    let ops = fs_ops::select(args.sudo);
+    let progress = FileProgress::new(ctx, "archive");
+    let pool = if args.sudo {
+        None
+    } else {
+        Some(ctx.gate.pool())
+    };
+    let created_dirs = Mutex::new(Vec::new());
+    let placed = Mutex::new(Vec::new());
+    tree::install_tree_with_pool(
+        &staging,
+        target,
+        &args.ignore,
+        pool,
+        |dir| {
+            if dir.symlink_metadata().is_err() {
+                created_dirs
+                    .lock()
+                    .expect("archive lock")
+                    .push(dir.to_path_buf());
+            }
+            ops.mkdir_p(dir)
+        },
+        |src, dest| {
+            placed
+                .lock()
+                .expect("archive lock")
+                .push(dest.to_path_buf());
+            place_one(ops.as_ref(), src, dest, &progress)
+        },
+    )?;
+
+    let mut files = placed.into_inner().expect("archive lock");
+    files.sort();
+    for stale in previous
+        .files
+        .iter()
+        .filter(|f| files.binary_search(f).is_err())
+    {
+        if stale.symlink_metadata().is_ok() {
+            progress
+                .note_apply(|| format!("remove {} (no longer in archive)", display_path(stale)));
+            ops.remove_file(stale)?;
+        }
+    }
+    progress.finish();
+    ops.flush()?;
+
+    let mut dirs = previous.dirs;
+    dirs.extend(created_dirs.into_inner().expect("archive lock"));
+    dirs.sort();
+    dirs.dedup();
+    Manifest {
+        files,
+        dirs,
+        ..Default::default()
+    }
+    .save(manifest_path)
+}
+
+/// Local archives are used in place; URLs are downloaded into `work`.
+fn fetch(src: &str, format: Format, work: &Path, ctx: &CommandContext) -> Result<PathBuf> {
+    if !is_url(src) {
+        let path = expand_path(src, Some(&ctx.config_dir));
+        if !path.is_file() {
+            return Err(Error::PathError(format!(
+                "Archive does not exist: {}",
+                path.display()
+            )));
+        }
+        return Ok(path);
+    }
+
+    ctx.log_progress(format!("download {src}"));
+    let dest = work.join(format!("download.{}", format.extension()));
+    let response = http::get(src)?
+        .call()
+        .map_err(|e| Error::Other(format!("Failed to download {src}: {e}")))?;
+    let mut file = std::fs::File::create(&dest)?;
+    std::io::copy(&mut response.into_body().into_reader(), &mut file)
+        .map_err(|e| Error::Other(format!("Failed to download {src}: {e}")))?;
+    Ok(dest)
+}
+
+/// Place one staged member at `dest`, skipping identical content. Symlinks
+/// inside the archive are recreated as symlinks.
+fn place_one(
+    ops: &dyn FileOps,
+    src: &Path,
+    dest: &Path,
+    progress: &FileProgress<'_>,
+) -> Result<()> {
+    let src_meta = src.symlink_metadata()?;
+    if src_meta.file_type().is_symlink() {
+        let link = std::fs::read_link(src)?;
+        if std::fs::read_link(dest).is_ok_and(|current| current == link) {
+            progress.note_skip(|| format!("skip {} (unchanged)", display_path(dest)));
+            return Ok(());
+        }
+        progress.note_apply(|| format!("link {} → {}", display_path(dest), link.display()));
+        if dest.symlink_metadata().is_ok() {
+            ops.remove_file(dest)?;
+        }
+        return ops.create_symlink(&link, dest);
+    }
+
+    if same_contents(src, dest, src_meta.len()) {
+        progress.note_skip(|| format!("skip {} (unchanged)", display_path(dest)));
+        return Ok(());
+    }
+    progress.note_apply(|| format!("extract {}", display_path(dest)));
+    ops.copy_file(src, dest)
+}
+
+fn same_contents(src: &Path, dest: &Path, src_len: u64) -> bool {
+    let Ok(dest_meta) = dest.symlink_metadata() else {
+        return false;
+    };
+    if !dest_meta.is_file() || dest_meta.len() != src_len {
+        return false;
+    }
+    matches!((std::fs::read(src), std::fs::read(dest)), (Ok(a), Ok(b)) if a == b)
+}
+
+fn uninstall(args: &ArchiveArgs, ctx: &CommandContext) -> Result<()> {
+    let manifest_path = manifest_path(args, ctx);
+    let Some(manifest) = Manifest::load(&manifest_path)? else {
+        ctx.log_progress(format!("archive: nothing recorded for {}", args.target));
+        return Ok(());
+    };
+
+    let ops = fs_ops::select(args.sudo);
+    let progress = FileProgress::new(ctx, "archive remove");
+    for file in &manifest.files {
+        if file.symlink_metadata().is_ok() {
+            progress.note_apply(|| format!("remove {}", display_path(file)));
+            ops.remove_file(file)?;
+        }
+    }
+    for dir in manifest.dirs_deepest_first() {
+        ops.remove_empty_dir(dir)?;
+    }
+    progress.finish();
+    ops.flush()?;
+    Manifest::delete(&manifest_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::commands::fs_ops::RecordingFs;
+    use tempfile::tempdir;
+
+    fn ctx_for(dir: &Path) -> CommandContext {
+        let (events, _rx) = crate::engine::sink::ChannelSink::channel();
+        CommandContext {
+            events,
+            gate: std::sync::Arc::new(
+                crate::engine::concurrency::ConcurrencyGate::from_num_threads(Some(1)),
+            ),
+            mode: Mode::Install,
+            config_dir: dir.to_path_buf(),
+            temp_dir: dir.to_path_buf(),
+            default_shell: crate::config::types::Shell::Bash,
+            task_name: "t".to_string(),
+            depth: 0,
+            conflicts: None,
+            force: false,
+            env: Default::default(),
+            vars: Default::default(),
+            accept_remote_changes: false,
+            remote_dir: PathBuf::new(),
+            executed: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_place_one_skips_identical_file() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src");
+        let dest = dir.path().join("dest");
+        std::fs::write(&src, "same").unwrap();
+        std::fs::write(&dest, "same").unwrap();
+        let ctx = ctx_for(dir.path());
+        let progress = FileProgress::new(&ctx, "archive");
+        let ops = RecordingFs::default();
+
+        place_one(&ops, &src, &dest, &progress).unwrap();
+
+        assert!(ops.calls().is_empty());
+    }
+
+    #[test]
+    fn test_place_one_copies_changed_file() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src");
+        let dest = dir.path().join("dest");
+        std::fs::write(&src, "new").unwrap();
+        std::fs::write(&dest, "old").unwrap();
+        let ctx = ctx_for(dir.path());
+        let progress = FileProgress::new(&ctx, "archive");
+        let ops = RecordingFs::default();
+
+        place_one(&ops, &src, &dest, &progress).unwrap();
+
+        assert_eq!(
+            ops.calls(),
+            vec![format!("copy_file {} {}", src.display(), dest.display())]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_place_one_recreates_symlinks() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src");
+        std::os::unix::fs::symlink("lib/tool", &src).unwrap();
+        let dest = dir.path().join("dest");
+        let ctx = ctx_for(dir.path());
+        let progress = FileProgress::new(&ctx, "archive");
+        let ops = RecordingFs::default();
+
+        place_one(&ops, &src, &dest, &progress).unwrap();
+
+        assert_eq!(
+            ops.calls(),
+            vec![format!("create_symlink lib/tool {}", dest.display())]
+        );
+    }
+
+    #[test]
+    fn test_unsupported_format_is_an_error() {
+        let dir = tempdir().unwrap();
+        let ctx = ctx_for(dir.path());
+        let args = ArchiveArgs {
+            src: "sdk.rar".to_string(),
+            target: dir.path().join("out").to_string_lossy().into_owned(),
+            strip_components: 0,
+            ignore: vec![],
+            sudo: false,
+        };
+        let err = install(&args, &ctx).unwrap_err();
+        assert!(err.to_string().contains("unsupported format"));
+    }
+}
@@ -0,0 +1,276 @@
+//! Backups of destinations a `symlink` or `copy` entry replaced.
+//!
+//! With `backup: true` (or a "backup" answer to a conflict prompt, see
+//! [`crate::engine::conflict`]), whatever the entry's conflict policy would
+//! overwrite is moved into a timestamped directory under
+//! `<temp_dir>/backups/` instead — one directory per entry per install run,
+//! holding the moved paths (mirrored under `files/` by their absolute path)
+//! and a `backup.json` record. Uninstall restores the newest backup of each
+//! path after removing the link or copy, and `machine_setup backups` lists
+//! what is kept.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::error::{Error, Result};
+
+use super::fs_ops::FileOps;
+
+const RECORD_FILE: &str = "backup.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupRecord {
+    pub task: String,
+    /// Entry key (see [`super::manifest::entry_key`]).
+    pub entry: String,
+    pub created: DateTime<Utc>,
+    #[serde(default)]
+    pub files: Vec<BackedUpPath>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackedUpPath {
+    /// Where the path lived before it was replaced.
+    pub original: PathBuf,
+    /// Where it is kept now.
+    pub stored: PathBuf,
+}
+
+/// One backup directory and its record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backup {
+    pub dir: PathBuf,
+    pub record: BackupRecord,
+}
+
+impl Backup {
+    fn save(&self) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let content =
+            serde_json::to_string_pretty(&self.record).map_err(|e| Error::Other(e.to_string()))?;
+        std::fs::write(self.dir.join(RECORD_FILE), content)?;
+        Ok(())
+    }
+}
+
+pub fn backups_dir(temp_dir: &Path) -> PathBuf {
+    temp_dir.join("backups")
+}
+
+/// Every backup with at least one kept path, oldest first.
+pub fn list(temp_dir: &Path) -> Result<Vec<Backup>> {
+    let root = backups_dir(temp_dir);
+    if !root.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut backups = Vec::new();
+    for entry in std::fs::read_dir(&root)? {
+        let dir = entry?.path();
+        let record_path = dir.join(RECORD_FILE);
+        if !record_path.is_file() {
+            continue;
+        }
+        let content = std::fs::read_to_string(&record_path)?;
+        let record: BackupRecord = serde_json::from_str(&content)
+            .map_err(|e| Error::Other(format!("{}: {e}", record_path.display())))?;
+        if !record.files.is_empty() {
+            backups.push(Backup { dir, record });
+        }
+    }
+    backups.sort_by(|a, b| (a.record.created, &a.dir).cmp(&(b.record.created, &b.dir)));
+    Ok(backups)
+}
+
+/// Backup writer for one install run of one entry. The directory is created
+/// on the first [`BackupSession::stash`], so runs that replace nothing leave
+/// no trace.
+pub struct BackupSession {
+    root: PathBuf,
+    task: String,
+    entry: String,
+    current: Mutex<Option<Backup>>,
+}
+
+impl BackupSession {
+    pub fn new(temp_dir: &Path, task: &str, entry: &str) -> Self {
+        Self {
+            root: backups_dir(temp_dir),
+            task: task.to_string(),
+            entry: entry.to_string(),
+            current: Mutex::new(None),
+        }
+    }
+
+    /// Move `path` into this run's backup through `ops` and record it.
+    /// Returns where it is kept.
+    pub fn stash(&self, ops: &dyn FileOps, path: &Path) -> Result<PathBuf> {
+        let mut current = self.current.lock().expect("backup lock");
+        let backup = current.get_or_insert_with(|| {
+            let created = Utc::now();
+            Backup {
+                dir: self.root.join(format!(
+                    "{}-{}",
+                    created.format("%Y%m%dT%H%M%S%.3f"),
+                    self.entry
+                )),
+                record: BackupRecord {
+                    task: self.task.clone(),
+                    entry: self.entry.clone(),
+                    created,
+                    files: Vec::new(),
+                },
+            }
+        });
+        let stored = backup.dir.join("files").join(mirrored(path));
+        if let Some(parent) = stored.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        ops.move_path(path, &stored)?;
+        backup.record.files.push(BackedUpPath {
+            original: path.to_path_buf(),
+            stored: stored.clone(),
+        });
+        backup.save()?;
+        Ok(stored)
+    }
+}
+
+/// Restores one entry's backups on uninstall.
+pub struct Restorer {
+    /// The entry's backups, newest first.
+    backups: Mutex<Vec<Backup>>,
+}
+
+impl Restorer {
+    pub fn load(temp_dir: &Path, entry: &str) -> Result<Self> {
+        let mut backups: Vec<Backup> = list(temp_dir)?
+            .into_iter()
+            .filter(|b| b.record.entry == entry)
+            .collect();
+        backups.reverse();
+        Ok(Self {
+            backups: Mutex::new(backups),
+        })
+    }
+
+    /// Move the newest kept copy of `original` back through `ops`. Returns
+    /// where it came from, or `None` when nothing was backed up.
+    pub fn restore(&self, ops: &dyn FileOps, original: &Path) -> Result<Option<PathBuf>> {
+        let mut backups = self.backups.lock().expect("backup lock");
+        for backup in backups.iter_mut() {
+            let Some(i) = backup
+                .record
+                .files
+                .iter()
+                .position(|f| f.original == original)
+            else {
+                continue;
+            };
+            let item = backup.record.files.remove(i);
+            if item.stored.symlink_metadata().is_err() {
+                backup.save()?;
+                continue;
+            }
+            ops.move_path(&item.stored, original)?;
+            backup.save()?;
+            return Ok(Some(item.stored));
+        }
+        Ok(None)
+    }
+
+    /// Remove backup directories with nothing left to restore. Call after the
+    /// File ops flush — a sudo restore moves files out only then.
+    pub fn prune(&self) -> Result<()> {
+        for backup in self.backups.lock().expect("backup lock").iter() {
+            if backup.record.files.is_empty() && backup.dir.exists() {
+                std::fs::remove_dir_all(&backup.dir)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `path` as a relative path below a backup's `files/` directory.
+fn mirrored(path: &Path) -> PathBuf {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part.to_os_string()),
+            Component::Prefix(prefix) => Some(
+                prefix
+                    .as_os_str()
+                    .to_string_lossy()
+                    .replace([':', '\\', '?'], "")
+                    .into(),
+            ),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::commands::fs_ops::DirectFs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn mirrored_drops_root() {
+        assert_eq!(
+            mirrored(Path::new("/home/me/.bashrc")),
+            PathBuf::from("home/me/.bashrc")
+        );
+    }
+
+    #[test]
+    fn stash_then_restore_roundtrip() {
+        let dir = tempdir().unwrap();
+        let temp_dir = dir.path().join("temp");
+        let original = dir.path().join("home/.bashrc");
+        std::fs::create_dir_all(original.parent().unwrap()).unwrap();
+        std::fs::write(&original, "mine").unwrap();
+
+        let session = BackupSession::new(&temp_dir, "dotfiles", "k1");
+        let stored = session.stash(&DirectFs, &original).unwrap();
+        assert!(!original.exists());
+        assert_eq!(std::fs::read_to_string(&stored).unwrap(), "mine");
+
+        let backups = list(&temp_dir).unwrap();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(backups[0].record.task, "dotfiles");
+        assert_eq!(backups[0].record.files[0].original, original);
+
+        let restorer = Restorer::load(&temp_dir, "k1").unwrap();
+        assert_eq!(
+            restorer.restore(&DirectFs, &original).unwrap(),
+            Some(stored)
+        );
+        assert_eq!(std::fs::read_to_string(&original).unwrap(), "mine");
+        restorer.prune().unwrap();
+        assert!(list(&temp_dir).unwrap().is_empty());
+        assert_eq!(
+            std::fs::read_dir(backups_dir(&temp_dir)).unwrap().count(),
+            0
+        );
+    }
+
+    #[test]
+    fn restore_ignores_other_entries_and_unknown_paths() {
+        let dir = tempdir().unwrap();
+        let original = dir.path().join("f");
+        std::fs::write(&original, "x").unwrap();
+        BackupSession::new(dir.path(), "t", "k1")
+            .stash(&DirectFs, &original)
+            .unwrap();
+
+        let other = Restorer::load(dir.path(), "k2").unwrap();
+        assert_eq!(other.restore(&DirectFs, &original).unwrap(), None);
+        let same = Restorer::load(dir.path(), "k1").unwrap();
+        assert_eq!(
+            same.restore(&DirectFs, &dir.path().join("g")).unwrap(),
+            None
+        );
+        assert!(!original.exists());
+    }
+}
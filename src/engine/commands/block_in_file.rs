@@ -0,0 +1,125 @@
+use async_trait::async_trait;
+
+use crate::config::types::BlockInFileArgs;
+use crate::engine::context::CommandContext;
+use crate::engine::mode::Mode;
+use crate::error::Result;
+use crate::utils::marked_block;
+
+use super::file_edit;
+use super::CommandExecutor;
+
+pub struct BlockInFileCommand {
+    args: BlockInFileArgs,
+}
+
+impl BlockInFileCommand {
+    pub fn new(args: BlockInFileArgs) -> Self {
+        Self { args }
+    }
+}
+
+#[async_trait]
+impl CommandExecutor for BlockInFileCommand {
+    async fn execute(&self, ctx: &CommandContext) -> Result<()> {
+        let (begin, end) = markers(&self.args);
+        match ctx.mode {
+            Mode::Install | Mode::Update => {
+                let block = render_block(&begin, &end, &self.args.content);
+                file_edit::apply(&self.args.path, self.args.sudo, ctx, move |text| {
+                    file_edit::edit_lf(text, |text| {
+                        marked_block::upsert(text, &begin, &end, &block)
+                    })
+                })
+                .await
+            }
+            Mode::Uninstall => {
+                file_edit::apply(&self.args.path, self.args.sudo, ctx, move |text| {
+                    file_edit::edit_lf(text, |text| {
+                        marked_block::strip(text, &begin, &end).unwrap_or_else(|| text.to_string())
+                    })
+                })
+                .await
+            }
+        }
+    }
+
+    fn description(&self) -> String {
+        self.args.to_string()
+    }
+}
+
+/// Begin/end marker lines, matching the schedule hook stub's style.
+fn markers(args: &BlockInFileArgs) -> (String, String) {
+    let comment = &args.comment;
+    let name = &args.name;
+    (
+        format!("{comment} >>> machine_setup {name} >>>"),
+        format!("{comment} <<< machine_setup {name} <<<"),
+    )
+}
+
+fn render_block(begin: &str, end: &str, content: &str) -> String {
+    let mut block = format!("{begin}\n{content}");
+    if !content.is_empty() && !content.ends_with('\n') {
+        block.push('\n');
+    }
+    block.push_str(end);
+    block.push('\n');
+    block
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(content: &str) -> BlockInFileArgs {
+        BlockInFileArgs {
+            path: "/tmp/hosts".to_string(),
+            name: "lab".to_string(),
+            content: content.to_string(),
+            comment: "#".to_string(),
+            sudo: false,
+        }
+    }
+
+    #[test]
+    fn markers_use_name_and_comment() {
+        let mut a = args("x");
+        a.comment = "//".to_string();
+        let (begin, end) = markers(&a);
+        assert_eq!(begin, "// >>> machine_setup lab >>>");
+        assert_eq!(end, "// <<< machine_setup lab <<<");
+    }
+
+    #[test]
+    fn block_install_is_idempotent() {
+        let a = args("10.0.0.2 lab");
+        let (begin, end) = markers(&a);
+        let block = render_block(&begin, &end, &a.content);
+        let once = marked_block::upsert("127.0.0.1 localhost\n", &begin, &end, &block);
+        let twice = marked_block::upsert(&once, &begin, &end, &block);
+        assert_eq!(once, twice);
+        assert_eq!(
+            once,
+            "127.0.0.1 localhost\n\
+             # >>> machine_setup lab >>>\n\
+             10.0.0.2 lab\n\
+             # <<< machine_setup lab <<<\n"
+        );
+        assert_eq!(
+            marked_block::strip(&once, &begin, &end).unwrap(),
+            "127.0.0.1 localhost\n"
+        );
+    }
+
+    #[test]
+    fn block_is_stripped_from_a_crlf_file_with_its_line_ending() {
+        let (begin, end) = markers(&args("x"));
+        let text = format!("a\r\n{begin}\r\nx\r\n{end}\r\nb\r\n");
+        let stripped = file_edit::edit_lf(&text, |text| {
+            marked_block::strip(text, &begin, &end).unwrap_or_else(|| text.to_string())
+        });
+        assert_eq!(stripped, "a\nb\n");
+    }
+}
@@ -9,18 +9,35 @@ use std::path::Path;
 use crate::config::types::{AppConfig, CommandEntry};
 use crate::engine::concurrency::ExclusiveLane;
 use crate::engine::mode::Mode;
+use crate::utils::ignore_rules::{unmatched_patterns, IgnoreRules};
 use crate::utils::shell::validate_env_key;
 
+use super::archive::ArchiveCommand;
+use super::block_in_file::BlockInFileCommand;
 use super::clone::CloneCommand;
 use super::copy::CopyCommand;
+use super::line_in_file::LineInFileCommand;
+use super::package::PackageCommand;
 use super::run::RunCommand;
 use super::setup::SetupCommand;
 use super::symlink::SymlinkCommand;
+use super::template::TemplateCommand;
 use super::CommandExecutor;
 
 /// YAML/JSON keys for Command entry kinds — single list for schema generation
 /// and authoring docs. Keep in sync with `CommandEntry` Deserialize.
-pub const KIND_KEYS: &[&str] = &["copy", "symlink", "clone", "run", "machine_setup"];
+pub const KIND_KEYS: &[&str] = &[
+    "copy",
+    "symlink",
+    "clone",
+    "run",
+    "machine_setup",
+    "template",
+    "line_in_file",
+    "block_in_file",
+    "package",
+    "archive",
+];
 
 /// Severity for kind-level validation notes (mapped by `config::validate`).
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -44,6 +61,11 @@ pub fn create_executor(entry: CommandEntry) -> Box<dyn CommandExecutor> {
         CommandEntry::Clone(args) => Box::new(CloneCommand::new(args)),
         CommandEntry::Run(args) => Box::new(RunCommand::new(args)),
         CommandEntry::MachineSetup(args) => Box::new(SetupCommand::new(args)),
+        CommandEntry::Template(args) => Box::new(TemplateCommand::new(args)),
+        CommandEntry::LineInFile(args) => Box::new(LineInFileCommand::new(args)),
+        CommandEntry::BlockInFile(args) => Box::new(BlockInFileCommand::new(args)),
+        CommandEntry::Package(args) => Box::new(PackageCommand::new(args)),
+        CommandEntry::Archive(args) => Box::new(ArchiveCommand::new(args)),
     }
 }
 
@@ -55,6 +77,11 @@ pub fn description(entry: &CommandEntry) -> String {
         CommandEntry::Clone(args) => args.to_string(),
         CommandEntry::Run(args) => args.to_string(),
         CommandEntry::MachineSetup(args) => args.to_string(),
+        CommandEntry::Template(args) => args.to_string(),
+        CommandEntry::LineInFile(args) => args.to_string(),
+        CommandEntry::BlockInFile(args) => args.to_string(),
+        CommandEntry::Package(args) => args.to_string(),
+        CommandEntry::Archive(args) => args.to_string(),
     }
 }
 
@@ -64,6 +91,13 @@ pub fn entry_requires_sudo(entry: &CommandEntry) -> bool {
         CommandEntry::Run(args) => args.all_command_strings().any(|s| s.contains("sudo")),
         CommandEntry::Copy(args) => args.sudo,
         CommandEntry::Symlink(args) => args.sudo,
+        CommandEntry::Template(args) => args.sudo,
+        CommandEntry::LineInFile(args) => args.sudo,
+        CommandEntry::BlockInFile(args) => args.sudo,
+        CommandEntry::Archive(args) => args.sudo,
+        CommandEntry::Package(args) => {
+            super::package::detect(args).is_some_and(|manager| manager.needs_root())
+        }
         CommandEntry::Clone(_) | CommandEntry::MachineSetup(_) => false,
     }
 }
@@ -106,6 +140,23 @@ pub fn validate_entry(entry: &CommandEntry, config_dir: &Path) -> Vec<KindIssue>
                     severity: KindSeverity::Warning,
                 });
             }
+            ignore_issues("copy", &src, &args.ignore, &mut issues);
+            if args.prune && src.is_file() {
+                issues.push(KindIssue {
+                    message: "copy: `prune` has no effect for a single-file `src`".to_string(),
+                    severity: KindSeverity::Warning,
+                });
+            }
+            file_attr_issues(
+                "copy",
+                &[
+                    ("mode", args.mode.as_deref()),
+                    ("dir_mode", args.dir_mode.as_deref()),
+                ],
+                args.owner.is_some() || args.group.is_some(),
+                args.sudo,
+                &mut issues,
+            );
         }
         CommandEntry::Symlink(args) => {
             let src = crate::utils::path::expand_path(&args.src, Some(config_dir));
@@ -115,9 +166,35 @@ pub fn validate_entry(entry: &CommandEntry, config_dir: &Path) -> Vec<KindIssue>
                     severity: KindSeverity::Warning,
                 });
             }
+            ignore_issues("symlink", &src, &args.ignore, &mut issues);
+            if args.fold && src.is_file() {
+                issues.push(KindIssue {
+                    message: "symlink: `fold` has no effect for a single-file `src`".to_string(),
+                    severity: KindSeverity::Warning,
+                });
+            }
+            if args.prune && src.is_file() {
+                issues.push(KindIssue {
+                    message: "symlink: `prune` has no effect for a single-file `src`".to_string(),
+                    severity: KindSeverity::Warning,
+                });
+            }
+            if args.backup && !args.force {
+                issues.push(KindIssue {
+                    message: "symlink: `backup` has no effect without `force`".to_string(),
+                    severity: KindSeverity::Warning,
+                });
+            }
+            file_attr_issues(
+                "symlink",
+                &[("dir_mode", args.dir_mode.as_deref())],
+                false,
+                args.sudo,
+                &mut issues,
+            );
         }
         CommandEntry::MachineSetup(args) => {
-            if !crate::config::is_url(&args.config) {
+            if !args.optional && !crate::config::is_remote(&args.config) {
                 let path = crate::utils::path::expand_path(&args.config, Some(config_dir));
                 let exists = path.exists()
                     || path.with_extension("yaml").exists()
@@ -131,14 +208,138 @@ pub fn validate_entry(entry: &CommandEntry, config_dir: &Path) -> Vec<KindIssue>
                 }
             }
         }
+        CommandEntry::Template(args) => {
+            let src = crate::utils::path::expand_path(&args.src, Some(config_dir));
+            if !src.exists() {
+                issues.push(KindIssue {
+                    message: format!("Template source does not exist: {}", src.display()),
+                    severity: KindSeverity::Warning,
+                });
+            }
+            file_attr_issues(
+                "template",
+                &[("mode", args.mode.as_deref())],
+                args.owner.is_some() || args.group.is_some(),
+                args.sudo,
+                &mut issues,
+            );
+        }
+        CommandEntry::LineInFile(args) => {
+            if let Err(e) = super::line_in_file::compile(args.regex.as_deref()) {
+                issues.push(KindIssue {
+                    message: e.to_string(),
+                    severity: KindSeverity::Error,
+                });
+            }
+            if args.line.contains('\n') {
+                issues.push(KindIssue {
+                    message: format!("line_in_file line must be a single line: {entry}"),
+                    severity: KindSeverity::Error,
+                });
+            }
+        }
+        CommandEntry::BlockInFile(args) => {
+            if args.name.trim().is_empty() || args.name.contains('\n') {
+                issues.push(KindIssue {
+                    message: format!("block_in_file needs a single-line, non-empty name: {entry}"),
+                    severity: KindSeverity::Error,
+                });
+            }
+        }
+        CommandEntry::Package(args) => {
+            if args.families().all(|(_, packages)| packages.is_empty()) {
+                issues.push(KindIssue {
+                    message: format!("Package command lists no packages: {entry}"),
+                    severity: KindSeverity::Warning,
+                });
+            }
+        }
+        CommandEntry::Archive(args) => {
+            if crate::utils::archive::Format::from_name(&args.src).is_none() {
+                issues.push(KindIssue {
+                    message: format!(
+                        "Unsupported archive format (expected .tar.gz, .tar.xz or .zip): {}",
+                        args.src
+                    ),
+                    severity: KindSeverity::Error,
+                });
+            } else if !crate::config::is_url(&args.src) {
+                let src = crate::utils::path::expand_path(&args.src, Some(config_dir));
+                if !src.exists() {
+                    issues.push(KindIssue {
+                        message: format!("Archive source does not exist: {}", src.display()),
+                        severity: KindSeverity::Warning,
+                    });
+                }
+            }
+            // Members are unknown until extraction: check pattern syntax only.
+            ignore_issues("archive", Path::new(""), &args.ignore, &mut issues);
+        }
         CommandEntry::Clone(_) => {}
     }
     issues
 }
 
+/// Ignore-pattern checks for tree-shaped kinds: invalid patterns are errors,
+/// patterns that match nothing under a directory `src` are warnings.
+fn ignore_issues(kind: &str, src: &Path, patterns: &[String], issues: &mut Vec<KindIssue>) {
+    if patterns.is_empty() {
+        return;
+    }
+    if let Err(e) = IgnoreRules::from_patterns(src, patterns) {
+        issues.push(KindIssue {
+            message: format!("{kind}: {e}"),
+            severity: KindSeverity::Error,
+        });
+        return;
+    }
+    if !src.is_dir() {
+        return;
+    }
+    for pattern in unmatched_patterns(src, patterns) {
+        issues.push(KindIssue {
+            message: format!(
+                "{kind} ignore pattern {pattern:?} matches nothing under {}",
+                src.display()
+            ),
+            severity: KindSeverity::Warning,
+        });
+    }
+}
+
+/// Octal-mode and ownership checks shared by file-producing kinds.
+fn file_attr_issues(
+    kind: &str,
+    modes: &[(&str, Option<&str>)],
+    sets_owner: bool,
+    sudo: bool,
+    issues: &mut Vec<KindIssue>,
+) {
+    for (field, mode) in modes {
+        if let Some(mode) = mode {
+            if crate::config::types::parse_file_mode(mode).is_none() {
+                issues.push(KindIssue {
+                    message: format!(
+                        "Invalid {kind} {field}: {mode:?} (expected octal, e.g. \"0644\")"
+                    ),
+                    severity: KindSeverity::Error,
+                });
+            }
+        }
+    }
+    if sets_owner && !sudo {
+        issues.push(KindIssue {
+            message: format!("{kind} owner/group requires `sudo: true`"),
+            severity: KindSeverity::Error,
+        });
+    }
+}
+
 /// Infer an Exclusive lane from a `run` Command entry's script for this Mode.
 ///
 /// Authors do not declare lanes. First matching family in table order wins.
+/// `package` entries are not listed here: their executor admits itself on the
+/// detected manager's lane so it can batch queued packages (ADR-0010).
 pub fn exclusive_lane(entry: &CommandEntry, mode: Mode) -> Option<ExclusiveLane> {
     let CommandEntry::Run(args) = entry else {
         return None;
@@ -234,6 +435,128 @@ mod tests {
         assert_eq!(exclusive_lane(&entry, Mode::Install), None);
     }
 
+    #[test]
+    fn template_requires_sudo_only_when_flagged() {
+        let plain = parse_entry("template:\n  src: ./a\n  target: /tmp/a");
+        let sudo = parse_entry("template:\n  src: ./a\n  target: /etc/a\n  sudo: true");
+        assert!(!entry_requires_sudo(&plain));
+        assert!(entry_requires_sudo(&sudo));
+    }
+
+    #[test]
+    fn template_invalid_mode_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a"), "x").unwrap();
+        let entry = parse_entry("template:\n  src: ./a\n  target: /tmp/a\n  mode: \"rw\"");
+        let issues = validate_entry(&entry, dir.path());
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity, KindSeverity::Error);
+    }
+
+    #[test]
+    fn copy_owner_without_sudo_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry = parse_entry("copy:\n  src: .\n  target: /tmp/b\n  mode: \"0600\"\n  owner: me");
+        let issues = validate_entry(&entry, dir.path());
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].message.contains("sudo"));
+
+        let sudo = parse_entry(
+            "copy:\n  src: .\n  target: /tmp/b\n  dir_mode: \"0700\"\n  group: staff\n  sudo: true",
+        );
+        assert!(validate_entry(&sudo, dir.path()).is_empty());
+    }
+
+    #[test]
+    fn copy_ignore_pattern_matching_nothing_warns() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("notes.md"), "x").unwrap();
+        let entry =
+            parse_entry("copy:\n  src: .\n  target: /tmp/b\n  ignore: [\"*.md\", \"*.swp\"]");
+        let issues = validate_entry(&entry, dir.path());
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity, KindSeverity::Warning);
+        assert!(issues[0].message.contains("*.swp"));
+    }
+
+    #[test]
+    fn symlink_backup_without_force_warns() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry = parse_entry("symlink:\n  src: .\n  target: /tmp/b\n  backup: true");
+        let issues = validate_entry(&entry, dir.path());
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity, KindSeverity::Warning);
+        assert!(issues[0].message.contains("force"));
+    }
+
+    #[test]
+    fn prune_with_single_file_src_warns() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("f"), "x").unwrap();
+        let file = parse_entry("copy:\n  src: f\n  target: /tmp/b\n  prune: true");
+        let issues = validate_entry(&file, dir.path());
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].message.contains("prune"));
+        let tree = parse_entry("symlink:\n  src: .\n  target: /tmp/b\n  prune: true");
+        assert!(validate_entry(&tree, dir.path()).is_empty());
+    }
+
+    #[test]
+    fn copy_invalid_dir_mode_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry = parse_entry("copy:\n  src: .\n  target: /tmp/b\n  dir_mode: \"999\"");
+        let issues = validate_entry(&entry, dir.path());
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity, KindSeverity::Error);
+    }
+
+    #[test]
+    fn line_in_file_invalid_regex_is_an_error() {
+        let entry = parse_entry("line_in_file:\n  path: /etc/hosts\n  line: x\n  regex: \"(\"");
+        let issues = validate_entry(&entry, Path::new("."));
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity, KindSeverity::Error);
+    }
+
+    #[test]
+    fn block_in_file_requires_sudo_only_when_flagged() {
+        let entry = parse_entry(
+            "block_in_file:\n  path: /etc/hosts\n  name: lab\n  content: x\n  sudo: true",
+        );
+        assert!(entry_requires_sudo(&entry));
+        assert!(validate_entry(&entry, Path::new(".")).is_empty());
+    }
+
+    #[test]
+    fn empty_package_entry_warns() {
+        let entry = parse_entry("package:\n  apt: []");
+        let issues = validate_entry(&entry, Path::new("."));
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity, KindSeverity::Warning);
+    }
+
+    #[test]
+    fn package_entry_is_not_runner_admitted() {
+        let entry = parse_entry("package:\n  apt: [git]");
+        assert_eq!(exclusive_lane(&entry, Mode::Install), None);
+    }
+
+    #[test]
+    fn archive_unsupported_format_is_an_error() {
+        let entry = parse_entry("archive:\n  src: https://x/sdk.rar\n  target: /opt/sdk");
+        let issues = validate_entry(&entry, Path::new("."));
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity, KindSeverity::Error);
+    }
+
+    #[test]
+    fn archive_url_source_is_not_checked_on_disk() {
+        let entry =
+            parse_entry("archive:\n  src: https://x/sdk.tar.gz\n  target: /opt/sdk\n  sudo: true");
+        assert!(validate_entry(&entry, Path::new(".")).is_empty());
+        assert!(entry_requires_sudo(&entry));
+    }
+
     #[test]
     fn unused_install_apt_does_not_join_on_update() {
         let entry = parse_entry("run:\n  install: sudo apt-get install git");
@@ -243,4 +566,13 @@ mod tests {
             Some(ExclusiveLane::Apt)
         );
     }
+
+    #[test]
+    fn optional_sub_config_may_be_missing() {
+        let entry = parse_entry("machine_setup:\n  config: /nonexistent/work.yaml");
+        assert_eq!(validate_entry(&entry, Path::new(".")).len(), 1);
+        let entry =
+            parse_entry("machine_setup:\n  config: /nonexistent/work.yaml\n  optional: true");
+        assert!(validate_entry(&entry, Path::new(".")).is_empty());
+    }
 }
@@ -1,12 +1,18 @@
 use async_trait::async_trait;
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 
-use crate::config::types::CopyArgs;
-use crate::engine::context::CommandContext;
+use crate::config::types::{CopyArgs, CopyCompare};
+use crate::engine::conflict::{self, ConflictPolicy, Resolution};
+use crate::engine::context::{display_path, CommandContext};
 use crate::engine::mode::Mode;
 use crate::error::Result;
+use crate::utils::path::{expand_path, walk_relative};
 
-use super::fs_ops::FileOps;
+use super::backup::{BackupSession, Restorer};
+use super::fs_ops::{FileAttrs, FileOps};
+use super::manifest::{self, FileDigest, Manifest};
 use super::progress_log::FileProgress;
 use super::tree_op::{self, TreeOpKind};
 use super::CommandExecutor;
@@ -24,11 +30,49 @@ impl CopyCommand {
 #[async_trait]
 impl CommandExecutor for CopyCommand {
     async fn execute(&self, ctx: &CommandContext) -> Result<()> {
+        let args = &self.args;
+        let owner = args.owner.as_deref();
+        let group = args.group.as_deref();
+        let entry = manifest::entry_key(&ctx.task_name, "copy", &args.src, &args.target);
+        let manifest = Manifest::path(&ctx.temp_dir, &entry);
+        // Only an interactive run asks; with nobody to ask, a changed
+        // destination is overwritten as `compare` allows (newer wins).
+        let conflicts = match ConflictPolicy::new(args.force, args.backup, ctx, "copy") {
+            ConflictPolicy::Skip => ConflictPolicy::Replace { backup: false },
+            policy => policy,
+        };
+        let installing = ctx.mode != Mode::Uninstall;
+        let backups = (conflicts.may_back_up() && installing)
+            .then(|| BackupSession::new(&ctx.temp_dir, &ctx.task_name, &entry));
+        let restorer = if installing {
+            None
+        } else {
+            Some(Restorer::load(&ctx.temp_dir, &entry)?)
+        };
+        let copied = if installing {
+            Manifest::load(&manifest)?.unwrap_or_default().digests
+        } else {
+            BTreeMap::new()
+        };
         tree_op::execute(
-            &self.args.src,
-            &self.args.target,
+            &args.src,
+            &args.target,
             CopyKind {
-                args: self.args.clone(),
+                args: args.clone(),
+                file_attrs: FileAttrs::parse("copy", "mode", args.mode.as_deref(), owner, group)?,
+                dir_attrs: FileAttrs::parse(
+                    "copy",
+                    "dir_mode",
+                    args.dir_mode.as_deref(),
+                    owner,
+                    group,
+                )?,
+                tree_source: expand_path(&args.src, Some(&ctx.config_dir)).is_dir(),
+                conflicts,
+                backups,
+                restorer,
+                copied,
+                manifest: Some(manifest),
             },
             ctx,
         )
@@ -42,6 +86,23 @@ impl CommandExecutor for CopyCommand {
 
 struct CopyKind {
     args: CopyArgs,
+    file_attrs: FileAttrs,
+    /// Applied to every directory of a copied tree, and to a parent directory
+    /// a single-file copy creates.
+    dir_attrs: FileAttrs,
+    tree_source: bool,
+    /// What to do with a changed destination this entry did not copy.
+    conflicts: ConflictPolicy,
+    /// Install with `force` + `backup`, or an interactive run: where replaced
+    /// files go.
+    backups: Option<BackupSession>,
+    /// Uninstall: puts backed-up files back once the copy is gone.
+    restorer: Option<Restorer>,
+    /// Install: digests of the files earlier runs copied. One still matching
+    /// is this entry's to update; any other existing file is a conflict.
+    copied: BTreeMap<PathBuf, FileDigest>,
+    /// Entry manifest of copied files and created directories.
+    manifest: Option<PathBuf>,
 }
 
 impl CopyKind {
@@ -51,8 +112,90 @@ impl CopyKind {
     /// Non-sudo Install uses parallel DirectFs apply (ADR-0004) instead of a
     /// bulk `cp -a`: Command bench on WSL showed process-spawned `cp` slower
     /// than in-process parallel `std::fs::copy` for typical tree sizes.
+    ///
+    /// Mode/ownership settings need per-path `chmod`/`chown`, and
+    /// `compare: hash` must leave unchanged files alone, so they opt out.
     fn eligible_for_bulk_sudo(src: &Path, args: &CopyArgs, mode: Mode) -> bool {
-        args.sudo && matches!(mode, Mode::Install) && src.is_dir() && args.ignore.is_empty()
+        args.sudo
+            && matches!(mode, Mode::Install)
+            && src.is_dir()
+            && args.ignore.is_empty()
+            && !sets_attributes(args)
+            && args.compare == CopyCompare::Mtime
+    }
+
+    /// Bulk `sudo cp -a`, then the Entry manifest from a walk planned before
+    /// the copy (so directories that were missing count as created).
+    fn bulk_copy(&self, src: &Path, target: &Path, ctx: &CommandContext) -> Result<()> {
+        let mut created = Vec::new();
+        let mut mapped = Vec::new();
+        if target.symlink_metadata().is_err() {
+            created.push(target.to_path_buf());
+        }
+        walk_relative(src, target, &self.args.ignore, |entry, dest| {
+            if entry.file_type().is_dir() {
+                if entry.depth() > 0 && dest.symlink_metadata().is_err() {
+                    created.push(dest.to_path_buf());
+                }
+            } else {
+                mapped.push((entry.path().to_path_buf(), dest.to_path_buf()));
+            }
+            Ok(())
+        })?;
+        ctx.log_progress(format!(
+            "bulk copy {} → {}",
+            crate::engine::context::display_path(src),
+            crate::engine::context::display_path(target),
+        ));
+        crate::utils::sudo::sudo_copy_tree(src, target)?;
+        tree_op::record_install(self, mapped, created, ctx)
+    }
+
+    /// `dest` exists and differs from `src`: `true` once it may be replaced.
+    /// An unmodified earlier copy of this entry always may; anything else is
+    /// a conflict for the [`ConflictPolicy`] — skipped, adopted (copied back
+    /// over `src`, which then matches), or moved into a backup first.
+    fn clear_conflict(
+        &self,
+        ops: &dyn FileOps,
+        src: &Path,
+        dest: &Path,
+        progress: &FileProgress<'_>,
+    ) -> Result<bool> {
+        if self
+            .copied
+            .get(dest)
+            .is_some_and(|digest| digest.matches(dest))
+        {
+            return Ok(true);
+        }
+        match self.conflicts.resolve(src, dest) {
+            Resolution::Skip => {
+                progress.note_skip(|| format!("skip {} (exists)", display_path(dest)));
+                Ok(false)
+            }
+            Resolution::Adopt => {
+                if conflict::adopt_into_source(src, dest)? {
+                    progress.note_apply(|| {
+                        format!("adopt {} into {}", display_path(dest), display_path(src))
+                    });
+                } else {
+                    progress
+                        .note_skip(|| format!("skip {} (not a file to adopt)", display_path(dest)));
+                }
+                Ok(false)
+            }
+            Resolution::Backup => {
+                if let Some(backups) = &self.backups {
+                    let stored = backups.stash(ops, dest)?;
+                    progress.note_apply(|| {
+                        format!("backup {} to {}", display_path(dest), display_path(&stored))
+                    });
+                }
+                Ok(true)
+            }
+            Resolution::Overwrite => Ok(true),
+        }
     }
 }
 
@@ -96,19 +239,21 @@ impl TreeOpKind for CopyKind {
         target: &Path,
         ctx: &CommandContext,
     ) -> Option<Result<()>> {
-        if Self::eligible_for_bulk_sudo(src, &self.args, ctx.mode) {
-            ctx.log_progress(format!(
-                "bulk copy {} → {}",
-                crate::engine::context::display_path(src),
-                crate::engine::context::display_path(target),
-            ));
-            return Some(crate::utils::sudo::sudo_copy_tree(src, target));
-        }
-        None
+        // `cp -a` replaces whatever is there, so an existing target goes file
+        // by file unless `force` (without `backup`) would replace it anyway.
+        let replaceable = target.symlink_metadata().is_err()
+            || matches!(self.conflicts, ConflictPolicy::Replace { backup: false });
+        (replaceable && Self::eligible_for_bulk_sudo(src, &self.args, ctx.mode))
+            .then(|| self.bulk_copy(src, target, ctx))
     }
 
     fn ensure_dir(&self, ops: &dyn FileOps, dir: &Path, _ctx: &CommandContext) -> Result<()> {
-        ops.mkdir_p(dir)
+        let created = dir.symlink_metadata().is_err();
+        ops.mkdir_p(dir)?;
+        if self.tree_source || created {
+            self.dir_attrs.apply(ops, dir, created)?;
+        }
+        Ok(())
     }
 
     fn on_install_file(
@@ -118,7 +263,10 @@ impl TreeOpKind for CopyKind {
         dest: &Path,
         progress: &FileProgress<'_>,
     ) -> Result<()> {
-        copy_one(ops, src, dest, progress)
+        let copied = copy_one(ops, src, dest, self.args.compare, progress, || {
+            self.clear_conflict(ops, src, dest, progress)
+        })?;
+        self.file_attrs.apply(ops, dest, copied)
     }
 
     fn on_uninstall_file(
@@ -127,17 +275,58 @@ impl TreeOpKind for CopyKind {
         dest: &Path,
         progress: &FileProgress<'_>,
     ) -> Result<()> {
-        if dest.exists() {
-            progress
-                .note_apply(|| format!("remove {}", crate::engine::context::display_path(dest)));
-            ops.remove_file(dest)
-        } else {
-            Ok(())
+        if !dest.exists() {
+            return Ok(());
+        }
+        progress.note_apply(|| format!("remove {}", display_path(dest)));
+        ops.remove_file(dest)?;
+        if let Some(stored) = self
+            .restorer
+            .as_ref()
+            .map(|r| r.restore(ops, dest))
+            .transpose()?
+            .flatten()
+        {
+            progress.note_apply(|| {
+                format!(
+                    "restore {} from {}",
+                    display_path(dest),
+                    display_path(&stored)
+                )
+            });
         }
+        Ok(())
+    }
+
+    fn after_uninstall(&self) -> Result<()> {
+        match &self.restorer {
+            Some(restorer) => restorer.prune(),
+            None => Ok(()),
+        }
+    }
+
+    fn manifest(&self) -> Option<&Path> {
+        self.manifest.as_deref()
+    }
+
+    fn prune(&self) -> bool {
+        self.args.prune
+    }
+
+    /// A destination copied by this run, or already byte-identical to the
+    /// source. One left alone because it differs (newer, `force: false`) is
+    /// not this entry's, and uninstall must not remove it.
+    fn is_managed(&self, src: &Path, dest: &Path) -> bool {
+        same_content(src, dest)
     }
 }
 
-/// True when `dest` exists and is at least as new as `src` (mtime skip).
+fn sets_attributes(args: &CopyArgs) -> bool {
+    args.mode.is_some() || args.dir_mode.is_some() || args.owner.is_some() || args.group.is_some()
+}
+
+/// True when `dest` exists with the size of `src` and is at least as new
+/// (mtime skip).
 ///
 /// Uses `metadata` only — no separate `exists()` — so the already-synced
 /// hot path pays fewer syscalls. Shared with Command bench.
@@ -148,23 +337,78 @@ pub fn should_skip_copy(src: &Path, dest: &Path) -> bool {
     let Ok(src_meta) = std::fs::metadata(src) else {
         return false;
     };
+    if dest_meta.len() != src_meta.len() {
+        return false;
+    }
     match (src_meta.modified(), dest_meta.modified()) {
         (Ok(src_mod), Ok(dest_mod)) => dest_mod >= src_mod,
         _ => false,
     }
 }
 
-/// Copy a single file, skipping when the destination is already at least as
-/// new as the source.
-fn copy_one(ops: &dyn FileOps, src: &Path, dest: &Path, progress: &FileProgress<'_>) -> Result<()> {
-    if should_skip_copy(src, dest) {
+/// True when `dest` is a file with the same bytes as `src` (hash skip).
+/// Sizes are compared first, so differing files are rarely read. An
+/// unreadable destination (e.g. root-only under `sudo`) counts as changed.
+/// Shared with Command bench.
+pub fn same_content(src: &Path, dest: &Path) -> bool {
+    let (Ok(src_meta), Ok(dest_meta)) = (std::fs::metadata(src), std::fs::metadata(dest)) else {
+        return false;
+    };
+    if !dest_meta.is_file() || src_meta.len() != dest_meta.len() {
+        return false;
+    }
+    let (Ok(mut a), Ok(mut b)) = (std::fs::File::open(src), std::fs::File::open(dest)) else {
+        return false;
+    };
+    let mut buf_a = vec![0u8; 64 * 1024];
+    let mut buf_b = vec![0u8; 64 * 1024];
+    loop {
+        match (read_full(&mut a, &mut buf_a), read_full(&mut b, &mut buf_b)) {
+            (Ok(0), Ok(0)) => return true,
+            (Ok(n), Ok(m)) if n == m && buf_a[..n] == buf_b[..n] => {}
+            _ => return false,
+        }
+    }
+}
+
+/// Fill `buf` as far as the reader allows; short only at end of file.
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
+
+/// Copy a single file unless `compare` finds the destination unchanged. A
+/// changed destination that exists is only replaced once `clear` returns
+/// `true`. Returns whether the file was copied.
+fn copy_one(
+    ops: &dyn FileOps,
+    src: &Path,
+    dest: &Path,
+    compare: CopyCompare,
+    progress: &FileProgress<'_>,
+    clear: impl FnOnce() -> Result<bool>,
+) -> Result<bool> {
+    let (unchanged, why) = match compare {
+        CopyCompare::Mtime => (should_skip_copy(src, dest), "newer"),
+        CopyCompare::Hash => (same_content(src, dest), "unchanged"),
+    };
+    if unchanged {
         progress.note_skip(|| {
             format!(
-                "skip {} (newer)",
+                "skip {} ({why})",
                 crate::engine::context::display_path(dest)
             )
         });
-        return Ok(());
+        return Ok(false);
+    }
+    if dest.symlink_metadata().is_ok() && !clear()? {
+        return Ok(false);
     }
 
     progress.note_apply(|| {
@@ -174,7 +418,8 @@ fn copy_one(ops: &dyn FileOps, src: &Path, dest: &Path, progress: &FileProgress<
             crate::engine::context::display_path(dest)
         )
     });
-    ops.copy_file(src, dest)
+    ops.copy_file(src, dest)?;
+    Ok(true)
 }
 
 #[cfg(test)]
@@ -197,6 +442,13 @@ mod tests {
             default_shell: crate::config::types::Shell::Bash,
             task_name: "t".to_string(),
             depth: 0,
+            conflicts: None,
+            force: false,
+            env: Default::default(),
+            vars: Default::default(),
+            accept_remote_changes: false,
+            remote_dir: PathBuf::new(),
+            executed: Default::default(),
         }
     }
 
@@ -212,7 +464,10 @@ mod tests {
         let ctx = ctx_for(dir.path());
         let progress = FileProgress::new(&ctx, "copy");
         let ops = RecordingFs::default();
-        copy_one(&ops, &src, &dest, &progress).unwrap();
+        copy_one(&ops, &src, &dest, CopyCompare::Mtime, &progress, || {
+            Ok(true)
+        })
+        .unwrap();
         // Skipped: no copy_file recorded.
         assert!(ops.calls().is_empty());
     }
@@ -248,6 +503,36 @@ mod tests {
         assert!(!should_skip_copy(&src, &dest));
     }
 
+    #[test]
+    fn should_skip_copy_when_size_differs() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("s.txt");
+        let dest = dir.path().join("d.txt");
+        std::fs::write(&src, b"old").unwrap();
+        std::fs::write(&dest, b"edited").unwrap();
+        assert!(!should_skip_copy(&src, &dest));
+    }
+
+    #[test]
+    fn test_copy_one_hash_skips_same_content_regardless_of_mtime() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("s.txt");
+        let dest = dir.path().join("d.txt");
+        std::fs::write(&dest, b"same").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        std::fs::write(&src, b"same").unwrap();
+        let ctx = ctx_for(dir.path());
+        let progress = FileProgress::new(&ctx, "copy");
+        let ops = RecordingFs::default();
+
+        assert!(!copy_one(&ops, &src, &dest, CopyCompare::Hash, &progress, || Ok(true)).unwrap());
+        assert!(ops.calls().is_empty());
+
+        std::fs::write(&dest, b"diff").unwrap();
+        assert!(copy_one(&ops, &src, &dest, CopyCompare::Hash, &progress, || Ok(true)).unwrap());
+        assert_eq!(ops.calls().len(), 1);
+    }
+
     #[test]
     fn test_copy_one_copies_when_dest_missing() {
         let dir = tempdir().unwrap();
@@ -258,7 +543,10 @@ mod tests {
         let ctx = ctx_for(dir.path());
         let progress = FileProgress::new(&ctx, "copy");
         let ops = RecordingFs::default();
-        copy_one(&ops, &src, &dest, &progress).unwrap();
+        copy_one(&ops, &src, &dest, CopyCompare::Mtime, &progress, || {
+            Ok(true)
+        })
+        .unwrap();
         assert_eq!(
             ops.calls(),
             vec![format!("copy_file {} {}", src.display(), dest.display())]
@@ -278,6 +566,7 @@ mod tests {
             target: "/t".into(),
             ignore: vec![],
             sudo: true,
+            ..Default::default()
         };
         assert!(CopyKind::eligible_for_bulk_sudo(
             &src_dir,
@@ -316,7 +605,216 @@ mod tests {
                 target: "/t".into(),
                 ignore: vec![],
                 sudo: true,
+                ..Default::default()
+            },
+            Mode::Install
+        ));
+    }
+
+    fn kind_with(args: CopyArgs, tree_source: bool) -> CopyKind {
+        CopyKind {
+            file_attrs: FileAttrs::parse(
+                "copy",
+                "mode",
+                args.mode.as_deref(),
+                args.owner.as_deref(),
+                args.group.as_deref(),
+            )
+            .unwrap(),
+            dir_attrs: FileAttrs::parse(
+                "copy",
+                "dir_mode",
+                args.dir_mode.as_deref(),
+                args.owner.as_deref(),
+                args.group.as_deref(),
+            )
+            .unwrap(),
+            args,
+            tree_source,
+            conflicts: ConflictPolicy::Replace { backup: false },
+            backups: None,
+            restorer: None,
+            copied: BTreeMap::new(),
+            manifest: None,
+        }
+    }
+
+    #[test]
+    fn test_copied_file_gets_mode_and_owner() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("id_ed25519");
+        std::fs::write(&src, b"key").unwrap();
+        let dest = dir.path().join("out");
+        let kind = kind_with(
+            CopyArgs {
+                mode: Some("0600".into()),
+                owner: Some("me".into()),
+                sudo: true,
+                ..Default::default()
             },
+            false,
+        );
+        let ctx = ctx_for(dir.path());
+        let progress = FileProgress::new(&ctx, "copy");
+        let ops = RecordingFs::default();
+
+        kind.on_install_file(&ops, &src, &dest, &progress).unwrap();
+
+        assert_eq!(
+            ops.calls(),
+            vec![
+                format!("copy_file {} {}", src.display(), dest.display()),
+                format!("set_mode {} 600", dest.display()),
+                format!("set_owner {} me", dest.display()),
+            ]
+        );
+    }
+
+    /// A source strictly newer than an existing, different destination, so
+    /// `compare: mtime` finds it changed.
+    fn changed_pair(dir: &Path) -> (PathBuf, PathBuf) {
+        let src = dir.join("s.txt");
+        let dest = dir.join("d.txt");
+        std::fs::write(&dest, b"machine").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        std::fs::write(&src, b"repo").unwrap();
+        (src, dest)
+    }
+
+    struct Answers(Resolution);
+
+    impl crate::engine::conflict::ConflictPrompt for Answers {
+        fn ask(&self, _: &crate::engine::conflict::Conflict) -> crate::engine::conflict::Answer {
+            crate::engine::conflict::Answer {
+                resolution: self.0,
+                apply_to_all: false,
+            }
+        }
+    }
+
+    #[test]
+    fn test_skipped_conflict_keeps_a_foreign_destination_but_not_its_own_copy() {
+        let dir = tempdir().unwrap();
+        let (src, dest) = changed_pair(dir.path());
+        let ctx = ctx_for(dir.path());
+        let progress = FileProgress::new(&ctx, "copy");
+        let mut kind = CopyKind {
+            conflicts: ConflictPolicy::Skip,
+            ..kind_with(CopyArgs::default(), false)
+        };
+
+        let ops = RecordingFs::default();
+        kind.on_install_file(&ops, &src, &dest, &progress).unwrap();
+        assert!(ops.calls().is_empty());
+
+        kind.copied
+            .insert(dest.clone(), FileDigest::of(&dest, None).unwrap());
+        kind.on_install_file(&ops, &src, &dest, &progress).unwrap();
+        assert_eq!(
+            ops.calls(),
+            vec![format!("copy_file {} {}", src.display(), dest.display())]
+        );
+    }
+
+    #[test]
+    fn test_non_interactive_run_overwrites_an_older_destination() {
+        let dir = tempdir().unwrap();
+        let (src, dest) = changed_pair(dir.path());
+        let ctx = ctx_for(dir.path());
+        let progress = FileProgress::new(&ctx, "copy");
+        let kind = kind_with(CopyArgs::default(), false);
+
+        let ops = RecordingFs::default();
+        kind.on_install_file(&ops, &src, &dest, &progress).unwrap();
+        assert_eq!(
+            ops.calls(),
+            vec![format!("copy_file {} {}", src.display(), dest.display())]
+        );
+    }
+
+    #[test]
+    fn test_force_with_backup_moves_the_destination_aside_first() {
+        let dir = tempdir().unwrap();
+        let (src, dest) = changed_pair(dir.path());
+        let ctx = ctx_for(dir.path());
+        let progress = FileProgress::new(&ctx, "copy");
+        let kind = CopyKind {
+            conflicts: ConflictPolicy::Replace { backup: true },
+            backups: Some(BackupSession::new(dir.path(), "t", "k")),
+            ..kind_with(CopyArgs::default(), false)
+        };
+
+        let ops = RecordingFs::default();
+        kind.on_install_file(&ops, &src, &dest, &progress).unwrap();
+        let calls = ops.calls();
+        assert!(calls[0].starts_with(&format!("move_path {} ", dest.display())));
+        assert_eq!(
+            calls[1],
+            format!("copy_file {} {}", src.display(), dest.display())
+        );
+    }
+
+    #[test]
+    fn test_adopt_copies_the_destination_back_into_the_source() {
+        let dir = tempdir().unwrap();
+        let (src, dest) = changed_pair(dir.path());
+        let mut ctx = ctx_for(dir.path());
+        ctx.conflicts = Some(crate::engine::conflict::ConflictResolver::new(Answers(
+            Resolution::Adopt,
+        )));
+        let progress = FileProgress::new(&ctx, "copy");
+        let kind = CopyKind {
+            conflicts: ConflictPolicy::new(false, false, &ctx, "copy"),
+            ..kind_with(CopyArgs::default(), false)
+        };
+
+        let ops = RecordingFs::default();
+        kind.on_install_file(&ops, &src, &dest, &progress).unwrap();
+        assert!(ops.calls().is_empty());
+        assert_eq!(std::fs::read(&src).unwrap(), b"machine");
+        assert!(kind.is_managed(&src, &dest));
+    }
+
+    #[test]
+    fn test_dir_mode_skips_existing_parent_of_single_file() {
+        let dir = tempdir().unwrap();
+        let kind = kind_with(
+            CopyArgs {
+                dir_mode: Some("0700".into()),
+                ..Default::default()
+            },
+            false,
+        );
+        let ctx = ctx_for(dir.path());
+        let ops = RecordingFs::default();
+
+        kind.ensure_dir(&ops, dir.path(), &ctx).unwrap();
+        let created = dir.path().join("new");
+        kind.ensure_dir(&ops, &created, &ctx).unwrap();
+
+        assert_eq!(
+            ops.calls(),
+            vec![
+                format!("mkdir_p {}", dir.path().display()),
+                format!("mkdir_p {}", created.display()),
+                format!("set_mode {} 700", created.display()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_attributes_opt_out_of_bulk_sudo() {
+        let dir = tempdir().unwrap();
+        let args = CopyArgs {
+            src: dir.path().to_string_lossy().into(),
+            target: "/t".into(),
+            sudo: true,
+            dir_mode: Some("0755".into()),
+            ..Default::default()
+        };
+        assert!(!CopyKind::eligible_for_bulk_sudo(
+            dir.path(),
+            &args,
             Mode::Install
         ));
     }
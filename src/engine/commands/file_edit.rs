@@ -0,0 +1,70 @@
+//! Shared in-place text edit for `line_in_file` / `block_in_file`.
+//!
+//! Reads the current file unprivileged, computes the new text, and writes it
+//! back through a File ops adapter only when it changed — so `sudo` edits to
+//! `/etc/hosts` batch through SudoFs like any other privileged write.
+
+use std::path::Path;
+
+use crate::engine::context::{display_path, CommandContext};
+use crate::error::{Error, Result};
+use crate::utils::path::expand_path;
+
+use super::fs_ops;
+
+/// Apply `edit` to the text of `path` on a blocking thread. A missing file
+/// reads as empty; an edit that leaves a missing file empty creates nothing.
+pub async fn apply(
+    path: &str,
+    sudo: bool,
+    ctx: &CommandContext,
+    edit: impl FnOnce(&str) -> String + Send + 'static,
+) -> Result<()> {
+    let path = expand_path(path, Some(&ctx.config_dir));
+    let ctx = ctx.clone();
+    tokio::task::spawn_blocking(move || apply_sync(&path, sudo, &ctx, edit))
+        .await
+        .map_err(|e| Error::Other(e.to_string()))?
+}
+
+/// `edit` of `text` with its CRLF line endings read as LF. An edit that
+/// changes nothing returns `text` as is, so an untouched file keeps its
+/// endings.
+pub fn edit_lf(text: &str, edit: impl FnOnce(&str) -> String) -> String {
+    let lf = text.replace("\r\n", "\n");
+    let after = edit(&lf);
+    if after == lf {
+        text.to_string()
+    } else {
+        after
+    }
+}
+
+fn apply_sync(
+    path: &Path,
+    sudo: bool,
+    ctx: &CommandContext,
+    edit: impl FnOnce(&str) -> String,
+) -> Result<()> {
+    let current = match std::fs::read_to_string(path) {
+        Ok(text) => Some(text),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+    let before = current.as_deref().unwrap_or_default();
+    let after = edit(before);
+    if after == before {
+        ctx.log_progress(format!("{} unchanged", display_path(path)));
+        return Ok(());
+    }
+
+    let ops = fs_ops::select(sudo);
+    if current.is_none() {
+        if let Some(parent) = path.parent().filter(|p| !p.exists()) {
+            ops.mkdir_p(parent)?;
+        }
+    }
+    ctx.log_progress(format!("edit {}", display_path(path)));
+    ops.write_file(path, after.as_bytes())?;
+    ops.flush()
+}
@@ -13,7 +13,8 @@
 
 use std::path::Path;
 
-use crate::error::Result;
+use crate::config::types::parse_file_mode;
+use crate::error::{Error, Result};
 use crate::utils::sudo;
 
 /// The filesystem primitives `copy` and `symlink` need. Each method hides the
@@ -28,7 +29,21 @@ pub trait FileOps: Send + Sync {
     /// Tree-op driver / Tree materialization `ensure_dir` path does this).
     fn copy_file(&self, src: &Path, dest: &Path) -> Result<()>;
 
-    /// Create a symlink at `dest` pointing to `src`.
+    /// Write `contents` to `dest`, replacing any existing file.
+    ///
+    /// Callers must ensure `dest`'s parent directory already exists.
+    fn write_file(&self, dest: &Path, contents: &[u8]) -> Result<()>;
+
+    /// Set the permission bits of `path` (octal, e.g. `0o600`). No-op where
+    /// the platform has no Unix modes.
+    fn set_mode(&self, path: &Path, mode: u32) -> Result<()>;
+
+    /// Change the owner and/or group of `path` (user/group names). Needs root:
+    /// DirectFs refuses, SudoFs batches a `chown`.
+    fn set_owner(&self, path: &Path, owner: Option<&str>, group: Option<&str>) -> Result<()>;
+
+    /// Create a symlink at `dest` pointing to `src`. `src` is stored as given;
+    /// a relative `src` resolves against `dest`'s directory.
     fn create_symlink(&self, src: &Path, dest: &Path) -> Result<()>;
 
     /// Remove a regular file at `path`.
@@ -38,16 +53,89 @@ pub trait FileOps: Send + Sync {
     /// its contents.
     fn remove_path(&self, path: &Path) -> Result<()>;
 
+    /// Remove the directory at `path` only if it is empty. A missing or
+    /// non-empty directory is left alone, not an error.
+    fn remove_empty_dir(&self, path: &Path) -> Result<()>;
+
     /// Remove a symlink at `path`, handling directory symlinks on platforms
     /// (Windows) that distinguish them from file symlinks.
     fn remove_symlink(&self, path: &Path) -> Result<()>;
 
+    /// Move whatever exists at `src` (file, directory or symlink) to `dest`.
+    /// Callers must ensure `dest`'s parent directory already exists.
+    fn move_path(&self, src: &Path, dest: &Path) -> Result<()>;
+
     /// Flush any buffered work (SudoFs script batch). DirectFs is a no-op.
     fn flush(&self) -> Result<()> {
         Ok(())
     }
 }
 
+/// Mode and ownership applied to a path after a kind places it (`copy`,
+/// `template`, directory walks). Empty attributes are a no-op.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileAttrs {
+    pub mode: Option<u32>,
+    pub owner: Option<String>,
+    pub group: Option<String>,
+}
+
+impl FileAttrs {
+    /// Parse the YAML fields of one kind; `field` names the mode key in errors.
+    pub fn parse(
+        kind: &str,
+        field: &str,
+        mode: Option<&str>,
+        owner: Option<&str>,
+        group: Option<&str>,
+    ) -> Result<Self> {
+        let mode = match mode {
+            Some(raw) => Some(
+                parse_file_mode(raw)
+                    .ok_or_else(|| Error::Other(format!("{kind}: invalid {field} {raw:?}")))?,
+            ),
+            None => None,
+        };
+        Ok(Self {
+            mode,
+            owner: owner.map(str::to_string),
+            group: group.map(str::to_string),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mode.is_none() && self.owner.is_none() && self.group.is_none()
+    }
+
+    /// Apply to `path`. The mode is set when `changed` or when it differs on
+    /// disk; ownership is always (re)applied — under SudoFs it is one batched
+    /// `chown` line.
+    pub fn apply(&self, ops: &dyn FileOps, path: &Path, changed: bool) -> Result<()> {
+        if let Some(mode) = self.mode {
+            if changed || !has_mode(path, mode) {
+                ops.set_mode(path, mode)?;
+            }
+        }
+        if self.owner.is_some() || self.group.is_some() {
+            ops.set_owner(path, self.owner.as_deref(), self.group.as_deref())?;
+        }
+        Ok(())
+    }
+}
+
+/// True when `path` exists with exactly `mode` permission bits. Always true
+/// where the platform has no Unix modes.
+#[cfg(unix)]
+pub fn has_mode(path: &Path, mode: u32) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|m| m.permissions().mode() & 0o7777 == mode)
+}
+
+#[cfg(not(unix))]
+pub fn has_mode(_path: &Path, _mode: u32) -> bool {
+    true
+}
+
 /// Pick the adapter for a command based on whether it requested `sudo`.
 pub fn select(use_sudo: bool) -> Box<dyn FileOps> {
     if use_sudo {
@@ -71,6 +159,33 @@ impl FileOps for DirectFs {
         Ok(())
     }
 
+    fn write_file(&self, dest: &Path, contents: &[u8]) -> Result<()> {
+        std::fs::write(dest, contents)?;
+        Ok(())
+    }
+
+    fn set_mode(&self, path: &Path, mode: u32) -> Result<()> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+        }
+
+        #[cfg(not(unix))]
+        {
+            let _ = (path, mode);
+        }
+
+        Ok(())
+    }
+
+    fn set_owner(&self, path: &Path, _owner: Option<&str>, _group: Option<&str>) -> Result<()> {
+        Err(Error::Other(format!(
+            "changing owner/group of {} requires sudo",
+            path.display()
+        )))
+    }
+
     fn create_symlink(&self, src: &Path, dest: &Path) -> Result<()> {
         #[cfg(unix)]
         {
@@ -79,7 +194,12 @@ impl FileOps for DirectFs {
 
         #[cfg(windows)]
         {
-            if src.is_dir() {
+            // A relative `src` resolves against the link's directory, not the CWD.
+            let resolved = match dest.parent() {
+                Some(parent) if src.is_relative() => parent.join(src),
+                _ => src.to_path_buf(),
+            };
+            if resolved.is_dir() {
                 std::os::windows::fs::symlink_dir(src, dest)?;
             } else {
                 std::os::windows::fs::symlink_file(src, dest)?;
@@ -111,6 +231,14 @@ impl FileOps for DirectFs {
         Ok(())
     }
 
+    fn remove_empty_dir(&self, path: &Path) -> Result<()> {
+        match std::fs::remove_dir(path) {
+            Ok(()) => Ok(()),
+            Err(_) if !path.is_dir() || std::fs::read_dir(path)?.next().is_some() => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
     fn remove_symlink(&self, path: &Path) -> Result<()> {
         #[cfg(windows)]
         {
@@ -122,6 +250,26 @@ impl FileOps for DirectFs {
         std::fs::remove_file(path)?;
         Ok(())
     }
+
+    fn move_path(&self, src: &Path, dest: &Path) -> Result<()> {
+        match std::fs::rename(src, dest) {
+            Ok(()) => Ok(()),
+            // Across filesystems: regular files and symlinks can still be
+            // copied then removed; directories surface the rename error.
+            Err(err) if err.kind() == std::io::ErrorKind::CrossesDevices => {
+                let meta = src.symlink_metadata()?;
+                if meta.file_type().is_symlink() {
+                    self.create_symlink(&std::fs::read_link(src)?, dest)?;
+                } else if meta.is_file() {
+                    std::fs::copy(src, dest)?;
+                } else {
+                    return Err(err.into());
+                }
+                self.remove_path(src)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
 }
 
 /// Privileged filesystem access via `sudo`.
@@ -129,14 +277,24 @@ impl FileOps for DirectFs {
 /// Ops are buffered and applied in one `sudo bash -s` on [`FileOps::flush`]
 /// (ADR-0002). Callers that need an immediate single op can still use the
 /// helpers in [`crate::utils::sudo`] directly (e.g. bulk `cp -a`).
+///
+/// [`FileOps::write_file`] stages contents in an unprivileged temp file that
+/// the batch copies into place; staged files are removed after the flush.
+/// They live in a private (0700) directory with a random name and are created
+/// fresh with mode 0600, so another local user can neither read nor swap what
+/// root installs.
 pub struct SudoFs {
     pending: std::sync::Mutex<Vec<sudo::SudoOp>>,
+    stage_dir: std::sync::Mutex<Option<tempfile::TempDir>>,
+    staged: std::sync::Mutex<Vec<std::path::PathBuf>>,
 }
 
 impl Default for SudoFs {
     fn default() -> Self {
         Self {
             pending: std::sync::Mutex::new(Vec::new()),
+            stage_dir: std::sync::Mutex::new(None),
+            staged: std::sync::Mutex::new(Vec::new()),
         }
     }
 }
@@ -145,6 +303,42 @@ impl SudoFs {
     fn push(&self, op: sudo::SudoOp) {
         self.pending.lock().expect("SudoFs lock").push(op);
     }
+
+    fn stage(&self, contents: &[u8]) -> Result<std::path::PathBuf> {
+        static NEXT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        let n = NEXT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let mut dir = self.stage_dir.lock().expect("SudoFs lock");
+        if dir.is_none() {
+            let mut builder = tempfile::Builder::new();
+            builder.prefix("machine_setup-stage-");
+            #[cfg(unix)]
+            builder.permissions(std::os::unix::fs::PermissionsExt::from_mode(0o700));
+            *dir = Some(builder.tempdir()?);
+        }
+        let path = dir.as_ref().expect("stage dir").path().join(n.to_string());
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&path)?;
+        std::io::Write::write_all(&mut file, contents)?;
+
+        self.staged.lock().expect("SudoFs lock").push(path.clone());
+        Ok(path)
+    }
+
+    fn remove_staged(&self) {
+        for path in std::mem::take(&mut *self.staged.lock().expect("SudoFs lock")) {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+impl Drop for SudoFs {
+    fn drop(&mut self) {
+        self.remove_staged();
+    }
 }
 
 impl FileOps for SudoFs {
@@ -161,6 +355,31 @@ impl FileOps for SudoFs {
         Ok(())
     }
 
+    fn write_file(&self, dest: &Path, contents: &[u8]) -> Result<()> {
+        let staged = self.stage(contents)?;
+        self.push(sudo::SudoOp::Copy {
+            src: staged,
+            dest: dest.to_path_buf(),
+        });
+        Ok(())
+    }
+
+    fn set_mode(&self, path: &Path, mode: u32) -> Result<()> {
+        self.push(sudo::SudoOp::Chmod {
+            path: path.to_path_buf(),
+            mode,
+        });
+        Ok(())
+    }
+
+    fn set_owner(&self, path: &Path, owner: Option<&str>, group: Option<&str>) -> Result<()> {
+        self.push(sudo::SudoOp::Chown {
+            path: path.to_path_buf(),
+            spec: sudo::chown_spec(owner, group),
+        });
+        Ok(())
+    }
+
     fn create_symlink(&self, src: &Path, dest: &Path) -> Result<()> {
         self.push(sudo::SudoOp::Symlink {
             src: src.to_path_buf(),
@@ -187,19 +406,34 @@ impl FileOps for SudoFs {
         Ok(())
     }
 
+    fn remove_empty_dir(&self, path: &Path) -> Result<()> {
+        self.push(sudo::SudoOp::RemoveEmptyDir(path.to_path_buf()));
+        Ok(())
+    }
+
     fn remove_symlink(&self, path: &Path) -> Result<()> {
         // `rm -f` on a symlink removes the link itself, file or dir target.
         self.push(sudo::SudoOp::Remove(path.to_path_buf()));
         Ok(())
     }
 
+    fn move_path(&self, src: &Path, dest: &Path) -> Result<()> {
+        self.push(sudo::SudoOp::Move {
+            src: src.to_path_buf(),
+            dest: dest.to_path_buf(),
+        });
+        Ok(())
+    }
+
     fn flush(&self) -> Result<()> {
         let ops = std::mem::take(&mut *self.pending.lock().expect("SudoFs lock"));
         if ops.is_empty() {
             return Ok(());
         }
         let script = sudo::build_sudo_script(&ops);
-        sudo::sudo_bash_script(&script)
+        let result = sudo::sudo_bash_script(&script);
+        self.remove_staged();
+        result
     }
 }
 
@@ -233,6 +467,22 @@ impl FileOps for RecordingFs {
         self.record(format!("copy_file {} {}", src.display(), dest.display()));
         Ok(())
     }
+    fn write_file(&self, dest: &Path, contents: &[u8]) -> Result<()> {
+        self.record(format!("write_file {} {}", dest.display(), contents.len()));
+        Ok(())
+    }
+    fn set_mode(&self, path: &Path, mode: u32) -> Result<()> {
+        self.record(format!("set_mode {} {mode:o}", path.display()));
+        Ok(())
+    }
+    fn set_owner(&self, path: &Path, owner: Option<&str>, group: Option<&str>) -> Result<()> {
+        self.record(format!(
+            "set_owner {} {}",
+            path.display(),
+            sudo::chown_spec(owner, group)
+        ));
+        Ok(())
+    }
     fn create_symlink(&self, src: &Path, dest: &Path) -> Result<()> {
         self.record(format!(
             "create_symlink {} {}",
@@ -249,10 +499,18 @@ impl FileOps for RecordingFs {
         self.record(format!("remove_path {}", path.display()));
         Ok(())
     }
+    fn remove_empty_dir(&self, path: &Path) -> Result<()> {
+        self.record(format!("remove_empty_dir {}", path.display()));
+        Ok(())
+    }
     fn remove_symlink(&self, path: &Path) -> Result<()> {
         self.record(format!("remove_symlink {}", path.display()));
         Ok(())
     }
+    fn move_path(&self, src: &Path, dest: &Path) -> Result<()> {
+        self.record(format!("move_path {} {}", src.display(), dest.display()));
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -339,6 +597,43 @@ mod tests {
         assert_eq!(std::fs::read(target.join("keep.txt")).unwrap(), b"safe");
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_direct_write_file_and_set_mode() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempdir().unwrap();
+        let ops = DirectFs;
+
+        let dest = dir.path().join("rendered");
+        ops.write_file(&dest, b"hello").unwrap();
+        ops.set_mode(&dest, 0o600).unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), b"hello");
+        let mode = std::fs::metadata(&dest).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn test_sudofs_write_file_stages_copy_and_cleans_up() {
+        let ops = SudoFs::default();
+        ops.write_file(Path::new("/etc/rendered"), b"x").unwrap();
+        let pending = ops.pending.lock().unwrap().clone();
+        let sudo::SudoOp::Copy { src, dest } = &pending[0] else {
+            panic!("expected staged copy, got {pending:?}");
+        };
+        assert_eq!(dest, Path::new("/etc/rendered"));
+        assert_eq!(std::fs::read(src).unwrap(), b"x");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = |p: &Path| std::fs::metadata(p).unwrap().permissions().mode() & 0o777;
+            assert_eq!(mode(src), 0o600);
+            assert_eq!(mode(src.parent().unwrap()), 0o700);
+        }
+        let staged = src.clone();
+        drop(ops);
+        assert!(!staged.exists());
+    }
+
     #[test]
     fn test_sudofs_buffers_until_flush() {
         let ops = SudoFs::default();
@@ -348,4 +643,68 @@ mod tests {
         assert_eq!(pending.len(), 2);
         // Do not call flush() — that would invoke real sudo.
     }
+
+    #[test]
+    fn test_direct_remove_empty_dir_keeps_non_empty() {
+        let dir = tempdir().unwrap();
+        let full = dir.path().join("full");
+        let empty = dir.path().join("empty");
+        std::fs::create_dir_all(&full).unwrap();
+        std::fs::create_dir_all(&empty).unwrap();
+        std::fs::write(full.join("f"), b"x").unwrap();
+        let ops = DirectFs;
+
+        ops.remove_empty_dir(&full).unwrap();
+        ops.remove_empty_dir(&empty).unwrap();
+        ops.remove_empty_dir(&dir.path().join("missing")).unwrap();
+
+        assert!(full.join("f").exists());
+        assert!(!empty.exists());
+    }
+
+    #[test]
+    fn test_direct_move_path_moves_files_and_dirs() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("f");
+        let tree = dir.path().join("tree");
+        std::fs::write(&file, b"x").unwrap();
+        std::fs::create_dir_all(tree.join("sub")).unwrap();
+        let ops = DirectFs;
+
+        ops.move_path(&file, &dir.path().join("f2")).unwrap();
+        ops.move_path(&tree, &dir.path().join("tree2")).unwrap();
+
+        assert!(!file.exists());
+        assert_eq!(std::fs::read(dir.path().join("f2")).unwrap(), b"x");
+        assert!(!tree.exists());
+        assert!(dir.path().join("tree2/sub").is_dir());
+    }
+
+    #[test]
+    fn test_file_attrs_parse_and_apply() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("f");
+        std::fs::write(&path, b"x").unwrap();
+        let attrs = FileAttrs::parse("copy", "mode", Some("0600"), Some("root"), None).unwrap();
+        assert!(FileAttrs::parse("copy", "mode", Some("rw"), None, None).is_err());
+        assert!(FileAttrs::default().is_empty());
+
+        let ops = RecordingFs::default();
+        attrs.apply(&ops, &path, true).unwrap();
+        assert_eq!(
+            ops.calls(),
+            vec![
+                format!("set_mode {} 600", path.display()),
+                format!("set_owner {} root", path.display()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_direct_set_owner_requires_sudo() {
+        let err = DirectFs
+            .set_owner(Path::new("/tmp/x"), Some("root"), None)
+            .unwrap_err();
+        assert!(err.to_string().contains("requires sudo"));
+    }
 }
@@ -0,0 +1,173 @@
+use async_trait::async_trait;
+use regex::Regex;
+
+use crate::config::types::{LineInFileArgs, LineState};
+use crate::engine::context::CommandContext;
+use crate::engine::mode::Mode;
+use crate::error::{Error, Result};
+
+use super::file_edit;
+use super::CommandExecutor;
+
+pub struct LineInFileCommand {
+    args: LineInFileArgs,
+}
+
+impl LineInFileCommand {
+    pub fn new(args: LineInFileArgs) -> Self {
+        Self { args }
+    }
+}
+
+#[async_trait]
+impl CommandExecutor for LineInFileCommand {
+    async fn execute(&self, ctx: &CommandContext) -> Result<()> {
+        let regex = compile(self.args.regex.as_deref())?;
+        let line = self.args.line.clone();
+        let edit: Box<dyn FnOnce(&str) -> String + Send> = match (ctx.mode, self.args.state) {
+            (Mode::Install | Mode::Update, LineState::Present) => {
+                Box::new(move |text| ensure_line(text, &line, regex.as_ref()))
+            }
+            (Mode::Install | Mode::Update, LineState::Absent) => {
+                Box::new(move |text| remove_lines(text, &line, regex.as_ref()))
+            }
+            // Undo only what install added: the exact line. Regex-replaced
+            // originals are not recoverable, and `absent` has nothing to undo.
+            (Mode::Uninstall, LineState::Present) => {
+                Box::new(move |text| remove_lines(text, &line, None))
+            }
+            (Mode::Uninstall, LineState::Absent) => return Ok(()),
+        };
+        file_edit::apply(&self.args.path, self.args.sudo, ctx, move |text| {
+            file_edit::edit_lf(text, edit)
+        })
+        .await
+    }
+
+    fn description(&self) -> String {
+        self.args.to_string()
+    }
+}
+
+/// Compile an optional `regex` argument (shared with validate).
+pub fn compile(pattern: Option<&str>) -> Result<Option<Regex>> {
+    pattern
+        .map(|p| {
+            Regex::new(p).map_err(|e| Error::Other(format!("line_in_file: invalid regex: {e}")))
+        })
+        .transpose()
+}
+
+/// Ensure `line` exists: replace the last line matching `regex` if any,
+/// otherwise append unless an identical line is already present.
+fn ensure_line(text: &str, line: &str, regex: Option<&Regex>) -> String {
+    let (mut lines, terminated) = split(text);
+    if let Some(regex) = regex {
+        if let Some(idx) = lines.iter().rposition(|l| regex.is_match(l)) {
+            lines[idx] = line;
+            return join(&lines, terminated);
+        }
+    }
+    if lines.contains(&line) {
+        return text.to_string();
+    }
+    lines.push(line);
+    join(&lines, true)
+}
+
+/// Drop every line equal to `line` or matching `regex`.
+fn remove_lines(text: &str, line: &str, regex: Option<&Regex>) -> String {
+    let (lines, terminated) = split(text);
+    let kept: Vec<&str> = lines
+        .iter()
+        .copied()
+        .filter(|l| *l != line && !regex.is_some_and(|r| r.is_match(l)))
+        .collect();
+    if kept.len() == lines.len() {
+        return text.to_string();
+    }
+    join(&kept, terminated)
+}
+
+/// The `\n`-separated lines of `text`, and whether its last line ends in one.
+fn split(text: &str) -> (Vec<&str>, bool) {
+    match text.strip_suffix('\n') {
+        Some(body) => (body.split('\n').collect(), true),
+        None if text.is_empty() => (Vec::new(), false),
+        None => (text.split('\n').collect(), false),
+    }
+}
+
+fn join(lines: &[&str], terminated: bool) -> String {
+    if lines.is_empty() {
+        return String::new();
+    }
+    let mut out = lines.join("\n");
+    if terminated {
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ensure_line_appends_once() {
+        let once = ensure_line("a\nb", "c", None);
+        assert_eq!(once, "a\nb\nc\n");
+        assert_eq!(ensure_line(&once, "c", None), once);
+    }
+
+    #[test]
+    fn ensure_line_replaces_last_regex_match() {
+        let regex = Regex::new(r"^#?PermitRootLogin").unwrap();
+        let text = "Port 22\n#PermitRootLogin yes\nUsePAM yes\n";
+        assert_eq!(
+            ensure_line(text, "PermitRootLogin no", Some(&regex)),
+            "Port 22\nPermitRootLogin no\nUsePAM yes\n"
+        );
+    }
+
+    #[test]
+    fn ensure_line_without_match_appends() {
+        let regex = Regex::new(r"^PermitRootLogin").unwrap();
+        assert_eq!(
+            ensure_line("Port 22\n", "PermitRootLogin no", Some(&regex)),
+            "Port 22\nPermitRootLogin no\n"
+        );
+    }
+
+    #[test]
+    fn remove_lines_by_value_and_regex() {
+        let regex = Regex::new(r"^export FOO=").unwrap();
+        let text = "a\nexport FOO=1\nb\nexport FOO=2\n";
+        assert_eq!(remove_lines(text, "b", Some(&regex)), "a\n");
+        assert_eq!(remove_lines(text, "zzz", None), text);
+    }
+
+    #[test]
+    fn edits_keep_the_trailing_newline_as_it_was() {
+        let regex = Regex::new(r"^b").unwrap();
+        assert_eq!(ensure_line("a\nb\n", "c", Some(&regex)), "a\nc\n");
+        assert_eq!(ensure_line("a\nb", "c", Some(&regex)), "a\nc");
+        assert_eq!(remove_lines("a\nb\n\n", "b", None), "a\n\n");
+        assert_eq!(remove_lines("a\nb", "a", None), "b");
+    }
+
+    #[test]
+    fn crlf_is_read_as_lf_and_an_untouched_file_is_kept() {
+        let edit = |text: &str| file_edit::edit_lf(text, |t| ensure_line(t, "c", None));
+        assert_eq!(edit("a\r\nb\r\n"), "a\nb\nc\n");
+        assert_eq!(edit("a\r\nc\r\n"), "a\r\nc\r\n");
+        // A lone `\r` is content, not a line ending.
+        assert_eq!(edit("a\rb\n"), "a\rb\nc\n");
+    }
+
+    #[test]
+    fn invalid_regex_is_an_error() {
+        assert!(compile(Some("(")).is_err());
+        assert!(compile(None).unwrap().is_none());
+    }
+}
@@ -0,0 +1,223 @@
+//! Per-entry record of the paths a Command entry materialized.
+//!
+//! Some kinds cannot re-derive what they placed at uninstall time — an
+//! `archive` source may be a URL, or a newer release with a different file
+//! list. Those kinds write a manifest on install and uninstall from it, so
+//! exactly the recorded files (and the directories the entry created) go.
+//! `copy` records its destinations the same way (and `symlink` with `prune`),
+//! with a content digest per copied file: uninstall then removes exactly what
+//! the entry placed, skips files modified since, and `prune` lets an update
+//! remove the ones whose source is gone.
+//!
+//! Manifests are JSON files under `<temp_dir>/manifests/`, keyed by a hash of
+//! the task name, kind and the entry's `src`/`target`.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::error::{Error, Result};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Files placed by the entry.
+    #[serde(default)]
+    pub files: Vec<PathBuf>,
+    /// Directories the entry created (absent before the first install).
+    #[serde(default)]
+    pub dirs: Vec<PathBuf>,
+    /// Content digests of recorded regular files, so uninstall can tell
+    /// files modified since install apart.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub digests: BTreeMap<PathBuf, FileDigest>,
+}
+
+/// Content of one recorded file when it was placed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileDigest {
+    pub sha256: String,
+    pub len: u64,
+    /// Modification time in nanoseconds since the Unix epoch. While size and
+    /// mtime are unchanged the file is not hashed again.
+    pub modified: u64,
+}
+
+impl FileDigest {
+    /// Digest of the regular file at `path`, reusing `previous` when size and
+    /// mtime still match. `None` for links, directories and unreadable files.
+    pub fn of(path: &Path, previous: Option<&FileDigest>) -> Option<Self> {
+        let (len, modified) = stamp(path)?;
+        if let Some(previous) = previous.filter(|p| p.len == len && p.modified == modified) {
+            return Some(previous.clone());
+        }
+        Some(Self {
+            sha256: sha256_file(path).ok()?,
+            len,
+            modified,
+        })
+    }
+
+    /// Whether `path` still holds the recorded content.
+    pub fn matches(&self, path: &Path) -> bool {
+        match stamp(path) {
+            Some((len, modified)) if len == self.len && modified == self.modified => true,
+            Some((len, _)) if len == self.len => {
+                sha256_file(path).is_ok_and(|sha256| sha256 == self.sha256)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Size and mtime of a regular file (not followed through symlinks).
+fn stamp(path: &Path) -> Option<(u64, u64)> {
+    let meta = path.symlink_metadata().ok()?;
+    if !meta.is_file() {
+        return None;
+    }
+    let modified = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some((meta.len(), modified.as_nanos() as u64))
+}
+
+fn sha256_file(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect())
+}
+
+impl Manifest {
+    /// Manifest location for the entry identified by `key` (see [`entry_key`]).
+    pub fn path(temp_dir: &Path, key: &str) -> PathBuf {
+        temp_dir.join("manifests").join(format!("{key}.json"))
+    }
+
+    /// `None` when the entry has never been installed.
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(path)?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| Error::Other(format!("{}: {e}", path.display())))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content =
+            serde_json::to_string_pretty(self).map_err(|e| Error::Other(e.to_string()))?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    pub fn delete(path: &Path) -> Result<()> {
+        match std::fs::remove_file(path) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Recorded directories, deepest first — the order to remove them in.
+    pub fn dirs_deepest_first(&self) -> Vec<&Path> {
+        let mut dirs: Vec<&Path> = self.dirs.iter().map(PathBuf::as_path).collect();
+        dirs.sort_by_key(|d| std::cmp::Reverse(d.components().count()));
+        dirs
+    }
+}
+
+/// Stable key for one Command entry of `kind` inside `task_name`.
+pub fn entry_key(task_name: &str, kind: &str, src: &str, target: &str) -> String {
+    let mut hasher = Sha256::new();
+    for part in [task_name, kind, src, target] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    hasher
+        .finalize()
+        .iter()
+        .take(8)
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn save_load_roundtrip() {
+        let dir = tempdir().unwrap();
+        let path = Manifest::path(dir.path(), "abc");
+        assert_eq!(Manifest::load(&path).unwrap(), None);
+
+        let manifest = Manifest {
+            files: vec![PathBuf::from("/t/a")],
+            dirs: vec![PathBuf::from("/t")],
+            digests: BTreeMap::new(),
+        };
+        manifest.save(&path).unwrap();
+        assert_eq!(Manifest::load(&path).unwrap(), Some(manifest));
+
+        Manifest::delete(&path).unwrap();
+        Manifest::delete(&path).unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn entry_key_is_stable_and_field_sensitive() {
+        let a = entry_key("t", "archive", "s", "d");
+        assert_eq!(a, entry_key("t", "archive", "s", "d"));
+        assert_eq!(a.len(), 16);
+        assert_ne!(a, entry_key("t", "archive", "sd", ""));
+    }
+
+    #[test]
+    fn digest_detects_modified_content() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("f");
+        std::fs::write(&file, "placed").unwrap();
+        let digest = FileDigest::of(&file, None).unwrap();
+        assert!(digest.matches(&file));
+        assert_eq!(FileDigest::of(&file, Some(&digest)), Some(digest.clone()));
+
+        std::fs::write(&file, "edited by hand").unwrap();
+        assert!(!digest.matches(&file));
+        assert!(FileDigest::of(dir.path(), None).is_none());
+    }
+
+    #[test]
+    fn dirs_deepest_first_orders_children_before_parents() {
+        let manifest = Manifest {
+            files: vec![],
+            dirs: vec![
+                PathBuf::from("/t"),
+                PathBuf::from("/t/a/b"),
+                PathBuf::from("/t/a"),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            manifest.dirs_deepest_first(),
+            vec![Path::new("/t/a/b"), Path::new("/t/a"), Path::new("/t")]
+        );
+    }
+}
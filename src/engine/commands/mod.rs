@@ -1,11 +1,19 @@
+pub mod archive;
+pub mod backup;
+pub mod block_in_file;
 pub mod catalog;
 pub mod clone;
 pub mod copy;
+pub mod file_edit;
 pub mod fs_ops;
+pub mod line_in_file;
+pub mod manifest;
+pub mod package;
 pub mod progress_log;
 pub mod run;
 pub mod setup;
 pub mod symlink;
+pub mod template;
 pub mod tree;
 pub mod tree_op;
 
@@ -0,0 +1,429 @@
+//! `package` Command executor — declarative packages per manager family.
+//!
+//! The executor admits itself on the **Exclusive lane** of the detected
+//! manager (lane first, then permit — the same order the Runner uses) so it
+//! can queue its packages on the lane before waiting. Whichever `package`
+//! entry next holds the lane drains every queued request for the same Mode
+//! and runs them as one manager invocation (ADR-0010).
+
+use async_trait::async_trait;
+use tokio::process::Command;
+
+use crate::config::types::PackageArgs;
+use crate::engine::concurrency::ExclusiveLane;
+use crate::engine::context::CommandContext;
+use crate::engine::mode::Mode;
+use crate::error::{Error, Result};
+use crate::utils::process;
+
+use super::CommandExecutor;
+
+/// How one package-manager family is driven.
+pub struct Manager {
+    pub lane: ExclusiveLane,
+    /// Binary probed on `PATH` for detection.
+    binary: &'static str,
+    /// Install/upgrade/remove need root.
+    needs_root: bool,
+    /// Query argv; the package name is appended. Success + non-empty stdout = present.
+    query: &'static [&'static str],
+    install: &'static [&'static str],
+    upgrade: &'static [&'static str],
+    remove: &'static [&'static str],
+    /// The manager takes one package per invocation (winget).
+    one_per_call: bool,
+}
+
+/// Stable family order — first family with packages and a binary on `PATH` wins.
+const MANAGERS: &[Manager] = &[
+    Manager {
+        lane: ExclusiveLane::Apt,
+        binary: "apt-get",
+        needs_root: true,
+        query: &["dpkg-query", "-W", "-f=${db:Status-Abbrev}"],
+        install: &["apt-get", "install", "-y"],
+        upgrade: &["apt-get", "install", "--only-upgrade", "-y"],
+        remove: &["apt-get", "remove", "-y"],
+        one_per_call: false,
+    },
+    Manager {
+        lane: ExclusiveLane::Brew,
+        binary: "brew",
+        needs_root: false,
+        query: &["brew", "list", "--versions"],
+        install: &["brew", "install"],
+        upgrade: &["brew", "upgrade"],
+        remove: &["brew", "uninstall"],
+        one_per_call: false,
+    },
+    Manager {
+        lane: ExclusiveLane::Dnf,
+        binary: "dnf",
+        needs_root: true,
+        query: &["rpm", "-q"],
+        install: &["dnf", "install", "-y"],
+        upgrade: &["dnf", "upgrade", "-y"],
+        remove: &["dnf", "remove", "-y"],
+        one_per_call: false,
+    },
+    Manager {
+        lane: ExclusiveLane::Pacman,
+        binary: "pacman",
+        needs_root: true,
+        query: &["pacman", "-Q"],
+        install: &["pacman", "-S", "--needed", "--noconfirm"],
+        upgrade: &["pacman", "-S", "--noconfirm"],
+        remove: &["pacman", "-R", "--noconfirm"],
+        one_per_call: false,
+    },
+    Manager {
+        lane: ExclusiveLane::Apk,
+        binary: "apk",
+        needs_root: true,
+        query: &["apk", "info", "-e"],
+        install: &["apk", "add"],
+        upgrade: &["apk", "upgrade"],
+        remove: &["apk", "del"],
+        one_per_call: false,
+    },
+    Manager {
+        lane: ExclusiveLane::Winget,
+        binary: "winget",
+        needs_root: false,
+        query: &["winget", "list", "--exact", "--id"],
+        install: &[
+            "winget",
+            "install",
+            "--exact",
+            "--silent",
+            "--accept-package-agreements",
+            "--accept-source-agreements",
+            "--id",
+        ],
+        upgrade: &["winget", "upgrade", "--exact", "--silent", "--id"],
+        remove: &["winget", "uninstall", "--exact", "--silent", "--id"],
+        one_per_call: true,
+    },
+    Manager {
+        lane: ExclusiveLane::Choco,
+        binary: "choco",
+        needs_root: false,
+        query: &["choco", "list", "--exact", "--limit-output"],
+        install: &["choco", "install", "-y"],
+        upgrade: &["choco", "upgrade", "-y"],
+        remove: &["choco", "uninstall", "-y"],
+        one_per_call: false,
+    },
+];
+
+impl Manager {
+    fn packages<'a>(&self, args: &'a PackageArgs) -> &'a [String] {
+        args.families()
+            .find(|(family, _)| *family == self.lane.as_str())
+            .map(|(_, packages)| packages)
+            .unwrap_or_default()
+    }
+
+    fn verb(&self, mode: Mode) -> &'static [&'static str] {
+        match mode {
+            Mode::Install => self.install,
+            Mode::Update => self.upgrade,
+            Mode::Uninstall => self.remove,
+        }
+    }
+
+    /// Whether running this manager's mutating verbs needs root.
+    pub fn needs_root(&self) -> bool {
+        self.needs_root
+    }
+}
+
+/// The manager this entry would use on this machine, if any.
+pub fn detect(args: &PackageArgs) -> Option<&'static Manager> {
+    detect_with(args, on_path)
+}
+
+fn detect_with(args: &PackageArgs, available: impl Fn(&str) -> bool) -> Option<&'static Manager> {
+    MANAGERS
+        .iter()
+        .find(|m| !m.packages(args).is_empty() && available(m.binary))
+}
+
+/// Whether `binary` resolves on `PATH` (with Windows executable suffixes).
+fn on_path(binary: &str) -> bool {
+    let Some(path) = std::env::var_os("PATH") else {
+        return false;
+    };
+    std::env::split_paths(&path).any(|dir| {
+        let candidate = dir.join(binary);
+        candidate.is_file()
+            || (cfg!(windows)
+                && ["exe", "cmd", "bat"]
+                    .iter()
+                    .any(|ext| candidate.with_extension(ext).is_file()))
+    })
+}
+
+pub struct PackageCommand {
+    args: PackageArgs,
+}
+
+impl PackageCommand {
+    pub fn new(args: PackageArgs) -> Self {
+        Self { args }
+    }
+}
+
+#[async_trait]
+impl CommandExecutor for PackageCommand {
+    async fn execute(&self, ctx: &CommandContext) -> Result<()> {
+        let Some(manager) = detect(&self.args) else {
+            ctx.log_info(format!(
+                "No supported package manager found for {}",
+                self.args
+            ));
+            return Ok(());
+        };
+
+        let pending = pending_packages(manager, manager.packages(&self.args), ctx.mode).await;
+        if pending.is_empty() {
+            ctx.log_info(format!("{}: nothing to do for {}", manager.lane, ctx.mode));
+            return Ok(());
+        }
+
+        let key = ctx.mode.to_string();
+        let reply =
+            ctx.gate
+                .enqueue_lane_batch(manager.lane, key.clone(), pending, ctx.task_name.clone());
+
+        let _lane = match ctx.gate.try_acquire_lane(manager.lane) {
+            Some(permit) => permit,
+            None => {
+                ctx.log_info(format!("waiting for {} lane", manager.lane));
+                ctx.gate.acquire_lane(manager.lane).await
+            }
+        };
+        let _permit = ctx.gate.acquire().await;
+
+        let batch = ctx.gate.drain_lane_batch(manager.lane, &key);
+        if !batch.is_empty() {
+            run_batch(manager, batch, ctx).await;
+        }
+
+        match reply.await {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(message)) => Err(Error::ShellFailed(message)),
+            Err(_) => Err(Error::Other(format!(
+                "{} batch was dropped before it ran",
+                manager.lane
+            ))),
+        }
+    }
+
+    fn description(&self) -> String {
+        self.args.to_string()
+    }
+
+    /// Admits itself (lane, then permit) so queued packages can be batched.
+    fn occupies_concurrency_slot(&self) -> bool {
+        false
+    }
+}
+
+/// Packages that need the Mode's verb: missing ones on install, present ones
+/// on update and uninstall.
+async fn pending_packages(manager: &Manager, packages: &[String], mode: Mode) -> Vec<String> {
+    let mut pending = Vec::new();
+    for package in packages {
+        let present = is_present(manager, package).await;
+        let wanted = match mode {
+            Mode::Install => !present,
+            Mode::Update | Mode::Uninstall => present,
+        };
+        if wanted && !pending.contains(package) {
+            pending.push(package.clone());
+        }
+    }
+    pending
+}
+
+async fn is_present(manager: &Manager, package: &str) -> bool {
+    let (program, args) = manager.query.split_first().expect("query argv");
+    let Ok(output) = Command::new(program)
+        .args(args)
+        .arg(package)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .await
+    else {
+        return false;
+    };
+    if !output.status.success() {
+        return false;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    match manager.lane {
+        // `ii ` = desired install, currently installed.
+        ExclusiveLane::Apt => stdout.starts_with("ii"),
+        _ => !stdout.trim().is_empty(),
+    }
+}
+
+/// Run every drained request as one invocation (or one per package for
+/// managers that only take a single id) and reply to each requester.
+async fn run_batch(
+    manager: &Manager,
+    batch: Vec<crate::engine::concurrency::LaneBatchItem>,
+    ctx: &CommandContext,
+) {
+    let mut queued: Vec<String> = Vec::new();
+    for item in &batch {
+        for package in &item.items {
+            if !queued.contains(package) {
+                queued.push(package.clone());
+            }
+        }
+    }
+    // Re-check under the lane: an earlier batch may already have handled some.
+    let packages = pending_packages(manager, &queued, ctx.mode).await;
+    let others: Vec<&str> = batch
+        .iter()
+        .map(|item| item.task_name.as_str())
+        .filter(|name| *name != ctx.task_name)
+        .collect();
+    if !others.is_empty() {
+        ctx.log_info(format!(
+            "{}: batching packages for {}",
+            manager.lane,
+            others.join(", ")
+        ));
+    }
+
+    let result = if packages.is_empty() {
+        Ok(())
+    } else if manager.one_per_call {
+        let mut result = Ok(());
+        for package in &packages {
+            result = invoke(manager, std::slice::from_ref(package), ctx).await;
+            if result.is_err() {
+                break;
+            }
+        }
+        result
+    } else {
+        invoke(manager, &packages, ctx).await
+    };
+
+    let reply = result.map_err(|e| e.to_string());
+    for item in batch {
+        let _ = item.reply.send(reply.clone());
+    }
+}
+
+async fn invoke(manager: &Manager, packages: &[String], ctx: &CommandContext) -> Result<()> {
+    let argv = invocation(manager, ctx.mode, packages, on_path("sudo"));
+    ctx.log_progress(argv.join(" "));
+    let (program, args) = argv.split_first().expect("manager argv");
+    let child = Command::new(program)
+        .args(args)
+        .env("DEBIAN_FRONTEND", "noninteractive")
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| Error::ShellFailed(format!("Failed to run {program}: {e}")))?;
+    let status = process::stream_and_wait(child, ctx, process::StreamOptions::interactive())
+        .await
+        .map_err(|e| Error::ShellFailed(format!("Failed to wait for {program}: {e}")))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(Error::ShellFailed(format!(
+            "{} exited with code {}",
+            manager.binary,
+            status.code().unwrap_or(-1)
+        )))
+    }
+}
+
+/// Full argv for one manager call, with a `sudo` prefix when root is needed
+/// and `sudo` is available (root containers often ship without it).
+fn invocation(manager: &Manager, mode: Mode, packages: &[String], have_sudo: bool) -> Vec<String> {
+    let mut argv = Vec::new();
+    if manager.needs_root && have_sudo {
+        argv.push("sudo".to_string());
+    }
+    argv.extend(manager.verb(mode).iter().map(|s| s.to_string()));
+    argv.extend(packages.iter().cloned());
+    argv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(yaml: &str) -> PackageArgs {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn detect_picks_first_family_with_packages_and_binary() {
+        let a = args("apt: [git]\nbrew: [git]");
+        let only_brew = detect_with(&a, |bin| bin == "brew").unwrap();
+        assert_eq!(only_brew.lane, ExclusiveLane::Brew);
+        let both = detect_with(&a, |_| true).unwrap();
+        assert_eq!(both.lane, ExclusiveLane::Apt);
+    }
+
+    #[test]
+    fn detect_ignores_managers_without_packages() {
+        let a = args("dnf: [git]");
+        assert!(detect_with(&a, |bin| bin == "apt-get").is_none());
+        assert_eq!(
+            detect_with(&a, |bin| bin == "dnf").unwrap().lane,
+            ExclusiveLane::Dnf
+        );
+    }
+
+    #[test]
+    fn invocation_uses_mode_verb_and_sudo_when_needed() {
+        let apt = &MANAGERS[0];
+        let pkgs = vec!["git".to_string(), "curl".to_string()];
+        assert_eq!(
+            invocation(apt, Mode::Install, &pkgs, true),
+            ["sudo", "apt-get", "install", "-y", "git", "curl"]
+        );
+        assert_eq!(
+            invocation(apt, Mode::Uninstall, &pkgs, false),
+            ["apt-get", "remove", "-y", "git", "curl"]
+        );
+        let brew = &MANAGERS[1];
+        assert_eq!(
+            invocation(brew, Mode::Update, &pkgs[..1], true),
+            ["brew", "upgrade", "git"]
+        );
+    }
+
+    #[test]
+    fn managers_cover_every_lane_once() {
+        for lane in [
+            ExclusiveLane::Apt,
+            ExclusiveLane::Brew,
+            ExclusiveLane::Dnf,
+            ExclusiveLane::Pacman,
+            ExclusiveLane::Apk,
+            ExclusiveLane::Winget,
+            ExclusiveLane::Choco,
+        ] {
+            assert_eq!(MANAGERS.iter().filter(|m| m.lane == lane).count(), 1);
+        }
+    }
+
+    #[test]
+    fn display_lists_non_empty_families() {
+        assert_eq!(
+            args("apt: [git, curl]\nbrew: [git]").to_string(),
+            "package: apt: git curl | brew: git"
+        );
+    }
+}
@@ -38,7 +38,13 @@ async fn run_for_mode(args: &RunArgs, mode: Mode, ctx: &CommandContext) -> Resul
     }
 
     let active_shell = args.shell.as_ref().unwrap_or(&ctx.default_shell);
-    let script = shell::build_shell_command(commands, active_shell, &args.env)?;
+    let script = if ctx.env.is_empty() {
+        shell::build_shell_command(commands, active_shell, &args.env)?
+    } else {
+        let mut env = ctx.env.clone();
+        env.extend(args.env.clone());
+        shell::build_shell_command(commands, active_shell, &env)?
+    };
 
     if !args.quiet {
         ctx.log_info(format!(
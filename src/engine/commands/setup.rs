@@ -3,7 +3,7 @@ use std::sync::Arc;
 
 use crate::config::types::MachineSetupArgs;
 use crate::engine::context::CommandContext;
-use crate::error::Result;
+use crate::error::{Error, Result};
 use crate::utils::path::expand_path;
 
 use super::CommandExecutor;
@@ -38,31 +38,76 @@ impl CommandExecutor for SetupCommand {
 async fn run_sub_config(args: &MachineSetupArgs, ctx: &CommandContext) -> Result<()> {
     let is_url = crate::config::is_url(&args.config);
 
-    // For URLs we pass the string through to load_config; for local paths
-    // we resolve via expand_path against the parent's config_dir.
+    // For URLs we pass the string through to load_config; a git source is
+    // checked out under the parent's remote_dir and loaded from there; local
+    // paths resolve via expand_path against the parent's config_dir.
     let config_str: std::borrow::Cow<'_, str> = if is_url {
         std::borrow::Cow::Borrowed(&args.config)
+    } else if crate::config::repo::is_git_source(&args.config) {
+        ctx.log_info(format!("Checking out sub-config: {}", args.config));
+        let path = crate::config::repo::checkout(&args.config, &ctx.remote_dir)?;
+        std::borrow::Cow::Owned(path.to_string_lossy().into_owned())
     } else {
         let path = expand_path(&args.config, Some(&ctx.config_dir));
+        if args.optional && crate::config::resolve_config_path(&path).is_err() {
+            ctx.log_info(format!(
+                "Optional sub-config not found, skipping: {}",
+                path.display()
+            ));
+            return Ok(());
+        }
         std::borrow::Cow::Owned(path.to_string_lossy().into_owned())
     };
 
     ctx.log_info(format!("Loading sub-config: {config_str}"));
 
-    let config = crate::config::load_config(&config_str)?;
+    // Nobody can be asked mid-run: a changed URL sub-config needs `--yes`.
+    let on_change = if ctx.accept_remote_changes {
+        crate::config::remote::OnChange::Accept
+    } else {
+        crate::config::remote::OnChange::Refuse
+    };
+    let config = crate::config::load_config_remote(
+        &config_str,
+        &crate::config::remote::Remote {
+            temp_dir: &ctx.remote_dir,
+            on_change,
+        },
+    )?;
 
     // Resolve the sub-config's directory for its own relative paths. URLs
     // and unresolvable paths fall back to the parent's config_dir.
     let sub_config_dir = crate::config::resolve_config_dir(&config_str, &ctx.config_dir);
 
+    // Selected Tasks run with their dependencies (`--with-deps` semantics).
+    let selected: Vec<String> = args.task.iter().chain(&args.tasks).cloned().collect();
+    if let Some(missing) = selected.iter().find(|t| !config.tasks.contains_key(*t)) {
+        return Err(Error::TaskNotFound(missing.clone()));
+    }
+    let task_names = if selected.is_empty() {
+        config.tasks.keys().cloned().collect()
+    } else {
+        crate::config::selection::expand_for_mode(&config, &selected, ctx.mode, true)?
+    };
+
+    // Nested entries see the parent's `env` / `vars`, overridden by this one's.
+    let mut env = ctx.env.clone();
+    env.extend(args.env.clone());
+    let mut vars = ctx.vars.clone();
+    vars.extend(args.vars.clone());
+
     let runner = crate::engine::runner::TaskRunner::new(config, ctx.mode, Arc::clone(&ctx.events))
         .with_gate(Arc::clone(&ctx.gate))
         .with_config_dir(sub_config_dir)
-        .with_depth(ctx.depth + 1);
+        .with_depth(ctx.depth + 1)
+        .with_conflicts(ctx.conflicts.clone())
+        .with_env(env)
+        .with_vars(vars)
+        .with_accept_remote_changes(ctx.accept_remote_changes)
+        .with_remote_dir(ctx.remote_dir.clone())
+        .with_executed(Arc::clone(&ctx.executed));
 
-    if let Some(task_name) = &args.task {
-        runner.run_single_task(task_name, false).await
-    } else {
-        runner.run_all(false).await
-    }
+    runner
+        .run_tasks(&task_names, args.force.resolve(ctx.force))
+        .await
 }
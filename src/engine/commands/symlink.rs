@@ -1,11 +1,18 @@
 use async_trait::async_trait;
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 use crate::config::types::SymlinkArgs;
-use crate::engine::context::CommandContext;
+use crate::engine::conflict::{self, ConflictPolicy, Resolution};
+use crate::engine::context::{display_path, CommandContext};
+use crate::engine::mode::Mode;
 use crate::error::{Error, Result};
+use crate::utils::ignore_rules::IgnoreRules;
+use crate::utils::path::{expand_path, relative_to};
 
-use super::fs_ops::FileOps;
+use super::backup::{BackupSession, Restorer};
+use super::fs_ops::{self, FileAttrs, FileOps};
+use super::manifest;
 use super::progress_log::FileProgress;
 use super::tree;
 use super::tree_op::{self, TreeOpKind};
@@ -24,11 +31,37 @@ impl SymlinkCommand {
 #[async_trait]
 impl CommandExecutor for SymlinkCommand {
     async fn execute(&self, ctx: &CommandContext) -> Result<()> {
+        let entry =
+            manifest::entry_key(&ctx.task_name, "symlink", &self.args.src, &self.args.target);
+        let conflicts = ConflictPolicy::new(self.args.force, self.args.backup, ctx, "symlink");
+        let backups = (conflicts.may_back_up() && ctx.mode != Mode::Uninstall)
+            .then(|| BackupSession::new(&ctx.temp_dir, &ctx.task_name, &entry));
+        let restorer = if ctx.mode == Mode::Uninstall {
+            Some(Restorer::load(&ctx.temp_dir, &entry)?)
+        } else {
+            None
+        };
         tree_op::execute(
             &self.args.src,
             &self.args.target,
             SymlinkKind {
                 args: self.args.clone(),
+                dir_attrs: FileAttrs::parse(
+                    "symlink",
+                    "dir_mode",
+                    self.args.dir_mode.as_deref(),
+                    None,
+                    None,
+                )?,
+                src: expand_path(&self.args.src, Some(&ctx.config_dir)),
+                tree_source: expand_path(&self.args.src, Some(&ctx.config_dir)).is_dir(),
+                conflicts,
+                backups,
+                restorer,
+                manifest: self
+                    .args
+                    .prune
+                    .then(|| manifest::Manifest::path(&ctx.temp_dir, &entry)),
             },
             ctx,
         )
@@ -42,6 +75,21 @@ impl CommandExecutor for SymlinkCommand {
 
 struct SymlinkKind {
     args: SymlinkArgs,
+    /// Applied to the real directories of a linked tree, and to a parent
+    /// directory a single-file link creates.
+    dir_attrs: FileAttrs,
+    /// `src`, expanded against the config directory.
+    src: PathBuf,
+    tree_source: bool,
+    /// What to do with a destination this entry did not place.
+    conflicts: ConflictPolicy,
+    /// Install with `force` + `backup`, or an interactive run: where replaced
+    /// paths go.
+    backups: Option<BackupSession>,
+    /// Uninstall: puts backed-up paths back once their link is gone.
+    restorer: Option<Restorer>,
+    /// With `prune`: the Entry manifest of links this entry made.
+    manifest: Option<PathBuf>,
 }
 
 impl TreeOpKind for SymlinkKind {
@@ -70,8 +118,39 @@ impl TreeOpKind for SymlinkKind {
         None
     }
 
+    fn try_short_circuit_install(
+        &self,
+        src: &Path,
+        target: &Path,
+        ctx: &CommandContext,
+    ) -> Option<Result<()>> {
+        (self.args.fold && src.is_dir()).then(|| {
+            self.run_fold(src, target, ctx, "symlink", |fold, src, dest| {
+                fold.place_dir(src, dest)
+            })
+        })
+    }
+
+    fn try_short_circuit_uninstall(
+        &self,
+        src: &Path,
+        target: &Path,
+        ctx: &CommandContext,
+    ) -> Option<Result<()>> {
+        (self.args.fold && src.is_dir()).then(|| {
+            self.run_fold(src, target, ctx, "symlink remove", |fold, src, dest| {
+                fold.remove_dir(src, dest)
+            })
+        })
+    }
+
     fn ensure_dir(&self, ops: &dyn FileOps, dir: &Path, ctx: &CommandContext) -> Result<()> {
-        tree::ensure_real_dir(ops, dir, |msg| ctx.log_progress(msg))
+        let created = dir.symlink_metadata().is_err();
+        tree::ensure_real_dir(ops, dir, |msg| ctx.log_progress(msg))?;
+        if self.tree_source || created {
+            self.dir_attrs.apply(ops, dir, created)?;
+        }
+        Ok(())
     }
 
     fn on_install_file(
@@ -81,7 +160,15 @@ impl TreeOpKind for SymlinkKind {
         dest: &Path,
         progress: &FileProgress<'_>,
     ) -> Result<()> {
-        symlink_one(ops, src, dest, self.args.force, progress)
+        symlink_one(
+            ops,
+            src,
+            dest,
+            &self.conflicts,
+            self.args.relative,
+            self.backups.as_ref(),
+            progress,
+        )
     }
 
     fn on_uninstall_file(
@@ -90,31 +177,410 @@ impl TreeOpKind for SymlinkKind {
         dest: &Path,
         progress: &FileProgress<'_>,
     ) -> Result<()> {
-        remove_link(ops, dest, progress)
+        // Only a link into this entry's source is ours to remove.
+        if dest.symlink_metadata().is_ok() && !links_into(dest, &self.src) {
+            progress.note_skip(|| {
+                format!(
+                    "skip {} (does not link into {})",
+                    display_path(dest),
+                    display_path(&self.src)
+                )
+            });
+            return Ok(());
+        }
+        unlink_and_restore(ops, dest, self.restorer.as_ref(), progress)
+    }
+
+    fn after_uninstall(&self) -> Result<()> {
+        match &self.restorer {
+            Some(restorer) => restorer.prune(),
+            None => Ok(()),
+        }
+    }
+
+    fn manifest(&self) -> Option<&Path> {
+        self.manifest.as_deref()
+    }
+
+    fn prune(&self) -> bool {
+        self.args.prune
+    }
+
+    fn is_managed(&self, src: &Path, dest: &Path) -> bool {
+        std::fs::read_link(dest).is_ok_and(|link| same_path(&resolve_link(dest, &link), src))
+    }
+
+    fn on_remove_recorded(
+        &self,
+        ops: &dyn FileOps,
+        dest: &Path,
+        progress: &FileProgress<'_>,
+    ) -> Result<()> {
+        // A recorded link the user has since replaced, or pointed elsewhere,
+        // is theirs now.
+        if links_into(dest, &self.src) {
+            unlink_and_restore(ops, dest, self.restorer.as_ref(), progress)?;
+        } else if dest.is_symlink() {
+            progress.note_skip(|| {
+                format!(
+                    "skip {} (no longer links into {})",
+                    crate::engine::context::display_path(dest),
+                    crate::engine::context::display_path(&self.src)
+                )
+            });
+        }
+        Ok(())
+    }
+}
+
+impl SymlinkKind {
+    /// Run one [`Fold`] walk from `src` onto `target` with its own File ops.
+    fn run_fold(
+        &self,
+        src: &Path,
+        target: &Path,
+        ctx: &CommandContext,
+        label: &'static str,
+        step: fn(&mut Fold<'_>, &Path, &Path) -> Result<()>,
+    ) -> Result<()> {
+        let ops = fs_ops::select(self.args.sudo);
+        let progress = FileProgress::new(ctx, label);
+        let mut fold = Fold {
+            kind: self,
+            ops: ops.as_ref(),
+            progress: &progress,
+            rules: IgnoreRules::load(src, &self.args.ignore)?,
+            src_root: src,
+            planned: HashMap::new(),
+            placed: Vec::new(),
+            created: Vec::new(),
+        };
+        if ctx.mode != Mode::Uninstall {
+            if let Some(parent) = target.parent() {
+                if parent.symlink_metadata().is_err() {
+                    ops.mkdir_p(parent)?;
+                    fold.created.push(parent.to_path_buf());
+                }
+            }
+        }
+        step(&mut fold, src, target)?;
+        let Fold {
+            placed, created, ..
+        } = fold;
+        progress.finish();
+        ops.flush()?;
+        if ctx.mode == Mode::Uninstall {
+            Ok(())
+        } else {
+            tree_op::record_install(self, placed, created, ctx)
+        }
     }
 }
 
-/// Create one symlink at `dest` pointing to `src`. When something already
-/// exists at `dest`, either replace it (`force`) or skip it.
+/// What sits at a target path, as far as a [`Fold`] walk is concerned.
+#[derive(Debug, Clone)]
+enum Dest {
+    Missing,
+    /// A symlink, with its target resolved against the link's directory.
+    Link(PathBuf),
+    Dir,
+    Other,
+}
+
+/// GNU stow-style placement for `fold: true`.
+///
+/// A source directory whose target path is free becomes one directory link.
+/// When a later source needs a directory that is a link into another source
+/// tree, that link is unfolded: replaced by a real directory holding one link
+/// per entry of the other tree, and both sources then merge into it.
+/// Directories with ignored entries below them are never folded, so ignored
+/// files cannot show through a link.
+struct Fold<'a> {
+    kind: &'a SymlinkKind,
+    ops: &'a dyn FileOps,
+    progress: &'a FileProgress<'a>,
+    rules: IgnoreRules,
+    src_root: &'a Path,
+    /// Paths this walk already changed. SudoFs applies ops only at flush, so
+    /// these override what the filesystem still shows.
+    planned: HashMap<PathBuf, Dest>,
+    /// `(src, dest)` links this walk placed or found in place, and the
+    /// directories it created, for the Entry manifest.
+    placed: Vec<(PathBuf, PathBuf)>,
+    created: Vec<PathBuf>,
+}
+
+impl Fold<'_> {
+    fn state(&self, path: &Path) -> Dest {
+        if let Some(dest) = self.planned.get(path) {
+            return dest.clone();
+        }
+        match path.symlink_metadata() {
+            Ok(meta) if meta.file_type().is_symlink() => match std::fs::read_link(path) {
+                Ok(link) => Dest::Link(resolve_link(path, &link)),
+                Err(_) => Dest::Other,
+            },
+            Ok(meta) if meta.is_dir() => Dest::Dir,
+            Ok(_) => Dest::Other,
+            Err(_) => Dest::Missing,
+        }
+    }
+
+    fn place_dir(&mut self, src: &Path, dest: &Path) -> Result<()> {
+        match self.state(dest) {
+            Dest::Missing if self.foldable(src)? => self.place_link(src, dest),
+            Dest::Missing => {
+                self.ops.mkdir_p(dest)?;
+                self.kind.dir_attrs.apply(self.ops, dest, true)?;
+                self.planned.insert(dest.to_path_buf(), Dest::Dir);
+                self.created.push(dest.to_path_buf());
+                self.place_children(src, dest)
+            }
+            Dest::Link(to) if same_path(&to, src) => {
+                self.progress
+                    .note_skip(|| format!("skip {} (linked)", display_path(dest)));
+                self.placed.push((src.to_path_buf(), dest.to_path_buf()));
+                Ok(())
+            }
+            Dest::Link(to) if to.is_dir() => {
+                self.unfold(dest, &to)?;
+                self.place_children(src, dest)
+            }
+            Dest::Dir => {
+                self.kind.dir_attrs.apply(self.ops, dest, false)?;
+                self.place_children(src, dest)
+            }
+            Dest::Link(_) | Dest::Other => {
+                if self.clear_conflict(src, dest)? {
+                    self.place_dir(src, dest)
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    fn place_file(&mut self, src: &Path, dest: &Path) -> Result<()> {
+        match self.state(dest) {
+            Dest::Missing => self.place_link(src, dest),
+            Dest::Link(to) if same_path(&to, src) => {
+                self.progress
+                    .note_skip(|| format!("skip {} (linked)", display_path(dest)));
+                self.placed.push((src.to_path_buf(), dest.to_path_buf()));
+                Ok(())
+            }
+            _ => {
+                if self.clear_conflict(src, dest)? {
+                    self.place_link(src, dest)
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    fn place_children(&mut self, src: &Path, dest: &Path) -> Result<()> {
+        for (child, is_dir) in self.children(src)? {
+            let name = child.file_name().expect("read_dir entries have a name");
+            if is_dir {
+                self.place_dir(&child, &dest.join(name))?;
+            } else {
+                self.place_file(&child, &dest.join(name))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn remove_dir(&mut self, src: &Path, dest: &Path) -> Result<()> {
+        match self.state(dest) {
+            Dest::Link(to) if same_path(&to, src) => self.unlink(dest),
+            Dest::Dir => {
+                for (child, is_dir) in self.children(src)? {
+                    let child_dest = dest.join(child.file_name().expect("named"));
+                    if is_dir {
+                        self.remove_dir(&child, &child_dest)?;
+                    } else if matches!(self.state(&child_dest), Dest::Link(to) if same_path(&to, &child))
+                    {
+                        self.unlink(&child_dest)?;
+                    }
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Non-ignored entries of `dir`, sorted, with whether each is a real
+    /// directory (symlinks inside the source are linked as-is).
+    fn children(&self, dir: &Path) -> Result<Vec<(PathBuf, bool)>> {
+        let mut children = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let is_dir = entry.file_type()?.is_dir();
+            let path = entry.path();
+            if !self.rules.is_ignored(self.relative(&path), is_dir) {
+                children.push((path, is_dir));
+            }
+        }
+        children.sort();
+        Ok(children)
+    }
+
+    /// Whether `src` can be one link: nothing below it is ignored.
+    fn foldable(&self, src: &Path) -> Result<bool> {
+        for entry in walkdir::WalkDir::new(src).min_depth(1) {
+            let entry = entry.map_err(|e| Error::Other(e.to_string()))?;
+            if self
+                .rules
+                .is_ignored(self.relative(entry.path()), entry.file_type().is_dir())
+            {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn relative<'p>(&self, path: &'p Path) -> &'p Path {
+        path.strip_prefix(self.src_root).unwrap_or(path)
+    }
+
+    fn link(&mut self, src: &Path, dest: &Path) -> Result<()> {
+        self.progress
+            .note_apply(|| format!("link {} → {}", display_path(src), display_path(dest)));
+        self.ops
+            .create_symlink(&link_value(src, dest, self.kind.args.relative), dest)?;
+        self.planned
+            .insert(dest.to_path_buf(), Dest::Link(src.to_path_buf()));
+        Ok(())
+    }
+
+    /// [`Fold::link`] for a destination of this entry's own source.
+    fn place_link(&mut self, src: &Path, dest: &Path) -> Result<()> {
+        self.link(src, dest)?;
+        self.placed.push((src.to_path_buf(), dest.to_path_buf()));
+        Ok(())
+    }
+
+    fn unlink(&mut self, dest: &Path) -> Result<()> {
+        unlink_and_restore(self.ops, dest, self.kind.restorer.as_ref(), self.progress)?;
+        self.planned.insert(dest.to_path_buf(), Dest::Missing);
+        Ok(())
+    }
+
+    /// Replace a directory link into another tree with a real directory that
+    /// links each of that tree's entries.
+    fn unfold(&mut self, dest: &Path, other: &Path) -> Result<()> {
+        self.progress
+            .note_apply(|| format!("unfold {} (shared)", display_path(dest)));
+        self.ops.remove_symlink(dest)?;
+        self.ops.mkdir_p(dest)?;
+        self.kind.dir_attrs.apply(self.ops, dest, true)?;
+        self.planned.insert(dest.to_path_buf(), Dest::Dir);
+        let mut entries: Vec<PathBuf> = std::fs::read_dir(other)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<std::io::Result<_>>()?;
+        entries.sort();
+        for entry in entries {
+            let name = entry.file_name().expect("read_dir entries have a name");
+            self.link(&entry, &dest.join(name))?;
+        }
+        Ok(())
+    }
+
+    /// Something foreign sits at `dest`: clear it as the entry's
+    /// [`ConflictPolicy`] says (and report `true`), or leave it.
+    fn clear_conflict(&mut self, src: &Path, dest: &Path) -> Result<bool> {
+        let resolution = self.kind.conflicts.resolve(src, dest);
+        if !clear_existing(
+            self.ops,
+            src,
+            dest,
+            resolution,
+            self.kind.backups.as_ref(),
+            self.progress,
+        )? {
+            return Ok(false);
+        }
+        self.planned.insert(dest.to_path_buf(), Dest::Missing);
+        Ok(true)
+    }
+}
+
+/// `link` as read from the symlink at `at`, made absolute.
+fn resolve_link(at: &Path, link: &Path) -> PathBuf {
+    match at.parent() {
+        Some(parent) if link.is_relative() => parent.join(link),
+        _ => link.to_path_buf(),
+    }
+}
+
+/// Whether `dest` is a symlink to `src` or to a path under it. Checked
+/// lexically as well, so a link whose source was deleted still counts.
+fn links_into(dest: &Path, src: &Path) -> bool {
+    let Ok(link) = std::fs::read_link(dest) else {
+        return false;
+    };
+    let src = crate::utils::path::normalize(src);
+    let canonical_src = src.canonicalize().ok();
+    let under_src = |target: &Path| {
+        let target = crate::utils::path::normalize(target);
+        target.starts_with(&src)
+            || canonical_src
+                .as_ref()
+                .is_some_and(|c| target.starts_with(c))
+    };
+    // A relative link is stored against the canonical parent (`link_value`).
+    let canonical_dest = dest
+        .parent()
+        .and_then(|parent| parent.canonicalize().ok())
+        .zip(dest.file_name())
+        .map(|(parent, name)| parent.join(name));
+    under_src(&resolve_link(dest, &link))
+        || canonical_dest.is_some_and(|at| under_src(&resolve_link(&at, &link)))
+}
+
+/// Whether `a` and `b` name the same filesystem object.
+fn same_path(a: &Path, b: &Path) -> bool {
+    a == b || matches!((a.canonicalize(), b.canonicalize()), (Ok(a), Ok(b)) if a == b)
+}
+
+/// What to store in the link at `dest` for `src`: `src` itself, or with
+/// `relative` the path from `dest`'s directory (canonical where it exists, so
+/// a symlinked parent does not skew the `..` count). Falls back to `src` when
+/// no relative path exists (another Windows drive).
+pub fn link_value(src: &Path, dest: &Path, relative: bool) -> PathBuf {
+    let Some(parent) = dest.parent().filter(|_| relative) else {
+        return src.to_path_buf();
+    };
+    let parent = parent
+        .canonicalize()
+        .unwrap_or_else(|_| parent.to_path_buf());
+    let src = src.canonicalize().unwrap_or_else(|_| src.to_path_buf());
+    relative_to(&src, &parent).unwrap_or(src)
+}
+
+/// Create one symlink at `dest` pointing to `src` (stored relative to `dest`
+/// with `relative`). When something already exists at `dest`, `conflicts`
+/// decides: replace it, skip it, or adopt it into `src`. A link that already
+/// points to `src` is only re-made under `force`, and never backed up.
 fn symlink_one(
     ops: &dyn FileOps,
     src: &Path,
     dest: &Path,
-    force: bool,
+    conflicts: &ConflictPolicy,
+    relative: bool,
+    backups: Option<&BackupSession>,
     progress: &FileProgress<'_>,
 ) -> Result<()> {
     if dest.exists() || dest.symlink_metadata().is_ok() {
-        if force {
-            progress
-                .note_apply(|| format!("remove {}", crate::engine::context::display_path(dest)));
-            ops.remove_path(dest)?;
-        } else {
-            progress.note_skip(|| {
-                format!(
-                    "skip {} (exists)",
-                    crate::engine::context::display_path(dest)
-                )
-            });
+        let ours =
+            std::fs::read_link(dest).is_ok_and(|link| same_path(&resolve_link(dest, &link), src));
+        let resolution = match conflicts {
+            ConflictPolicy::Replace { .. } if ours => Resolution::Overwrite,
+            _ if ours => Resolution::Skip,
+            _ => conflicts.resolve(src, dest),
+        };
+        if !clear_existing(ops, src, dest, resolution, backups, progress)? {
             return Ok(());
         }
     }
@@ -127,17 +593,68 @@ fn symlink_one(
         )));
     }
 
-    progress.note_apply(|| {
-        format!(
-            "link {} → {}",
-            crate::engine::context::display_path(src),
-            crate::engine::context::display_path(dest)
-        )
-    });
-    ops.create_symlink(src, dest)
+    progress.note_apply(|| format!("link {} → {}", display_path(src), display_path(dest)));
+    ops.create_symlink(&link_value(src, dest, relative), dest)
+}
+
+/// Act on `resolution` for the existing `dest`; `true` once it is cleared
+/// for the link. Adopt copies a regular file into `src` first; anything else
+/// it leaves alone.
+fn clear_existing(
+    ops: &dyn FileOps,
+    src: &Path,
+    dest: &Path,
+    resolution: Resolution,
+    backups: Option<&BackupSession>,
+    progress: &FileProgress<'_>,
+) -> Result<bool> {
+    match resolution {
+        Resolution::Skip => {
+            progress.note_skip(|| format!("skip {} (exists)", display_path(dest)));
+            return Ok(false);
+        }
+        Resolution::Adopt => {
+            if !conflict::adopt_into_source(src, dest)? {
+                progress.note_skip(|| format!("skip {} (not a file to adopt)", display_path(dest)));
+                return Ok(false);
+            }
+            progress
+                .note_apply(|| format!("adopt {} into {}", display_path(dest), display_path(src)));
+            progress.note_apply(|| format!("remove {}", display_path(dest)));
+            ops.remove_path(dest)?;
+        }
+        Resolution::Backup => replace_existing(ops, dest, backups, progress)?,
+        Resolution::Overwrite => replace_existing(ops, dest, None, progress)?,
+    }
+    Ok(true)
 }
 
-/// Cheap self-link check: path equality first; canonicalize only when both exist.
+/// Clear `dest` for a replace: move it into `backups` when given, delete it
+/// otherwise.
+fn replace_existing(
+    ops: &dyn FileOps,
+    dest: &Path,
+    backups: Option<&BackupSession>,
+    progress: &FileProgress<'_>,
+) -> Result<()> {
+    match backups {
+        Some(backups) => {
+            let stored = backups.stash(ops, dest)?;
+            progress.note_apply(|| {
+                format!("backup {} to {}", display_path(dest), display_path(&stored))
+            });
+            Ok(())
+        }
+        None => {
+            progress.note_apply(|| format!("remove {}", display_path(dest)));
+            ops.remove_path(dest)
+        }
+    }
+}
+
+/// Cheap self-link check: path equality first; canonicalize only when both
+/// exist. `dest` is a self-link when its location — not what a link already
+/// there points to — is `src`, e.g. via a parent directory link into the source.
 fn would_self_symlink(src: &Path, dest: &Path) -> bool {
     if src == dest {
         return true;
@@ -146,8 +663,11 @@ fn would_self_symlink(src: &Path, dest: &Path) -> bool {
     if dest.symlink_metadata().is_err() {
         return false;
     }
-    match (src.canonicalize(), dest.canonicalize()) {
-        (Ok(src_canon), Ok(dest_canon)) => src_canon == dest_canon,
+    let (Some(parent), Some(name)) = (dest.parent(), dest.file_name()) else {
+        return false;
+    };
+    match (parent.canonicalize(), src.canonicalize()) {
+        (Ok(parent), Ok(src_canon)) => parent.join(name) == src_canon,
         _ => false,
     }
 }
@@ -155,12 +675,36 @@ fn would_self_symlink(src: &Path, dest: &Path) -> bool {
 /// Remove the symlink an install would have created at `dest`, if present.
 fn remove_link(ops: &dyn FileOps, dest: &Path, progress: &FileProgress<'_>) -> Result<()> {
     if dest.symlink_metadata().is_ok() {
-        progress.note_apply(|| format!("unlink {}", crate::engine::context::display_path(dest)));
+        progress.note_apply(|| format!("unlink {}", display_path(dest)));
         ops.remove_symlink(dest)?;
     }
     Ok(())
 }
 
+/// [`remove_link`], then put back what a `backup` install moved aside.
+fn unlink_and_restore(
+    ops: &dyn FileOps,
+    dest: &Path,
+    restorer: Option<&Restorer>,
+    progress: &FileProgress<'_>,
+) -> Result<()> {
+    remove_link(ops, dest, progress)?;
+    if let Some(stored) = restorer
+        .map(|r| r.restore(ops, dest))
+        .transpose()?
+        .flatten()
+    {
+        progress.note_apply(|| {
+            format!(
+                "restore {} from {}",
+                display_path(dest),
+                display_path(&stored)
+            )
+        });
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -186,6 +730,13 @@ mod tests {
             default_shell: crate::config::types::Shell::Bash,
             task_name: "t".to_string(),
             depth: 0,
+            conflicts: None,
+            force: false,
+            env: Default::default(),
+            vars: Default::default(),
+            accept_remote_changes: false,
+            remote_dir: PathBuf::new(),
+            executed: Default::default(),
         };
         (ctx, rx)
     }
@@ -200,7 +751,16 @@ mod tests {
         let progress = FileProgress::new(&ctx, "symlink");
 
         let ops = RecordingFs::default();
-        symlink_one(&ops, &src, &dest, false, &progress).unwrap();
+        symlink_one(
+            &ops,
+            &src,
+            &dest,
+            &ConflictPolicy::Skip,
+            false,
+            None,
+            &progress,
+        )
+        .unwrap();
         assert_eq!(
             ops.calls(),
             vec![format!(
@@ -222,7 +782,16 @@ mod tests {
         let progress = FileProgress::new(&ctx, "symlink");
 
         let ops = RecordingFs::default();
-        symlink_one(&ops, &src, &dest, false, &progress).unwrap();
+        symlink_one(
+            &ops,
+            &src,
+            &dest,
+            &ConflictPolicy::Skip,
+            false,
+            None,
+            &progress,
+        )
+        .unwrap();
         // Skipped: nothing touched.
         assert!(ops.calls().is_empty());
     }
@@ -238,7 +807,16 @@ mod tests {
         let progress = FileProgress::new(&ctx, "symlink");
 
         let ops = RecordingFs::default();
-        symlink_one(&ops, &src, &dest, true, &progress).unwrap();
+        symlink_one(
+            &ops,
+            &src,
+            &dest,
+            &ConflictPolicy::Replace { backup: false },
+            false,
+            None,
+            &progress,
+        )
+        .unwrap();
         assert_eq!(
             ops.calls(),
             vec![
@@ -248,6 +826,135 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_symlink_one_force_with_backup_moves_then_creates() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("s");
+        std::fs::write(&src, b"x").unwrap();
+        let dest = dir.path().join("existing");
+        std::fs::write(&dest, b"old").unwrap();
+        let (ctx, _rx) = ctx_for(dir.path());
+        let progress = FileProgress::new(&ctx, "symlink");
+        let backups = BackupSession::new(dir.path(), "t", "k");
+
+        let ops = RecordingFs::default();
+        symlink_one(
+            &ops,
+            &src,
+            &dest,
+            &ConflictPolicy::Replace { backup: true },
+            false,
+            Some(&backups),
+            &progress,
+        )
+        .unwrap();
+        let calls = ops.calls();
+        assert_eq!(calls.len(), 2);
+        assert!(calls[0].starts_with(&format!("move_path {} ", dest.display())));
+        assert_eq!(
+            calls[1],
+            format!("create_symlink {} {}", src.display(), dest.display())
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_one_does_not_back_up_its_own_link() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("s");
+        std::fs::write(&src, b"x").unwrap();
+        let dest = dir.path().join("link");
+        std::os::unix::fs::symlink(&src, &dest).unwrap();
+        let (ctx, _rx) = ctx_for(dir.path());
+        let progress = FileProgress::new(&ctx, "symlink");
+        let backups = BackupSession::new(dir.path(), "t", "k");
+
+        symlink_one(
+            &crate::engine::commands::fs_ops::DirectFs,
+            &src,
+            &dest,
+            &ConflictPolicy::Replace { backup: true },
+            false,
+            Some(&backups),
+            &progress,
+        )
+        .unwrap();
+        assert_eq!(std::fs::read_link(&dest).unwrap(), src);
+        assert!(!dir.path().join("backups").exists());
+    }
+
+    struct Answers(crate::engine::conflict::Resolution);
+
+    impl crate::engine::conflict::ConflictPrompt for Answers {
+        fn ask(&self, _: &crate::engine::conflict::Conflict) -> crate::engine::conflict::Answer {
+            crate::engine::conflict::Answer {
+                resolution: self.0,
+                apply_to_all: false,
+            }
+        }
+    }
+
+    fn asking(ctx: &mut CommandContext, resolution: Resolution) -> ConflictPolicy {
+        ctx.conflicts = Some(crate::engine::conflict::ConflictResolver::new(Answers(
+            resolution,
+        )));
+        ConflictPolicy::new(false, false, ctx, "symlink")
+    }
+
+    #[test]
+    fn test_symlink_one_asks_and_backs_up_existing_file() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("s");
+        std::fs::write(&src, b"x").unwrap();
+        let dest = dir.path().join("existing");
+        std::fs::write(&dest, b"old").unwrap();
+        let (mut ctx, _rx) = ctx_for(dir.path());
+        let policy = asking(&mut ctx, Resolution::Backup);
+        let progress = FileProgress::new(&ctx, "symlink");
+        let backups = BackupSession::new(dir.path(), "t", "k");
+
+        let ops = RecordingFs::default();
+        symlink_one(&ops, &src, &dest, &policy, false, Some(&backups), &progress).unwrap();
+        let calls = ops.calls();
+        assert!(calls[0].starts_with(&format!("move_path {} ", dest.display())));
+        assert_eq!(calls.len(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_one_adopt_moves_existing_file_into_source() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("s");
+        std::fs::write(&src, b"repo").unwrap();
+        let dest = dir.path().join("existing");
+        std::fs::write(&dest, b"machine").unwrap();
+        let (mut ctx, _rx) = ctx_for(dir.path());
+        let policy = asking(&mut ctx, Resolution::Adopt);
+        let progress = FileProgress::new(&ctx, "symlink");
+
+        let ops = crate::engine::commands::fs_ops::DirectFs;
+        symlink_one(&ops, &src, &dest, &policy, false, None, &progress).unwrap();
+        assert_eq!(std::fs::read(&src).unwrap(), b"machine");
+        assert_eq!(std::fs::read_link(&dest).unwrap(), src);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_one_never_asks_about_its_own_link() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("s");
+        std::fs::write(&src, b"x").unwrap();
+        let dest = dir.path().join("link");
+        std::os::unix::fs::symlink(&src, &dest).unwrap();
+        let (mut ctx, _rx) = ctx_for(dir.path());
+        let policy = asking(&mut ctx, Resolution::Overwrite);
+        let progress = FileProgress::new(&ctx, "symlink");
+
+        let ops = RecordingFs::default();
+        symlink_one(&ops, &src, &dest, &policy, false, None, &progress).unwrap();
+        assert!(ops.calls().is_empty());
+    }
+
     #[test]
     fn test_would_self_symlink_path_equality() {
         let p = Path::new("/tmp/x");
@@ -263,6 +970,62 @@ mod tests {
         assert!(!would_self_symlink(&src, &dest));
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_would_self_symlink_is_false_for_a_relative_link_to_src() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("s");
+        std::fs::write(&src, b"x").unwrap();
+        let dest = dir.path().join("link");
+        std::os::unix::fs::symlink("s", &dest).unwrap();
+        assert!(!would_self_symlink(&src, &dest));
+        assert!(would_self_symlink(&src, &src));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_one_relative_stores_path_from_link_dir() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("repo/s");
+        std::fs::create_dir_all(dir.path().join("repo")).unwrap();
+        std::fs::create_dir_all(dir.path().join("home/.config")).unwrap();
+        std::fs::write(&src, b"x").unwrap();
+        let dest = dir.path().join("home/.config/s");
+        let (ctx, _rx) = ctx_for(dir.path());
+        let progress = FileProgress::new(&ctx, "symlink");
+
+        let ops = crate::engine::commands::fs_ops::DirectFs;
+        symlink_one(
+            &ops,
+            &src,
+            &dest,
+            &ConflictPolicy::Skip,
+            true,
+            None,
+            &progress,
+        )
+        .unwrap();
+        assert_eq!(
+            std::fs::read_link(&dest).unwrap(),
+            PathBuf::from("../../repo/s")
+        );
+
+        // A forced re-run recognises the relative link as its own.
+        let backups = BackupSession::new(dir.path(), "t", "k");
+        symlink_one(
+            &ops,
+            &src,
+            &dest,
+            &ConflictPolicy::Replace { backup: true },
+            true,
+            Some(&backups),
+            &progress,
+        )
+        .unwrap();
+        assert!(!dir.path().join("backups").exists());
+        assert_eq!(std::fs::read(&dest).unwrap(), b"x");
+    }
+
     #[test]
     fn test_remove_link_noop_when_absent() {
         let dir = tempdir().unwrap();
@@ -274,4 +1037,171 @@ mod tests {
         remove_link(&ops, &dest, &progress).unwrap();
         assert!(ops.calls().is_empty());
     }
+
+    #[test]
+    fn test_dir_mode_applies_to_tree_dirs() {
+        let dir = tempdir().unwrap();
+        let existing = dir.path().join("existing");
+        std::fs::create_dir_all(&existing).unwrap();
+        let kind = SymlinkKind {
+            args: SymlinkArgs::default(),
+            dir_attrs: FileAttrs {
+                mode: Some(0o700),
+                ..Default::default()
+            },
+            src: PathBuf::new(),
+            tree_source: true,
+            conflicts: ConflictPolicy::Skip,
+            backups: None,
+            restorer: None,
+            manifest: None,
+        };
+        let (ctx, _rx) = ctx_for(dir.path());
+        let ops = RecordingFs::default();
+
+        kind.ensure_dir(&ops, &existing, &ctx).unwrap();
+
+        assert_eq!(
+            ops.calls(),
+            vec![format!("set_mode {} 700", existing.display())]
+        );
+    }
+
+    #[cfg(unix)]
+    fn fold_kind(ignore: &[&str]) -> SymlinkKind {
+        SymlinkKind {
+            args: SymlinkArgs {
+                fold: true,
+                ignore: ignore.iter().map(|p| p.to_string()).collect(),
+                ..Default::default()
+            },
+            dir_attrs: FileAttrs::default(),
+            src: PathBuf::new(),
+            tree_source: true,
+            conflicts: ConflictPolicy::Skip,
+            backups: None,
+            restorer: None,
+            manifest: None,
+        }
+    }
+
+    #[cfg(unix)]
+    fn fold_install(kind: &SymlinkKind, src: &Path, target: &Path, ctx: &CommandContext) {
+        kind.try_short_circuit_install(src, target, ctx)
+            .expect("fold handles directory sources")
+            .unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_fold_links_highest_missing_directory() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src");
+        std::fs::create_dir_all(src.join("nvim/lua")).unwrap();
+        std::fs::write(src.join("nvim/init.lua"), "").unwrap();
+        std::fs::write(src.join("nvim/lua/a.lua"), "").unwrap();
+        let target = dir.path().join("home");
+        std::fs::create_dir_all(&target).unwrap();
+        let (ctx, _rx) = ctx_for(dir.path());
+
+        fold_install(&fold_kind(&[]), &src, &target, &ctx);
+
+        assert_eq!(
+            std::fs::read_link(target.join("nvim")).unwrap(),
+            src.join("nvim")
+        );
+        // New source files show up without another install.
+        std::fs::write(src.join("nvim/new.lua"), "").unwrap();
+        assert!(target.join("nvim/new.lua").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_fold_prune_removes_link_of_deleted_source_directory() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src");
+        std::fs::create_dir_all(src.join("old")).unwrap();
+        std::fs::create_dir_all(src.join("kept")).unwrap();
+        let target = dir.path().join("home");
+        std::fs::create_dir_all(&target).unwrap();
+        let (ctx, _rx) = ctx_for(dir.path());
+        let mut kind = SymlinkKind {
+            manifest: Some(dir.path().join("manifest.json")),
+            ..fold_kind(&[])
+        };
+        kind.args.prune = true;
+
+        fold_install(&kind, &src, &target, &ctx);
+        assert!(target.join("old").is_symlink());
+
+        std::fs::remove_dir(src.join("old")).unwrap();
+        fold_install(&kind, &src, &target, &ctx);
+        assert!(target.join("old").symlink_metadata().is_err());
+        assert!(target.join("kept").is_symlink());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_fold_unfolds_directory_shared_by_a_second_source() {
+        let dir = tempdir().unwrap();
+        let a = dir.path().join("a");
+        let b = dir.path().join("b");
+        std::fs::create_dir_all(a.join(".config/nvim")).unwrap();
+        std::fs::create_dir_all(b.join(".config/fish")).unwrap();
+        std::fs::write(a.join(".config/nvim/init.lua"), "").unwrap();
+        std::fs::write(b.join(".config/fish/config.fish"), "").unwrap();
+        let home = dir.path().join("home");
+        std::fs::create_dir_all(&home).unwrap();
+        let (mut ctx, _rx) = ctx_for(dir.path());
+        let kind = fold_kind(&[]);
+
+        fold_install(&kind, &a, &home, &ctx);
+        assert_eq!(
+            std::fs::read_link(home.join(".config")).unwrap(),
+            a.join(".config")
+        );
+
+        fold_install(&kind, &b, &home, &ctx);
+        let config = home.join(".config");
+        assert!(!config.symlink_metadata().unwrap().file_type().is_symlink());
+        assert_eq!(
+            std::fs::read_link(config.join("nvim")).unwrap(),
+            a.join(".config/nvim")
+        );
+        assert_eq!(
+            std::fs::read_link(config.join("fish")).unwrap(),
+            b.join(".config/fish")
+        );
+
+        ctx.mode = Mode::Uninstall;
+        kind.try_short_circuit_uninstall(&b, &home, &ctx)
+            .unwrap()
+            .unwrap();
+        assert!(config.join("fish").symlink_metadata().is_err());
+        assert!(config.join("nvim/init.lua").exists());
+        assert!(b.join(".config/fish/config.fish").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_fold_keeps_directories_with_ignored_entries_real() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src");
+        std::fs::create_dir_all(src.join("nvim")).unwrap();
+        std::fs::write(src.join("nvim/init.lua"), "").unwrap();
+        std::fs::write(src.join("nvim/.init.lua.swp"), "").unwrap();
+        let target = dir.path().join("home");
+        std::fs::create_dir_all(&target).unwrap();
+        let (ctx, _rx) = ctx_for(dir.path());
+
+        fold_install(&fold_kind(&["*.swp"]), &src, &target, &ctx);
+
+        let nvim = target.join("nvim");
+        assert!(nvim.symlink_metadata().unwrap().is_dir());
+        assert_eq!(
+            std::fs::read_link(nvim.join("init.lua")).unwrap(),
+            src.join("nvim/init.lua")
+        );
+        assert!(nvim.join(".init.lua.swp").symlink_metadata().is_err());
+    }
 }
@@ -0,0 +1,283 @@
+use async_trait::async_trait;
+use indexmap::IndexMap;
+use std::path::Path;
+
+use crate::config::types::TemplateArgs;
+use crate::engine::context::{display_path, CommandContext};
+use crate::error::{Error, Result};
+use crate::utils::template;
+
+use super::fs_ops::{FileAttrs, FileOps};
+use super::progress_log::FileProgress;
+use super::tree_op::{self, TreeOpKind};
+use super::CommandExecutor;
+
+pub struct TemplateCommand {
+    args: TemplateArgs,
+}
+
+impl TemplateCommand {
+    pub fn new(args: TemplateArgs) -> Self {
+        Self { args }
+    }
+}
+
+#[async_trait]
+impl CommandExecutor for TemplateCommand {
+    async fn execute(&self, ctx: &CommandContext) -> Result<()> {
+        let attrs = FileAttrs::parse(
+            "template",
+            "mode",
+            self.args.mode.as_deref(),
+            self.args.owner.as_deref(),
+            self.args.group.as_deref(),
+        )?;
+        let mut vars = template::facts();
+        vars.extend(ctx.vars.clone());
+        vars.extend(self.args.vars.clone());
+        tree_op::execute(
+            &self.args.src,
+            &self.args.target,
+            TemplateKind {
+                sudo: self.args.sudo,
+                vars,
+                attrs,
+            },
+            ctx,
+        )
+        .await
+    }
+
+    fn description(&self) -> String {
+        self.args.to_string()
+    }
+}
+
+/// Renders each source file (a single template or a tree of them) with the
+/// same destination rules as `copy`.
+struct TemplateKind {
+    sudo: bool,
+    vars: IndexMap<String, String>,
+    attrs: FileAttrs,
+}
+
+impl TreeOpKind for TemplateKind {
+    fn ignore(&self) -> &[String] {
+        &[]
+    }
+
+    fn sudo(&self) -> bool {
+        self.sudo
+    }
+
+    fn progress_install(&self) -> &'static str {
+        "template"
+    }
+
+    fn progress_uninstall(&self) -> &'static str {
+        "template remove"
+    }
+
+    fn install_pool<'a>(&self, _ctx: &'a CommandContext) -> Option<&'a rayon::ThreadPool> {
+        None
+    }
+
+    fn uninstall_pool<'a>(&self, _ctx: &'a CommandContext) -> Option<&'a rayon::ThreadPool> {
+        None
+    }
+
+    fn ensure_dir(&self, ops: &dyn FileOps, dir: &Path, _ctx: &CommandContext) -> Result<()> {
+        ops.mkdir_p(dir)
+    }
+
+    fn on_install_file(
+        &self,
+        ops: &dyn FileOps,
+        src: &Path,
+        dest: &Path,
+        progress: &FileProgress<'_>,
+    ) -> Result<()> {
+        render_one(ops, src, dest, &self.vars, &self.attrs, progress)
+    }
+
+    fn on_uninstall_file(
+        &self,
+        ops: &dyn FileOps,
+        dest: &Path,
+        progress: &FileProgress<'_>,
+    ) -> Result<()> {
+        if dest.exists() {
+            progress.note_apply(|| format!("remove {}", display_path(dest)));
+            ops.remove_file(dest)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Render `src` into `dest`, writing only when the rendered content differs.
+fn render_one(
+    ops: &dyn FileOps,
+    src: &Path,
+    dest: &Path,
+    vars: &IndexMap<String, String>,
+    attrs: &FileAttrs,
+    progress: &FileProgress<'_>,
+) -> Result<()> {
+    let source = std::fs::read_to_string(src)?;
+    let rendered = template::render(&source, vars)
+        .map_err(|e| Error::Other(format!("{}: {e}", display_path(src))))?;
+
+    let unchanged = std::fs::read(dest).is_ok_and(|current| current == rendered.as_bytes());
+    if unchanged {
+        progress.note_skip(|| format!("skip {} (unchanged)", display_path(dest)));
+    } else {
+        progress.note_apply(|| format!("render {}", display_path(dest)));
+        ops.write_file(dest, rendered.as_bytes())?;
+    }
+
+    attrs.apply(ops, dest, !unchanged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::commands::fs_ops::RecordingFs;
+    use crate::engine::mode::Mode;
+    use tempfile::tempdir;
+
+    fn ctx_for(
+        dir: &Path,
+    ) -> (
+        CommandContext,
+        tokio::sync::mpsc::UnboundedReceiver<crate::engine::event::TaskEvent>,
+    ) {
+        let (events, rx) = crate::engine::sink::ChannelSink::channel();
+        let ctx = CommandContext {
+            events,
+            gate: std::sync::Arc::new(
+                crate::engine::concurrency::ConcurrencyGate::from_num_threads(Some(1)),
+            ),
+            mode: Mode::Install,
+            config_dir: dir.to_path_buf(),
+            temp_dir: dir.to_path_buf(),
+            default_shell: crate::config::types::Shell::Bash,
+            task_name: "t".to_string(),
+            depth: 0,
+            conflicts: None,
+            force: false,
+            env: Default::default(),
+            vars: Default::default(),
+            accept_remote_changes: false,
+            remote_dir: dir.to_path_buf(),
+            executed: Default::default(),
+        };
+        (ctx, rx)
+    }
+
+    fn vars(pairs: &[(&str, &str)]) -> IndexMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_render_one_writes_when_dest_missing() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("tmpl");
+        std::fs::write(&src, "email = {{ email }}\n").unwrap();
+        let dest = dir.path().join("out");
+        let (ctx, _rx) = ctx_for(dir.path());
+        let progress = FileProgress::new(&ctx, "template");
+        let ops = RecordingFs::default();
+
+        render_one(
+            &ops,
+            &src,
+            &dest,
+            &vars(&[("email", "me@example.com")]),
+            &FileAttrs::default(),
+            &progress,
+        )
+        .unwrap();
+
+        let expected = "email = me@example.com\n".len();
+        assert_eq!(
+            ops.calls(),
+            vec![format!("write_file {} {expected}", dest.display())]
+        );
+    }
+
+    #[test]
+    fn test_render_one_skips_unchanged_content() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("tmpl");
+        std::fs::write(&src, "host = {{ host }}\n").unwrap();
+        let dest = dir.path().join("out");
+        std::fs::write(&dest, "host = box\n").unwrap();
+        let (ctx, _rx) = ctx_for(dir.path());
+        let progress = FileProgress::new(&ctx, "template");
+        let ops = RecordingFs::default();
+
+        render_one(
+            &ops,
+            &src,
+            &dest,
+            &vars(&[("host", "box")]),
+            &FileAttrs::default(),
+            &progress,
+        )
+        .unwrap();
+
+        assert!(ops.calls().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_render_one_fixes_mode_on_unchanged_content() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("tmpl");
+        std::fs::write(&src, "same\n").unwrap();
+        let dest = dir.path().join("out");
+        std::fs::write(&dest, "same\n").unwrap();
+        std::fs::set_permissions(&dest, std::fs::Permissions::from_mode(0o644)).unwrap();
+        let (ctx, _rx) = ctx_for(dir.path());
+        let progress = FileProgress::new(&ctx, "template");
+        let ops = RecordingFs::default();
+
+        let attrs = FileAttrs {
+            mode: Some(0o600),
+            ..Default::default()
+        };
+        render_one(&ops, &src, &dest, &vars(&[]), &attrs, &progress).unwrap();
+
+        assert_eq!(
+            ops.calls(),
+            vec![format!("set_mode {} 600", dest.display())]
+        );
+    }
+
+    #[test]
+    fn test_render_one_reports_unknown_variable_with_path() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("tmpl");
+        std::fs::write(&src, "{{ nope }}").unwrap();
+        let (ctx, _rx) = ctx_for(dir.path());
+        let progress = FileProgress::new(&ctx, "template");
+        let ops = RecordingFs::default();
+
+        let err = render_one(
+            &ops,
+            &src,
+            &dir.path().join("out"),
+            &vars(&[]),
+            &FileAttrs::default(),
+            &progress,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("nope"));
+        assert!(ops.calls().is_empty());
+    }
+}
@@ -5,14 +5,16 @@
 //! policy (bulk sudo, `force`, pool choice, per-file apply) stays behind
 //! [`TreeOpKind`] (CONTEXT.md **Tree-op driver**, ADR-0002).
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 
-use crate::engine::context::CommandContext;
+use crate::engine::context::{display_path, CommandContext};
 use crate::engine::mode::Mode;
 use crate::error::{Error, Result};
 use crate::utils::path::expand_path;
 
 use super::fs_ops::{self, FileOps};
+use super::manifest::{FileDigest, Manifest};
 use super::progress_log::FileProgress;
 use super::tree;
 
@@ -30,7 +32,8 @@ pub trait TreeOpKind: Send + Sync {
     fn uninstall_pool<'a>(&self, ctx: &'a CommandContext) -> Option<&'a rayon::ThreadPool>;
 
     /// Optional short-circuit for install (e.g. bulk `sudo cp -a`). When
-    /// `Some`, the normal Tree materialization walk is skipped.
+    /// `Some`, the normal Tree materialization walk is skipped, and recording
+    /// the Entry manifest ([`record_install`]) is up to the short-circuit.
     fn try_short_circuit_install(
         &self,
         src: &Path,
@@ -41,6 +44,20 @@ pub trait TreeOpKind: Send + Sync {
         None
     }
 
+    /// Optional short-circuit for uninstall, the counterpart of
+    /// [`TreeOpKind::try_short_circuit_install`]. Only used when no Entry
+    /// manifest is recorded; [`TreeOpKind::after_uninstall`] still runs
+    /// afterwards.
+    fn try_short_circuit_uninstall(
+        &self,
+        src: &Path,
+        target: &Path,
+        ctx: &CommandContext,
+    ) -> Option<Result<()>> {
+        let _ = (src, target, ctx);
+        None
+    }
+
     fn ensure_dir(&self, ops: &dyn FileOps, dir: &Path, ctx: &CommandContext) -> Result<()>;
 
     fn on_install_file(
@@ -57,6 +74,43 @@ pub trait TreeOpKind: Send + Sync {
         dest: &Path,
         progress: &FileProgress<'_>,
     ) -> Result<()>;
+
+    /// Runs after the uninstall File ops flush, once every removal is applied.
+    fn after_uninstall(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Entry manifest of what this entry placed. When set, install records
+    /// the managed destinations (with content digests of regular files) and
+    /// the directories it created, and uninstall removes exactly those
+    /// instead of walking `src`.
+    fn manifest(&self) -> Option<&Path> {
+        None
+    }
+
+    /// With a manifest: on install, remove recorded destinations the source
+    /// no longer maps. Without it they stay recorded until uninstall.
+    fn prune(&self) -> bool {
+        false
+    }
+
+    /// Whether `dest` holds what this entry placed from `src`. Checked after
+    /// the install flush; only managed destinations are recorded.
+    fn is_managed(&self, src: &Path, dest: &Path) -> bool {
+        let _ = (src, dest);
+        false
+    }
+
+    /// Remove one recorded destination (pruned, or on uninstall from the
+    /// manifest). Defaults to the uninstall per-file op.
+    fn on_remove_recorded(
+        &self,
+        ops: &dyn FileOps,
+        dest: &Path,
+        progress: &FileProgress<'_>,
+    ) -> Result<()> {
+        self.on_uninstall_file(ops, dest, progress)
+    }
 }
 
 /// Run a tree-shaped Command entry: Mode dispatch on a blocking thread.
@@ -98,25 +152,173 @@ fn install(src: &Path, target: &Path, kind: &dyn TreeOpKind, ctx: &CommandContex
     let ops = fs_ops::select(kind.sudo());
     let progress = FileProgress::new(ctx, kind.progress_install());
     let pool = kind.install_pool(ctx);
+    let record = kind.manifest().is_some();
+    let mut created = Vec::new();
+    let mapped = Mutex::new(Vec::new());
     tree::install_tree_with_pool(
         src,
         target,
         kind.ignore(),
         pool,
-        |dir| kind.ensure_dir(ops.as_ref(), dir, ctx),
-        |file, dest| kind.on_install_file(ops.as_ref(), file, dest, &progress),
+        |dir| {
+            if record && dir.symlink_metadata().is_err() {
+                created.push(dir.to_path_buf());
+            }
+            kind.ensure_dir(ops.as_ref(), dir, ctx)
+        },
+        |file, dest| {
+            kind.on_install_file(ops.as_ref(), file, dest, &progress)?;
+            if record {
+                mapped
+                    .lock()
+                    .expect("mapped lock")
+                    .push((file.to_path_buf(), dest.to_path_buf()));
+            }
+            Ok(())
+        },
     )?;
     progress.finish();
-    ops.flush()
+    ops.flush()?;
+    record_install(
+        kind,
+        mapped.into_inner().expect("mapped lock"),
+        created,
+        ctx,
+    )
 }
 
-fn uninstall(src: &Path, target: &Path, kind: &dyn TreeOpKind, ctx: &CommandContext) -> Result<()> {
+/// Update the Entry manifest after an install flush. `mapped` holds the
+/// `(src, dest)` pairs this run placed and `created` the directories it
+/// created. Destinations recorded earlier but no longer mapped (source
+/// deleted or now ignored) are removed with `prune` and kept on record
+/// otherwise, so uninstall still finds them. Paths never recorded are never
+/// touched.
+pub fn record_install(
+    kind: &dyn TreeOpKind,
+    mapped: Vec<(PathBuf, PathBuf)>,
+    created: Vec<PathBuf>,
+    ctx: &CommandContext,
+) -> Result<()> {
+    let Some(manifest_path) = kind.manifest() else {
+        return Ok(());
+    };
+    let previous = Manifest::load(manifest_path)?.unwrap_or_default();
+    let mut dests: Vec<&Path> = mapped.iter().map(|(_, dest)| dest.as_path()).collect();
+    dests.sort();
+    let stale: Vec<&PathBuf> = previous
+        .files
+        .iter()
+        .filter(|f| dests.binary_search(&f.as_path()).is_err() && f.symlink_metadata().is_ok())
+        .collect();
+
+    let mut files: Vec<PathBuf> = mapped
+        .into_iter()
+        .filter(|(src, dest)| {
+            // Recorded before and unchanged since: no need to compare again.
+            previous.digests.get(dest).is_some_and(|d| d.matches(dest))
+                || kind.is_managed(src, dest)
+        })
+        .map(|(_, dest)| dest)
+        .collect();
+    if kind.prune() {
+        let ops = fs_ops::select(kind.sudo());
+        let progress = FileProgress::new(ctx, "prune");
+        for file in stale {
+            remove_unmodified(kind, ops.as_ref(), file, &previous, &progress)?;
+        }
+        progress.finish();
+        ops.flush()?;
+    } else {
+        files.extend(stale.into_iter().cloned());
+    }
+    files.sort();
+    files.dedup();
+
+    let digests = files
+        .iter()
+        .filter_map(|file| {
+            FileDigest::of(file, previous.digests.get(file)).map(|d| (file.clone(), d))
+        })
+        .collect();
+    let mut dirs: Vec<PathBuf> = previous.dirs.into_iter().chain(created).collect();
+    dirs.retain(|dir| dir.is_dir());
+    dirs.sort();
+    dirs.dedup();
+    Manifest {
+        files,
+        dirs,
+        digests,
+    }
+    .save(manifest_path)
+}
+
+/// Remove recorded `file` unless it changed since its digest in `manifest`
+/// was taken: an edited destination is the user's now.
+fn remove_unmodified(
+    kind: &dyn TreeOpKind,
+    ops: &dyn FileOps,
+    file: &Path,
+    manifest: &Manifest,
+    progress: &FileProgress<'_>,
+) -> Result<()> {
+    if manifest
+        .digests
+        .get(file)
+        .is_some_and(|digest| !digest.matches(file))
+    {
+        progress.note_skip(|| format!("skip {} (modified since install)", display_path(file)));
+        return Ok(());
+    }
+    kind.on_remove_recorded(ops, file, progress)
+}
+
+/// Uninstall from the Entry manifest: remove exactly the recorded paths,
+/// skipping files modified since install, then the recorded directories that
+/// are left empty.
+fn remove_recorded(
+    kind: &dyn TreeOpKind,
+    manifest_path: &Path,
+    manifest: &Manifest,
+    ctx: &CommandContext,
+) -> Result<()> {
     let ops = fs_ops::select(kind.sudo());
     let progress = FileProgress::new(ctx, kind.progress_uninstall());
-    let pool = kind.uninstall_pool(ctx);
-    tree::uninstall_tree_with_pool(src, target, kind.ignore(), pool, |dest| {
-        kind.on_uninstall_file(ops.as_ref(), dest, &progress)
-    })?;
+    for file in &manifest.files {
+        if file.symlink_metadata().is_err() {
+            continue;
+        }
+        remove_unmodified(kind, ops.as_ref(), file, manifest, &progress)?;
+    }
+    for dir in manifest.dirs_deepest_first() {
+        ops.remove_empty_dir(dir)?;
+    }
     progress.finish();
-    ops.flush()
+    ops.flush()?;
+    Manifest::delete(manifest_path)
+}
+
+fn uninstall(src: &Path, target: &Path, kind: &dyn TreeOpKind, ctx: &CommandContext) -> Result<()> {
+    let recorded = match kind.manifest() {
+        Some(path) => Manifest::load(path)?.map(|manifest| (path, manifest)),
+        None => None,
+    };
+    if let Some((path, manifest)) = recorded {
+        remove_recorded(kind, path, &manifest, ctx)?;
+        return kind.after_uninstall();
+    }
+
+    match kind.try_short_circuit_uninstall(src, target, ctx) {
+        Some(early) => early?,
+        None => {
+            let ops = fs_ops::select(kind.sudo());
+            let progress = FileProgress::new(ctx, kind.progress_uninstall());
+            let pool = kind.uninstall_pool(ctx);
+            tree::uninstall_tree_with_pool(src, target, kind.ignore(), pool, |dest| {
+                kind.on_uninstall_file(ops.as_ref(), dest, &progress)
+            })?;
+            progress.finish();
+            ops.flush()?;
+        }
+    }
+    kind.after_uninstall()
 }
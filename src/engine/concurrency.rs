@@ -4,11 +4,12 @@
 //! Also owns the shared Rayon pool used for in-tree file apply (ADR-0004);
 //! the pool is created lazily on first [`ConcurrencyGate::pool`] call.
 
+use std::collections::HashMap;
 use std::fmt;
-use std::sync::{Arc, OnceLock};
+use std::sync::{Arc, Mutex, OnceLock};
 
 use rayon::ThreadPool;
-use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::sync::{oneshot, OwnedSemaphorePermit, Semaphore};
 
 /// Package-manager family for an **Exclusive lane** (ADR-0010).
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -48,6 +49,19 @@ impl fmt::Display for ExclusiveLane {
     }
 }
 
+/// Work queued on an Exclusive lane so whichever Command entry next holds the
+/// lane can run every queued request in one invocation (`package` batching).
+pub struct LaneBatchItem {
+    /// Opaque batch key (e.g. the Mode verb); only equal keys are merged.
+    pub key: String,
+    pub items: Vec<String>,
+    pub task_name: String,
+    pub reply: oneshot::Sender<Result<(), String>>,
+}
+
+/// Outcome of a queued [`LaneBatchItem`], delivered by the lane holder that ran it.
+pub type LaneBatchReply = oneshot::Receiver<Result<(), String>>;
+
 fn lane_semaphores() -> [Arc<Semaphore>; ExclusiveLane::COUNT] {
     std::array::from_fn(|_| Arc::new(Semaphore::new(1)))
 }
@@ -62,6 +76,8 @@ pub struct ConcurrencyGate {
     pool: Arc<OnceLock<ThreadPool>>,
     /// One permit per Exclusive lane family (intra-run serialization).
     lanes: [Arc<Semaphore>; ExclusiveLane::COUNT],
+    /// Pending batchable work per lane, drained by the next lane holder.
+    batches: Arc<Mutex<HashMap<ExclusiveLane, Vec<LaneBatchItem>>>>,
 }
 
 impl ConcurrencyGate {
@@ -75,6 +91,7 @@ impl ConcurrencyGate {
             limit,
             pool: Arc::new(OnceLock::new()),
             lanes: lane_semaphores(),
+            batches: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
@@ -107,6 +124,43 @@ impl ConcurrencyGate {
             .await
             .expect("Exclusive lane semaphore is never closed")
     }
+
+    /// Queue batchable work on `lane`; the returned receiver resolves once a
+    /// lane holder has run it (possibly merged with other queued work).
+    pub fn enqueue_lane_batch(
+        &self,
+        lane: ExclusiveLane,
+        key: String,
+        items: Vec<String>,
+        task_name: String,
+    ) -> LaneBatchReply {
+        let (reply, rx) = oneshot::channel();
+        self.batches
+            .lock()
+            .expect("lane batch lock")
+            .entry(lane)
+            .or_default()
+            .push(LaneBatchItem {
+                key,
+                items,
+                task_name,
+                reply,
+            });
+        rx
+    }
+
+    /// Take all queued work for `lane` with the given key. Call while holding the lane.
+    pub fn drain_lane_batch(&self, lane: ExclusiveLane, key: &str) -> Vec<LaneBatchItem> {
+        let mut batches = self.batches.lock().expect("lane batch lock");
+        let Some(queued) = batches.get_mut(&lane) else {
+            return Vec::new();
+        };
+        let (taken, kept) = std::mem::take(queued)
+            .into_iter()
+            .partition(|item| item.key == key);
+        *queued = kept;
+        taken
+    }
 }
 
 fn build_fs_pool(limit: usize) -> ThreadPool {
@@ -181,6 +235,37 @@ mod tests {
         let _ = handle.await.unwrap();
     }
 
+    #[tokio::test]
+    async fn lane_batch_drains_only_matching_key() {
+        let gate = ConcurrencyGate::from_num_threads(Some(2));
+        let rx_a = gate.enqueue_lane_batch(
+            ExclusiveLane::Apt,
+            "install".into(),
+            vec!["git".into()],
+            "a".into(),
+        );
+        let _rx_b = gate.enqueue_lane_batch(
+            ExclusiveLane::Apt,
+            "uninstall".into(),
+            vec!["vim".into()],
+            "b".into(),
+        );
+        let drained = gate.drain_lane_batch(ExclusiveLane::Apt, "install");
+        assert_eq!(drained.len(), 1);
+        assert_eq!(drained[0].items, vec!["git".to_string()]);
+        for item in drained {
+            item.reply.send(Ok(())).unwrap();
+        }
+        assert_eq!(rx_a.await.unwrap(), Ok(()));
+        assert_eq!(
+            gate.drain_lane_batch(ExclusiveLane::Apt, "uninstall").len(),
+            1
+        );
+        assert!(gate
+            .drain_lane_batch(ExclusiveLane::Brew, "install")
+            .is_empty());
+    }
+
     #[tokio::test]
     async fn brew_lane_does_not_block_apt() {
         let gate = ConcurrencyGate::from_num_threads(Some(2));
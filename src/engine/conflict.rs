@@ -0,0 +1,349 @@
+//! Conflict resolution — what to do with a destination that already exists.
+//!
+//! When `symlink` or `copy` (without `force`) meets a destination it did not
+//! place, an interactive run asks instead of skipping: overwrite, back up
+//! then overwrite, skip, or adopt (copy the existing file into the source
+//! tree, then link it or leave the now identical copy). [`ConflictResolver`] serializes questions from parallel Tasks,
+//! remembers an "apply to all" answer for the rest of the run, and leaves the
+//! asking to a [`ConflictPrompt`] adapter — dialoguer in plain mode, or
+//! [`ChannelPrompt`] for the TUI, which answers through a channel.
+//! Non-interactive runs have no resolver and keep skipping.
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex};
+
+use super::context::CommandContext;
+use super::event::TaskEvent;
+use super::sink::SharedSink;
+
+/// Diffs longer than this are cut, with a note of how much was left out.
+const DIFF_MAX_LINES: usize = 200;
+
+/// Answer to one conflict.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// Replace the existing destination.
+    Overwrite,
+    /// Move the existing destination into a backup, then replace it.
+    Backup,
+    /// Leave the destination alone.
+    Skip,
+    /// Move the existing destination's content into the source tree, then
+    /// place the entry as usual.
+    Adopt,
+}
+
+impl Resolution {
+    pub const ALL: [Resolution; 4] = [
+        Resolution::Overwrite,
+        Resolution::Backup,
+        Resolution::Skip,
+        Resolution::Adopt,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Resolution::Overwrite => "overwrite",
+            Resolution::Backup => "backup",
+            Resolution::Skip => "skip",
+            Resolution::Adopt => "adopt",
+        }
+    }
+}
+
+/// One existing destination a Command entry wants to replace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub task_name: String,
+    /// Command kind asking (`symlink`, `copy`).
+    pub kind: &'static str,
+    pub src: PathBuf,
+    pub dest: PathBuf,
+    /// Unified diff from the destination to the source, when both are text.
+    pub diff: Option<String>,
+}
+
+impl Conflict {
+    pub fn new(task_name: &str, kind: &'static str, src: &Path, dest: &Path) -> Self {
+        Self {
+            task_name: task_name.to_string(),
+            kind,
+            src: src.to_path_buf(),
+            dest: dest.to_path_buf(),
+            diff: diff(dest, src),
+        }
+    }
+}
+
+/// What a prompt returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Answer {
+    pub resolution: Resolution,
+    /// Use `resolution` for every later conflict of this run.
+    pub apply_to_all: bool,
+}
+
+/// UI adapter that asks about one conflict. Called on a blocking thread, one
+/// conflict at a time.
+pub trait ConflictPrompt: Send + Sync {
+    fn ask(&self, conflict: &Conflict) -> Answer;
+}
+
+/// Run-wide conflict policy shared by every Task of an interactive run.
+pub struct ConflictResolver {
+    prompt: Box<dyn ConflictPrompt>,
+    /// An "apply to all" answer; `None` until one is given.
+    remembered: Mutex<Option<Resolution>>,
+}
+
+impl ConflictResolver {
+    pub fn new(prompt: impl ConflictPrompt + 'static) -> Arc<Self> {
+        Arc::new(Self {
+            prompt: Box::new(prompt),
+            remembered: Mutex::new(None),
+        })
+    }
+
+    /// Ask (or reuse the "apply to all" answer) about `dest`. Blocks until
+    /// answered; holding the lock keeps parallel Tasks from asking at once.
+    pub fn resolve(
+        &self,
+        task_name: &str,
+        kind: &'static str,
+        src: &Path,
+        dest: &Path,
+    ) -> Resolution {
+        let mut remembered = self.remembered.lock().expect("conflict lock");
+        if let Some(resolution) = *remembered {
+            return resolution;
+        }
+        let answer = self.prompt.ask(&Conflict::new(task_name, kind, src, dest));
+        if answer.apply_to_all {
+            *remembered = Some(answer.resolution);
+        }
+        answer.resolution
+    }
+}
+
+/// A [`ConflictResolver`] bound to one Command entry's Task.
+#[derive(Clone)]
+pub struct Conflicts {
+    resolver: Arc<ConflictResolver>,
+    task_name: String,
+    kind: &'static str,
+}
+
+impl Conflicts {
+    /// `None` for non-interactive runs.
+    pub fn for_entry(ctx: &CommandContext, kind: &'static str) -> Option<Self> {
+        ctx.conflicts.as_ref().map(|resolver| Self {
+            resolver: Arc::clone(resolver),
+            task_name: ctx.task_name.clone(),
+            kind,
+        })
+    }
+
+    pub fn resolve(&self, src: &Path, dest: &Path) -> Resolution {
+        self.resolver.resolve(&self.task_name, self.kind, src, dest)
+    }
+}
+
+/// How a Command entry treats an existing destination it did not place.
+#[derive(Clone)]
+pub enum ConflictPolicy {
+    /// Leave it (no `force`, non-interactive run).
+    Skip,
+    /// `force`: replace it, moving it into a backup first with `backup`.
+    Replace { backup: bool },
+    /// Interactive run without `force`: ask.
+    Ask(Conflicts),
+}
+
+impl ConflictPolicy {
+    pub fn new(force: bool, backup: bool, ctx: &CommandContext, kind: &'static str) -> Self {
+        if force {
+            return ConflictPolicy::Replace { backup };
+        }
+        match Conflicts::for_entry(ctx, kind) {
+            Some(conflicts) => ConflictPolicy::Ask(conflicts),
+            None => ConflictPolicy::Skip,
+        }
+    }
+
+    /// Whether a replaced destination may need a backup session.
+    pub fn may_back_up(&self) -> bool {
+        matches!(
+            self,
+            ConflictPolicy::Replace { backup: true } | ConflictPolicy::Ask(_)
+        )
+    }
+
+    pub fn resolve(&self, src: &Path, dest: &Path) -> Resolution {
+        match self {
+            ConflictPolicy::Skip => Resolution::Skip,
+            ConflictPolicy::Replace { backup: true } => Resolution::Backup,
+            ConflictPolicy::Replace { backup: false } => Resolution::Overwrite,
+            ConflictPolicy::Ask(conflicts) => conflicts.resolve(src, dest),
+        }
+    }
+}
+
+/// Adopt: copy the existing file at `dest` over `src`, so the source tree
+/// holds what was on the machine. `false` when `dest` is not a regular file.
+pub fn adopt_into_source(src: &Path, dest: &Path) -> std::io::Result<bool> {
+    if !dest.is_file() {
+        return Ok(false);
+    }
+    std::fs::copy(dest, src)?;
+    Ok(true)
+}
+
+/// TUI adapter: emits [`TaskEvent::ConflictPending`] and waits for the UI to
+/// send an [`Answer`] back. A closed channel (UI gone) answers skip.
+pub struct ChannelPrompt {
+    events: SharedSink,
+    answers: Mutex<Receiver<Answer>>,
+}
+
+impl ChannelPrompt {
+    pub fn new(events: SharedSink, answers: Receiver<Answer>) -> Self {
+        Self {
+            events,
+            answers: Mutex::new(answers),
+        }
+    }
+}
+
+impl ConflictPrompt for ChannelPrompt {
+    fn ask(&self, conflict: &Conflict) -> Answer {
+        self.events.emit(TaskEvent::ConflictPending {
+            conflict: conflict.clone(),
+        });
+        self.answers
+            .lock()
+            .expect("conflict answers lock")
+            .recv()
+            .unwrap_or(Answer {
+                resolution: Resolution::Skip,
+                apply_to_all: true,
+            })
+    }
+}
+
+/// Unified diff turning `from` into `to`, or `None` unless both are UTF-8
+/// files. Long diffs are cut at [`DIFF_MAX_LINES`].
+pub fn diff(from: &Path, to: &Path) -> Option<String> {
+    let old = std::fs::read_to_string(from).ok()?;
+    let new = std::fs::read_to_string(to).ok()?;
+    Some(diff_text(
+        &old,
+        &new,
+        &from.display().to_string(),
+        &to.display().to_string(),
+    ))
+}
+
+/// Unified diff of two texts under the given headers, cut at
+/// [`DIFF_MAX_LINES`].
+pub fn diff_text(old: &str, new: &str, from: &str, to: &str) -> String {
+    let text = similar::TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(3)
+        .header(from, to)
+        .to_string();
+    let total = text.lines().count();
+    if total <= DIFF_MAX_LINES {
+        return text;
+    }
+    let mut cut: String = text
+        .lines()
+        .take(DIFF_MAX_LINES)
+        .flat_map(|line| [line, "\n"])
+        .collect();
+    cut.push_str(&format!("… {} more lines\n", total - DIFF_MAX_LINES));
+    cut
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Scripted {
+        answer: Answer,
+        asked: Arc<AtomicUsize>,
+    }
+
+    impl ConflictPrompt for Scripted {
+        fn ask(&self, _conflict: &Conflict) -> Answer {
+            self.asked.fetch_add(1, Ordering::SeqCst);
+            self.answer
+        }
+    }
+
+    fn resolver(
+        resolution: Resolution,
+        apply_to_all: bool,
+    ) -> (Arc<ConflictResolver>, Arc<AtomicUsize>) {
+        let asked = Arc::new(AtomicUsize::new(0));
+        let resolver = ConflictResolver::new(Scripted {
+            answer: Answer {
+                resolution,
+                apply_to_all,
+            },
+            asked: Arc::clone(&asked),
+        });
+        (resolver, asked)
+    }
+
+    #[test]
+    fn asks_every_time_without_apply_to_all() {
+        let (resolver, asked) = resolver(Resolution::Backup, false);
+        for _ in 0..2 {
+            assert_eq!(
+                resolver.resolve("t", "copy", Path::new("/s"), Path::new("/d")),
+                Resolution::Backup
+            );
+        }
+        assert_eq!(asked.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn apply_to_all_answers_later_conflicts() {
+        let (resolver, asked) = resolver(Resolution::Skip, true);
+        resolver.resolve("t", "copy", Path::new("/s"), Path::new("/d"));
+        assert_eq!(
+            resolver.resolve("u", "symlink", Path::new("/s2"), Path::new("/d2")),
+            Resolution::Skip
+        );
+        assert_eq!(asked.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn channel_prompt_emits_event_and_skips_when_ui_is_gone() {
+        let (events, mut rx) = crate::engine::sink::ChannelSink::channel();
+        let (tx, answers) = std::sync::mpsc::channel();
+        drop(tx);
+        let prompt = ChannelPrompt::new(events, answers);
+        let conflict = Conflict::new("t", "copy", Path::new("/s"), Path::new("/d"));
+        assert_eq!(prompt.ask(&conflict).resolution, Resolution::Skip);
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(TaskEvent::ConflictPending { conflict: c }) if c.dest == Path::new("/d")
+        ));
+    }
+
+    #[test]
+    fn diff_shows_changed_lines_of_text_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("old");
+        let new = dir.path().join("new");
+        std::fs::write(&old, "a\nb\n").unwrap();
+        std::fs::write(&new, "a\nc\n").unwrap();
+        let text = diff(&old, &new).unwrap();
+        assert!(text.contains("-b\n"));
+        assert!(text.contains("+c\n"));
+        assert_eq!(diff(dir.path(), &new), None);
+    }
+}
@@ -1,5 +1,7 @@
+use indexmap::IndexMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use crate::config::types::Shell;
 use crate::engine::event::TaskEvent;
@@ -7,8 +9,12 @@ use crate::engine::output::{sanitize_subprocess_line, OutputKind};
 use crate::engine::sink::{SharedSink, TaskEventSink};
 
 use super::concurrency::ConcurrencyGate;
+use super::conflict::ConflictResolver;
 use super::mode::Mode;
 
+/// `(document, Task)` pairs a run has started.
+pub type Executed = Arc<Mutex<HashSet<(String, String)>>>;
+
 /// Context passed to each command during execution.
 #[derive(Clone)]
 pub struct CommandContext {
@@ -35,6 +41,34 @@ pub struct CommandContext {
 
     /// Nesting depth (0 = top-level, 1 = sub-config, etc.)
     pub depth: usize,
+
+    /// Asks about existing destinations; `None` for non-interactive runs.
+    pub conflicts: Option<Arc<ConflictResolver>>,
+
+    /// The run's `--force` (History bypass), for nested Sub-configs.
+    pub force: bool,
+
+    /// Environment variables a parent `machine_setup` entry passed down for
+    /// `run` commands.
+    pub env: HashMap<String, String>,
+
+    /// Variables a parent `machine_setup` entry passed down for `template`
+    /// entries.
+    pub vars: IndexMap<String, String>,
+
+    /// The run's `--yes`: URL Sub-configs changed since their last run may
+    /// run.
+    pub accept_remote_changes: bool,
+
+    /// Where URL and git Sub-configs are cached and verified: the root
+    /// Config's directory (see [`crate::config::remote::default_dir`]), not
+    /// `temp_dir`.
+    pub remote_dir: PathBuf,
+
+    /// Tasks this run has started, as `(document, Task)` (see
+    /// [`crate::config::history::History::scope_of`]), shared with nested
+    /// Sub-configs so a Task reached two ways runs once.
+    pub executed: Executed,
 }
 
 impl CommandContext {
@@ -71,6 +71,12 @@ pub enum TaskEvent {
         error: String,
     },
 
+    /// A Command entry waits for an answer about an existing destination
+    /// (interactive runs only; see [`crate::engine::conflict`]).
+    ConflictPending {
+        conflict: crate::engine::conflict::Conflict,
+    },
+
     /// All tasks are done.
     AllDone {
         succeeded: usize,
@@ -29,6 +29,9 @@ impl Mode {
             | Command::Init
             | Command::Wizard
             | Command::Add { .. }
+            | Command::Adopt { .. }
+            | Command::Import { .. }
+            | Command::Backups
             | Command::Schedule { .. }
             | Command::Schema
             | Command::Completions { .. } => None,
@@ -74,6 +77,7 @@ mod tests {
             }),
             None
         );
+        assert_eq!(Mode::from_command(&Command::Backups), None);
         assert_eq!(Mode::from_command(&Command::Schema), None);
         assert_eq!(
             Mode::from_command(&Command::Completions {
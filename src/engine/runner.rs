@@ -1,15 +1,19 @@
+use indexmap::IndexMap;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use crate::config::graph::TaskGraph;
 use crate::config::history::History;
+use crate::config::remote;
 use crate::config::types::{AppConfig, CommandEntry, TaskConfig};
 use crate::error::{Error, Result};
 use crate::utils::path::expand_path;
 
 use super::commands::{create_executor, exclusive_lane, CommandExecutor};
 use super::concurrency::ConcurrencyGate;
-use super::context::CommandContext;
+use super::conflict::ConflictResolver;
+use super::context::{CommandContext, Executed};
 use super::event::TaskEvent;
 use super::mode::Mode;
 use super::sink::{SharedSink, TaskEventSink};
@@ -21,6 +25,12 @@ pub struct TaskRunner {
     gate: Arc<ConcurrencyGate>,
     config_dir: PathBuf,
     depth: usize,
+    conflicts: Option<Arc<ConflictResolver>>,
+    env: HashMap<String, String>,
+    vars: IndexMap<String, String>,
+    accept_remote_changes: bool,
+    remote_dir: PathBuf,
+    executed: Executed,
 }
 
 /// Running counts of task outcomes across all layers of a run.
@@ -41,6 +51,12 @@ impl TaskRunner {
             gate,
             config_dir: std::env::current_dir().unwrap_or_default(),
             depth: 0,
+            conflicts: None,
+            env: HashMap::new(),
+            vars: IndexMap::new(),
+            accept_remote_changes: false,
+            remote_dir: crate::config::remote::default_dir(),
+            executed: Executed::default(),
         }
     }
 
@@ -60,6 +76,44 @@ impl TaskRunner {
         self
     }
 
+    /// Ask about existing destinations instead of skipping them (interactive
+    /// runs; see [`crate::engine::conflict`]).
+    pub fn with_conflicts(mut self, conflicts: Option<Arc<ConflictResolver>>) -> Self {
+        self.conflicts = conflicts;
+        self
+    }
+
+    /// Environment variables for every `run` command (Sub-config `env`).
+    pub fn with_env(mut self, env: HashMap<String, String>) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// Variables for every `template` entry (Sub-config `vars`).
+    pub fn with_vars(mut self, vars: IndexMap<String, String>) -> Self {
+        self.vars = vars;
+        self
+    }
+
+    /// Let URL Sub-configs that changed since their last run run (`--yes`).
+    pub fn with_accept_remote_changes(mut self, accept: bool) -> Self {
+        self.accept_remote_changes = accept;
+        self
+    }
+
+    /// Cache and trusted-key directory for URL and git Sub-configs (the
+    /// parent's, for nested ones).
+    pub fn with_remote_dir(mut self, dir: PathBuf) -> Self {
+        self.remote_dir = dir;
+        self
+    }
+
+    /// Share the Tasks a parent run has started (nested Sub-configs).
+    pub fn with_executed(mut self, executed: Executed) -> Self {
+        self.executed = executed;
+        self
+    }
+
     /// Run all tasks (respecting parallel config).
     pub async fn run_all(&self, force: bool) -> Result<()> {
         let task_names: Vec<String> = self.config.tasks.keys().cloned().collect();
@@ -83,7 +137,7 @@ impl TaskRunner {
         let ordered: &[String] = &ordered;
 
         let temp_dir = expand_path(&self.config.temp_dir, None);
-        let mut history = History::load(&temp_dir).unwrap_or_default();
+        let mut history = History::load_for(&temp_dir, &self.config)?;
 
         // Both execution modes are the same loop over layers; sequential is the
         // degenerate case where each task is its own layer (so the join below
@@ -110,6 +164,14 @@ impl TaskRunner {
         if let Err(e) = history.save(&temp_dir) {
             tracing::warn!("Failed to save history: {e}");
         }
+        // A URL document counts as run only when its run succeeded.
+        if tally.failed == 0 {
+            for document in &self.config.remote_documents {
+                if let Err(e) = remote::record_run(&self.remote_dir, document) {
+                    tracing::warn!("Failed to record the run of {}: {e}", document.url);
+                }
+            }
+        }
 
         self.send(TaskEvent::AllDone {
             succeeded: tally.succeeded,
@@ -154,7 +216,23 @@ impl TaskRunner {
                 continue;
             }
 
-            let ctx = self.create_context(name, temp_dir);
+            // A Task linked from another document and run by that document's
+            // Sub-config too: whichever comes first runs it.
+            let claimed = self
+                .executed
+                .lock()
+                .expect("executed lock")
+                .insert(history.scope_of(name));
+            if !claimed {
+                self.send(TaskEvent::TaskSkipped {
+                    task_name: name.clone(),
+                    reason: "Already ran in this run".to_string(),
+                });
+                tally.skipped += 1;
+                continue;
+            }
+
+            let ctx = self.create_context(name, temp_dir, force);
             let task = task_config.clone();
             let name = name.clone();
             handles.push(tokio::spawn(async move {
@@ -197,8 +275,9 @@ impl TaskRunner {
         }
 
         // Check only_if conditions
+        let config_dir = self.config.task_config_dir(name, &self.config_dir);
         for path_str in task.only_if.as_slice() {
-            let path = expand_path(path_str, Some(&self.config_dir));
+            let path = expand_path(path_str, Some(config_dir));
             if !path.exists() {
                 return Some(format!("Condition not met: '{path_str}' does not exist"));
             }
@@ -206,7 +285,7 @@ impl TaskRunner {
 
         // Check skip_if conditions
         for path_str in task.skip_if.as_slice() {
-            let path = expand_path(path_str, Some(&self.config_dir));
+            let path = expand_path(path_str, Some(config_dir));
             if path.exists() {
                 return Some(format!("Skipped: '{path_str}' exists"));
             }
@@ -232,16 +311,26 @@ impl TaskRunner {
         &self.config.tasks
     }
 
-    fn create_context(&self, task_name: &str, temp_dir: &Path) -> CommandContext {
+    fn create_context(&self, task_name: &str, temp_dir: &Path, force: bool) -> CommandContext {
         CommandContext {
             events: Arc::clone(&self.events),
             gate: Arc::clone(&self.gate),
             mode: self.mode,
-            config_dir: self.config_dir.clone(),
+            config_dir: self
+                .config
+                .task_config_dir(task_name, &self.config_dir)
+                .to_path_buf(),
             temp_dir: temp_dir.to_path_buf(),
-            default_shell: self.config.default_shell.clone(),
+            default_shell: self.config.task_default_shell(task_name).clone(),
             task_name: task_name.to_string(),
             depth: self.depth,
+            conflicts: self.conflicts.clone(),
+            force,
+            env: self.env.clone(),
+            vars: self.vars.clone(),
+            accept_remote_changes: self.accept_remote_changes,
+            remote_dir: self.remote_dir.clone(),
+            executed: Arc::clone(&self.executed),
         }
     }
 
@@ -50,9 +50,21 @@ pub enum Error {
     #[error("Cyclic dependency detected: {0}")]
     CyclicDependency(String),
 
+    #[error("Task '{0}' is defined in both {1} and {2}")]
+    TaskConflict(String, String, String),
+
+    #[error("Cyclic extends/include: {0}")]
+    CyclicInclude(String),
+
     #[error("Unknown dependency: task '{0}' depends on '{1}' which does not exist")]
     MissingDependency(String, String),
 
+    #[error("Config verification failed: {0}")]
+    Verification(String),
+
+    #[error("{0} is held by {1}; wait for it to finish or re-run with --wait")]
+    RunLocked(String, String),
+
     #[error("{0}")]
     Other(String),
 }
@@ -6,8 +6,10 @@ use std::path::{Path, PathBuf};
 use tokio_util::sync::CancellationToken;
 
 use cli::{AddTarget, Cli, Command, RecipeCommand, ScheduleAction};
+use engine::conflict::{ChannelPrompt, ConflictResolver};
 use engine::mode::Mode;
 use engine::runner::TaskRunner;
+use machine_setup::utils::lock::RunLock;
 
 /// Tracks temp_dir + config flag for the post-command self update-check.
 struct UpdateNoticeCtx {
@@ -66,7 +68,7 @@ fn main() -> anyhow::Result<()> {
         action: ScheduleAction::Notify { ref temp_dir },
     } = &cli.command
     {
-        let dir = resolve_notify_temp_dir(temp_dir.as_deref(), cli.config.as_deref())?;
+        let dir = resolve_notify_temp_dir(temp_dir.as_deref(), single_config(&cli)?)?;
         if let Some(msg) = machine_setup::schedule::notices::notify(&dir)? {
             println!("{msg}");
         }
@@ -77,7 +79,7 @@ fn main() -> anyhow::Result<()> {
 
     // Authoring verbs mutate the Config document
     if cli.command == Command::Init {
-        let path = config::document::resolve_init_path(cli.config.as_deref(), &cwd);
+        let path = config::document::resolve_init_path(single_config(&cli)?, &cwd);
         config::document::init(&path)?;
         println!("Created {}", path.display());
         if config::document::validate_after_write(&path)? {
@@ -89,13 +91,13 @@ fn main() -> anyhow::Result<()> {
     }
 
     if cli.command == Command::Wizard {
-        config::wizard::run(cli.config.as_deref(), &cwd)?;
+        config::wizard::run(single_config(&cli)?, &cwd)?;
         notice.emit(&cli.command);
         return Ok(());
     }
 
     if let Command::Add { target } = &cli.command {
-        let path = resolve_existing_document(cli.config.as_deref(), &cwd)?;
+        let path = resolve_existing_document(single_config(&cli)?, &cwd)?;
         match target {
             AddTarget::Task { name } => {
                 config::document::add_task(&path, name)?;
@@ -116,27 +118,134 @@ fn main() -> anyhow::Result<()> {
         return Ok(());
     }
 
-    // Load config (supports local paths, URLs, and locator when `-c` omitted)
-    let config_source = config::resolve_config_source(cli.config.as_deref(), &cwd)?;
-    let app_config = config::load_config(&config_source)?;
+    if let Command::Adopt {
+        file,
+        into,
+        target,
+        name,
+    } = &cli.command
+    {
+        let path = resolve_existing_document(single_config(&cli)?, &cwd)?;
+        let request = config::adopt::AdoptRequest {
+            file,
+            into,
+            target,
+            name,
+        };
+        let adopted = config::adopt::adopt(&path, &request, &cwd)?;
+        println!(
+            "Moved {} to {}",
+            adopted.link.display(),
+            adopted.stored.display()
+        );
+        if adopted.appended {
+            println!("Added task `{}` to {}", adopted.task, path.display());
+        }
+        println!(
+            "Linked it back. Commit {} to keep it.",
+            adopted.stored.display()
+        );
+        if adopted.appended && config::document::validate_after_write(&path)? {
+            notice.emit(&cli.command);
+            std::process::exit(1);
+        }
+        notice.emit(&cli.command);
+        return Ok(());
+    }
+
+    if let Command::Import {
+        repo,
+        git_dirs,
+        packages,
+    } = &cli.command
+    {
+        let path = match single_config(&cli)? {
+            Some(raw) if config::is_remote(raw) => {
+                anyhow::bail!("`import` requires a local Config document path, not a URL")
+            }
+            Some(raw) => config::document::resolve_init_path(Some(raw), &cwd),
+            None => {
+                config::locator::find(&cwd).unwrap_or_else(|_| config::locator::init_path(&cwd))
+            }
+        };
+        let path = cwd.join(path);
+        let config_dir = path.parent().unwrap_or(&cwd).to_path_buf();
+        let request = config::import::ImportRequest {
+            repo: repo.as_deref(),
+            git_dirs,
+            packages: *packages,
+        };
+        let tasks = config::import::scan(&request, &config_dir, &cwd)?;
+        if tasks.is_empty() {
+            println!("Nothing to import.");
+            notice.emit(&cli.command);
+            return Ok(());
+        }
+        if !path.exists() {
+            config::document::init(&path)?;
+            println!("Created {}", path.display());
+        }
+        config::import::append(&path, &tasks)?;
+        for task in &tasks {
+            println!("Added task `{}` to {}", task.name, path.display());
+        }
+        if config::document::validate_after_write(&path)? {
+            notice.emit(&cli.command);
+            std::process::exit(1);
+        }
+        notice.emit(&cli.command);
+        return Ok(());
+    }
+
+    // Load config (supports local paths, URLs, git sources, and locator when
+    // `-c` omitted); several `-c` run together as one workspace.
+    let config_sources = match cli.config.as_slice() {
+        [] | [_] => {
+            let source = config::resolve_config_source(single_config(&cli)?, &cwd)?;
+            vec![config::verify::pin(source, cli.config_sha256.as_deref())?]
+        }
+        several => {
+            if cli.config_sha256.is_some() {
+                anyhow::bail!(
+                    "--config-sha256 applies to a single -c; pin each URL with `#sha256=<hex>`"
+                );
+            }
+            single_config(&cli)?;
+            several
+                .iter()
+                .map(|raw| config::resolve_config_source(Some(raw), &cwd))
+                .collect::<Result<Vec<_>, _>>()?
+        }
+    };
+    let config_source = config_sources[0].clone();
+    let app_config = load_root_config(&cli, &config_sources, &cwd)?;
     notice = UpdateNoticeCtx::from_config(&app_config);
 
     // Handle list command
     if cli.command == Command::List {
         use machine_setup::tui::catalog::{adapt, plain, run_browse};
 
-        let history = config::history::History::load(&notice.temp_dir).unwrap_or_default();
+        let history = config::history::History::load_for(&notice.temp_dir, &app_config)?;
         let items = adapt::list_items(&app_config, &history);
+        let others = config::status::other_documents(&history);
         let use_tui = !cli.no_tui && std::io::stdout().is_terminal();
         if use_tui {
-            run_browse(items, None)?;
+            let banner = adapt::others_banner(&others);
+            run_browse(items, (!banner.is_empty()).then_some(banner))?;
         } else {
             plain::print_list(&items);
+            plain::print_others(&others);
         }
         notice.emit(&cli.command);
         return Ok(());
     }
 
+    if cli.command == Command::Backups {
+        print_backups(&notice.temp_dir)?;
+        notice.emit(&cli.command);
+        return Ok(());
+    }
+
     // Handle validate command
     if cli.command == Command::Validate {
         let config_dir = config::resolve_config_dir(&config_source, &cwd);
@@ -166,7 +275,7 @@ fn main() -> anyhow::Result<()> {
     if let Command::Doctor { fix } = cli.command {
         let config_dir = config::resolve_config_dir(&config_source, &cwd);
         let use_tui = !cli.no_tui && std::io::stdout().is_terminal();
-        run_doctor(&app_config, &config_dir, fix, use_tui)?;
+        run_doctor(&app_config, &config_dir, fix, cli.wait, use_tui)?;
         notice.emit(&cli.command);
         return Ok(());
     }
@@ -224,14 +333,33 @@ fn main() -> anyhow::Result<()> {
     result
 }
 
+/// `machine_setup backups`: kept copies of paths `symlink` or `copy` replaced.
+fn print_backups(temp_dir: &Path) -> anyhow::Result<()> {
+    use machine_setup::engine::commands::backup;
+
+    let backups = backup::list(temp_dir)?;
+    if backups.is_empty() {
+        println!("No backups.");
+        return Ok(());
+    }
+    for entry in backups {
+        let created = entry.record.created.format("%Y-%m-%d %H:%M:%S UTC");
+        println!("{created}  {}", entry.record.task);
+        for file in &entry.record.files {
+            println!("  {} <- {}", file.original.display(), file.stored.display());
+        }
+    }
+    Ok(())
+}
+
 /// Path for add/list-style ops that need an existing local Config document.
 fn resolve_existing_document(
     config_arg: Option<&str>,
     cwd: &Path,
 ) -> anyhow::Result<std::path::PathBuf> {
     if let Some(raw) = config_arg {
-        if config::is_url(raw) {
-            anyhow::bail!("`add` requires a local Config document path, not a URL");
+        if config::is_remote(raw) {
+            anyhow::bail!("`add` and `adopt` require a local Config document path, not a URL");
         }
         let path = config::resolve_config_path(Path::new(raw))?;
         return Ok(path);
@@ -271,6 +399,65 @@ fn emit_recipe(recipe: &RecipeCommand) -> anyhow::Result<config::recipes::Emitte
     })
 }
 
+/// The `-c` document of verbs that work on one document; `None` for several
+/// with verbs that run them together.
+fn single_config(cli: &Cli) -> anyhow::Result<Option<&str>> {
+    let together = Mode::from_command(&cli.command).is_some()
+        || matches!(cli.command, Command::List | Command::Validate);
+    match cli.config.as_slice() {
+        [] => Ok(None),
+        [one] => Ok(Some(one)),
+        _ if together => Ok(None),
+        _ => anyhow::bail!(
+            "this command takes one -c; list several documents under `configs:` in a workspace file"
+        ),
+    }
+}
+
+/// Load the Config documents; a URL document that changed since it last ran
+/// only runs after the user saw the diff and agreed (or with `--yes`).
+fn load_root_config(
+    cli: &Cli,
+    config_sources: &[String],
+    cwd: &Path,
+) -> anyhow::Result<config::types::AppConfig> {
+    use config::remote::{OnChange, Remote};
+
+    let executes = Mode::from_command(&cli.command).is_some()
+        || matches!(
+            cli.command,
+            Command::Schedule {
+                action: ScheduleAction::Run { .. }
+            }
+        );
+    let ask = |url: &str, diff: &str| {
+        eprintln!("{diff}");
+        dialoguer::Confirm::new()
+            .with_prompt(format!("{url} changed since it last ran. Run it?"))
+            .default(false)
+            .interact()
+            .unwrap_or(false)
+    };
+    let on_change = match (executes, cli.yes, std::io::stdin().is_terminal()) {
+        (false, _, _) => OnChange::Read,
+        (true, true, _) => OnChange::Accept,
+        (true, false, true) => OnChange::Ask(&ask),
+        (true, false, false) => OnChange::Refuse,
+    };
+    let temp_dir = config::remote::default_dir();
+    let remote = Remote {
+        temp_dir: &temp_dir,
+        on_change,
+    };
+    Ok(match config_sources {
+        [one] => config::load_config_remote(one, &remote)?,
+        several => {
+            let members = config::workspace::members(several);
+            config::workspace::load(None, &members, &remote, cwd)?
+        }
+    })
+}
+
 async fn run_execution(
     cli: Cli,
     app_config: config::types::AppConfig,
@@ -285,6 +472,10 @@ async fn run_execution(
 
     let use_tui = !cli.no_tui && std::io::stdout().is_terminal();
 
+    // One run per temp_dir: History and Entry manifests live there.
+    let temp_dir = machine_setup::utils::path::expand_path(&app_config.temp_dir, None);
+    let _run_lock = RunLock::acquire_async(temp_dir, cli.wait).await?;
+
     if use_tui && app_config.requires_sudo(&task_names) {
         pre_authenticate_sudo();
     }
@@ -292,7 +483,22 @@ async fn run_execution(
     let mode = Mode::from_command(&cli.command)
         .expect("non-execution verbs are handled before this point");
 
-    let runner = TaskRunner::new(app_config, mode, events).with_config_dir(config_dir);
+    // A terminal on stdin asks about existing destinations instead of
+    // skipping: through the TUI, or dialoguer with `--no-tui`.
+    let (answers, answer_rx) = std::sync::mpsc::channel();
+    let conflicts = match (std::io::stdin().is_terminal(), use_tui) {
+        (false, _) => None,
+        (true, true) => Some(ConflictResolver::new(ChannelPrompt::new(
+            std::sync::Arc::clone(&events),
+            answer_rx,
+        ))),
+        (true, false) => Some(ConflictResolver::new(tui::plain::PlainConflictPrompt)),
+    };
+
+    let runner = TaskRunner::new(app_config, mode, events)
+        .with_config_dir(config_dir)
+        .with_conflicts(conflicts)
+        .with_accept_remote_changes(cli.yes);
     let force = cli.force;
     let task_names_clone = task_names.clone();
 
@@ -307,7 +513,7 @@ async fn run_execution(
             }
         });
 
-        tui::run(event_rx, task_names, mode, cancel).await?;
+        tui::run(event_rx, answers, task_names, mode, cancel).await?;
 
         engine_handle.abort();
         let _ = engine_handle.await;
@@ -488,6 +694,7 @@ fn run_doctor(
     config: &config::types::AppConfig,
     config_dir: &Path,
     fix: bool,
+    wait: bool,
     use_tui: bool,
 ) -> anyhow::Result<()> {
     use config::status::{doctor, prune_orphans};
@@ -495,7 +702,8 @@ fn run_doctor(
     use machine_setup::utils::path::expand_path;
 
     let temp_dir = expand_path(&config.temp_dir, None);
-    let mut history = config::history::History::load(&temp_dir).unwrap_or_default();
+    let _run_lock = fix.then(|| RunLock::acquire(&temp_dir, wait)).transpose()?;
+    let mut history = config::history::History::load_for(&temp_dir, config)?;
     let report = doctor(config, &history, config_dir);
 
     let items = adapt::doctor_items(&report);
@@ -510,6 +718,7 @@ fn run_doctor(
         run_browse(items, Some(banner))?;
     } else {
         plain::print_doctor(&items, &issue_lines, &report.orphans);
+        plain::print_others(&report.others);
     }
 
     let has_errors = report.has_errors();
@@ -568,7 +777,7 @@ fn select_tasks(config: &config::types::AppConfig, use_tui: bool) -> anyhow::Res
         use machine_setup::utils::path::expand_path;
 
         let temp_dir = expand_path(&config.temp_dir, None);
-        let history = config::history::History::load(&temp_dir).unwrap_or_default();
+        let history = config::history::History::load_for(&temp_dir, config)?;
         let items = adapt::select_items(config, &history);
         match run_select(items)? {
             Some(ids) => Ok(ids),
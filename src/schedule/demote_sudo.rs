@@ -37,6 +37,13 @@ fn demote_entry(task_name: &str, entry: &mut CommandEntry, warnings: &mut Vec<St
     match entry {
         CommandEntry::Copy(args) if args.sudo => {
             args.sudo = false;
+            demote_owner(
+                task_name,
+                "copy",
+                &mut args.owner,
+                &mut args.group,
+                warnings,
+            );
             warnings.push(format!(
                 "task `{task_name}`: schedule run demoted copy sudo (running without privileges)"
             ));
@@ -47,6 +54,44 @@ fn demote_entry(task_name: &str, entry: &mut CommandEntry, warnings: &mut Vec<St
                 "task `{task_name}`: schedule run demoted symlink sudo (running without privileges)"
             ));
         }
+        CommandEntry::Template(args) if args.sudo => {
+            args.sudo = false;
+            demote_owner(
+                task_name,
+                "template",
+                &mut args.owner,
+                &mut args.group,
+                warnings,
+            );
+            warnings.push(format!(
+                "task `{task_name}`: schedule run demoted template sudo (running without privileges)"
+            ));
+        }
+        CommandEntry::LineInFile(args) if args.sudo => {
+            args.sudo = false;
+            warnings.push(format!(
+                "task `{task_name}`: schedule run demoted line_in_file sudo (running without privileges)"
+            ));
+        }
+        CommandEntry::BlockInFile(args) if args.sudo => {
+            args.sudo = false;
+            warnings.push(format!(
+                "task `{task_name}`: schedule run demoted block_in_file sudo (running without privileges)"
+            ));
+        }
+        CommandEntry::Archive(args) if args.sudo => {
+            args.sudo = false;
+            warnings.push(format!(
+                "task `{task_name}`: schedule run demoted archive sudo (running without privileges)"
+            ));
+        }
+        CommandEntry::Package(args)
+            if crate::engine::commands::package::detect(args).is_some_and(|m| m.needs_root()) =>
+        {
+            warnings.push(format!(
+                "task `{task_name}`: package manager needs root; scheduled update may fail without passwordless sudo"
+            ));
+        }
         CommandEntry::Run(args) => {
             let before = args.all_command_strings().any(|s| s.contains("sudo"));
             if !before {
@@ -66,6 +111,22 @@ fn demote_entry(task_name: &str, entry: &mut CommandEntry, warnings: &mut Vec<St
     }
 }
 
+/// Ownership needs root; drop it along with `sudo` so the unprivileged run
+/// still places the files.
+fn demote_owner(
+    task_name: &str,
+    kind: &str,
+    owner: &mut Option<String>,
+    group: &mut Option<String>,
+    warnings: &mut Vec<String>,
+) {
+    if owner.take().is_some() | group.take().is_some() {
+        warnings.push(format!(
+            "task `{task_name}`: schedule run dropped {kind} owner/group (needs sudo)"
+        ));
+    }
+}
+
 fn strip_run_fields(args: &mut crate::config::types::RunArgs) {
     strip_string_or_vec(&mut args.commands);
     strip_string_or_vec(&mut args.install);
@@ -158,6 +219,10 @@ mod tests {
             parallel: false,
             num_threads: None,
             check_for_updates: true,
+            origins: Default::default(),
+            id: None,
+            history_scopes: Vec::new(),
+            remote_documents: Vec::new(),
         }
     }
 
@@ -221,6 +286,8 @@ mod tests {
                         target: "/b".into(),
                         ignore: vec![],
                         sudo: true,
+                        owner: Some("root".into()),
+                        ..Default::default()
                     }),
                     CommandEntry::Symlink(SymlinkArgs {
                         src: "/a".into(),
@@ -228,6 +295,7 @@ mod tests {
                         ignore: vec![],
                         force: false,
                         sudo: true,
+                        ..Default::default()
                     }),
                     CommandEntry::Run(RunArgs {
                         commands: StringOrVec::default(),
@@ -258,7 +326,7 @@ mod tests {
         assert!(!warnings.is_empty());
         let task = &demoted.tasks["priv"];
         match &task.commands[0] {
-            CommandEntry::Copy(a) => assert!(!a.sudo),
+            CommandEntry::Copy(a) => assert!(!a.sudo && a.owner.is_none()),
             _ => panic!("expected copy"),
         }
         match &task.commands[1] {
@@ -292,6 +360,7 @@ mod tests {
                     target: "/b".into(),
                     ignore: vec![],
                     sudo: true,
+                    ..Default::default()
                 })],
                 os: Default::default(),
                 parallel: false,
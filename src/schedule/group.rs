@@ -69,6 +69,10 @@ mod tests {
             parallel: false,
             num_threads: None,
             check_for_updates: true,
+            origins: Default::default(),
+            id: None,
+            history_scopes: Vec::new(),
+            remote_documents: Vec::new(),
         };
         let g = group_keys(&config).unwrap();
         assert_eq!(g.len(), 1);
@@ -3,6 +3,7 @@
 use std::path::{Path, PathBuf};
 
 use crate::error::{Error, Result};
+use crate::utils::marked_block;
 
 pub const HOOK_BEGIN: &str = "# >>> machine_setup schedule hook >>>";
 pub const HOOK_END: &str = "# <<< machine_setup schedule hook <<<";
@@ -43,7 +44,7 @@ pub fn install_stub(rc_path: &Path, hook_path: &Path) -> Result<bool> {
     let block = stub_block(hook_path);
     if rc_path.exists() {
         let content = std::fs::read_to_string(rc_path)?;
-        if let Some(updated) = replace_marked_block(&content, &block) {
+        if let Some(updated) = marked_block::replace(&content, HOOK_BEGIN, HOOK_END, &block) {
             std::fs::write(rc_path, updated)?;
             return Ok(true);
         }
@@ -72,7 +73,7 @@ pub fn remove_stub(rc_path: &Path) -> Result<bool> {
         return Ok(false);
     }
     let content = std::fs::read_to_string(rc_path)?;
-    match strip_marked_block(&content) {
+    match marked_block::strip(&content, HOOK_BEGIN, HOOK_END) {
         Some(updated) => {
             std::fs::write(rc_path, updated)?;
             Ok(true)
@@ -81,34 +82,6 @@ pub fn remove_stub(rc_path: &Path) -> Result<bool> {
     }
 }
 
-fn replace_marked_block(content: &str, new_block: &str) -> Option<String> {
-    let start = content.find(HOOK_BEGIN)?;
-    let end_rel = content[start..].find(HOOK_END)?;
-    let end = start + end_rel + HOOK_END.len();
-    let mut end = end;
-    if content[end..].starts_with('\n') {
-        end += 1;
-    }
-    let mut out = String::new();
-    out.push_str(&content[..start]);
-    out.push_str(new_block);
-    out.push_str(&content[end..]);
-    Some(out)
-}
-
-fn strip_marked_block(content: &str) -> Option<String> {
-    let start = content.find(HOOK_BEGIN)?;
-    let end_rel = content[start..].find(HOOK_END)?;
-    let mut end = start + end_rel + HOOK_END.len();
-    if content[end..].starts_with('\n') {
-        end += 1;
-    }
-    let mut out = String::new();
-    out.push_str(&content[..start]);
-    out.push_str(&content[end..]);
-    Some(out)
-}
-
 /// Default rc paths under `home` for zsh and bash.
 pub fn default_rc_paths(home: &Path) -> Vec<PathBuf> {
     vec![home.join(".zshrc"), home.join(".bashrc")]
@@ -12,6 +12,7 @@ use crate::error::{Error, Result};
 use crate::schedule::group;
 use crate::schedule::key::ScheduleKey;
 use crate::schedule::notices::NoticeStore;
+use crate::utils::lock::RunLock;
 
 /// Run update mode for installed tasks matching `key`.
 pub async fn run_key(
@@ -28,7 +29,9 @@ pub async fn run_key(
         )));
     }
 
-    let history = History::load(temp_dir).unwrap_or_default();
+    // A timer firing during an interactive run waits for it.
+    let _run_lock = RunLock::acquire_async(temp_dir.to_path_buf(), true).await?;
+    let history = History::load_for(temp_dir, &config)?;
     let mut to_run: Vec<String> = candidates
         .into_iter()
         .filter(|name| history.is_installed(name))
@@ -69,10 +69,19 @@ pub fn doctor_banner(report: &DoctorReport<'_>, fix: bool) -> Vec<String> {
             lines.push("Hint: re-run with `doctor --fix` to remove orphan History entries.".into());
         }
     }
+    lines.extend(others_banner(&report.others));
 
     lines
 }
 
+/// Banner lines for other Config documents' installed Tasks in History.
+pub fn others_banner(others: &[String]) -> Vec<String> {
+    others
+        .iter()
+        .map(|line| format!("Other document in History — {line}"))
+        .collect()
+}
+
 fn row_to_item(row: &status::TaskStatusRow<'_>, issues: &[&ValidationIssue]) -> CatalogItem {
     let status = if !row.os_applies {
         CatalogStatus::SkippedOs
@@ -201,6 +210,10 @@ mod tests {
             parallel: false,
             num_threads: None,
             check_for_updates: true,
+            origins: Default::default(),
+            id: None,
+            history_scopes: Vec::new(),
+            remote_documents: Vec::new(),
         }
     }
 
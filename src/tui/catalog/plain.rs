@@ -170,6 +170,26 @@ fn style_muted(text: &str, color: bool) -> String {
 }
 
 /// Pretty plain doctor report: task table + validation + orphans.
+/// Prints other Config documents' installed Tasks, if any.
+pub fn print_others(others: &[String]) {
+    print!("{}", render_others(others));
+}
+
+/// Renders the installed Tasks of other Config documents sharing History;
+/// empty when there are none.
+pub fn render_others(others: &[String]) -> String {
+    if others.is_empty() {
+        return String::new();
+    }
+    let mut out = "\nOther Config documents in History:\n".to_string();
+    for line in others {
+        out.push_str("  ");
+        out.push_str(line);
+        out.push('\n');
+    }
+    out
+}
+
 pub fn print_doctor(items: &[CatalogItem], issue_lines: &[String], orphans: &[String]) {
     print!(
         "{}",
@@ -399,6 +419,14 @@ mod tests {
         );
     }
 
+    #[test]
+    fn render_others_lists_documents_only_when_present() {
+        assert_eq!(render_others(&[]), "");
+        let out = render_others(&["/home/me/work.yaml: tools".to_string()]);
+        assert!(out.contains("Other Config documents in History:"));
+        assert!(out.contains("  /home/me/work.yaml: tools\n"));
+    }
+
     #[test]
     fn color_enabled_with_respects_tty_and_no_color() {
         assert!(color_enabled_with(true, false));
@@ -7,6 +7,7 @@ use ratatui::Terminal;
 use tokio::sync::mpsc;
 use tokio_util::sync::CancellationToken;
 
+use crate::engine::conflict::{Answer, Resolution};
 use crate::engine::event::TaskEvent;
 
 use super::message::{Effect, Input, Message};
@@ -20,6 +21,7 @@ pub async fn run_loop(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     mut state: UiState,
     mut event_rx: mpsc::UnboundedReceiver<TaskEvent>,
+    answers: std::sync::mpsc::Sender<Answer>,
     cancel: CancellationToken,
 ) -> anyhow::Result<UiState> {
     let (key_tx, mut key_rx) = mpsc::unbounded_channel::<KeyEvent>();
@@ -88,6 +90,9 @@ pub async fn run_loop(
                             cancel.cancel();
                             break;
                         }
+                        Effect::AnswerConflict(answer) => {
+                            let _ = answers.send(answer);
+                        }
                     }
                 }
             }
@@ -152,6 +157,18 @@ fn map_key(state: &UiState, key: KeyEvent) -> Option<Input> {
         return Some(Input::CancelAndQuit);
     }
 
+    if state.conflict.is_some() {
+        return match key.code {
+            KeyCode::Char('o') => Some(Input::ResolveConflict(Resolution::Overwrite)),
+            KeyCode::Char('b') => Some(Input::ResolveConflict(Resolution::Backup)),
+            KeyCode::Char('s') | KeyCode::Esc => Some(Input::ResolveConflict(Resolution::Skip)),
+            KeyCode::Char('a') => Some(Input::ResolveConflict(Resolution::Adopt)),
+            KeyCode::Char(' ') => Some(Input::ToggleConflictApplyToAll),
+            KeyCode::Char('q') => Some(Input::CancelAndQuit),
+            _ => None,
+        };
+    }
+
     if state.search_mode {
         return match key.code {
             KeyCode::Esc => Some(Input::ExitSearch),
@@ -224,4 +241,28 @@ mod tests {
         release.kind = KeyEventKind::Release;
         assert_eq!(map_key(&state, release), None);
     }
+
+    #[test]
+    fn pending_conflict_keys_answer_it() {
+        let mut state = UiState::new(vec!["a".into()], Mode::Install);
+        state.conflict = Some(crate::tui::state::PendingConflict {
+            conflict: crate::engine::conflict::Conflict {
+                task_name: "a".into(),
+                kind: "symlink",
+                src: "/s".into(),
+                dest: "/d".into(),
+                diff: None,
+            },
+            apply_to_all: false,
+        });
+        assert_eq!(
+            map_key(&state, key(KeyCode::Char('a'))),
+            Some(Input::ResolveConflict(Resolution::Adopt))
+        );
+        assert_eq!(
+            map_key(&state, key(KeyCode::Esc)),
+            Some(Input::ResolveConflict(Resolution::Skip))
+        );
+        assert_eq!(map_key(&state, key(KeyCode::Char('j'))), None);
+    }
 }
@@ -1,3 +1,4 @@
+use crate::engine::conflict::{Answer, Resolution};
 use crate::engine::event::TaskEvent;
 
 /// Side effects requested by [`crate::tui::reduce::reduce`].
@@ -8,6 +9,8 @@ pub enum Effect {
     Quit,
     /// Cancel the engine and leave the UI loop.
     CancelAndQuit,
+    /// Send the answer to the pending conflict back to the engine.
+    AnswerConflict(Answer),
 }
 
 /// Keyboard / UI intents (mapped from crossterm in the event loop).
@@ -29,6 +32,10 @@ pub enum Input {
     LogEnd,
     /// Enter: expand/collapse full log during a parallel burst.
     ToggleDetailsExpand,
+    /// Answer the pending conflict prompt.
+    ResolveConflict(Resolution),
+    /// Space: toggle "apply to all" on the pending conflict prompt.
+    ToggleConflictApplyToAll,
 }
 
 /// All messages the reducer accepts.
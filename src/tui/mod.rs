@@ -33,6 +33,7 @@ pub(crate) fn restore_terminal() {
 /// Run the TUI, consuming events from the engine until all tasks are done.
 pub async fn run(
     event_rx: mpsc::UnboundedReceiver<TaskEvent>,
+    answers: std::sync::mpsc::Sender<crate::engine::conflict::Answer>,
     task_names: Vec<String>,
     mode: crate::engine::mode::Mode,
     cancel: CancellationToken,
@@ -53,7 +54,7 @@ pub async fn run(
 
     let state = UiState::new(task_names, mode);
 
-    let result = event_loop::run_loop(&mut terminal, state, event_rx, cancel).await;
+    let result = event_loop::run_loop(&mut terminal, state, event_rx, answers, cancel).await;
 
     // Always restore terminal
     restore_terminal();
@@ -88,6 +89,10 @@ pub(crate) fn render(f: &mut ratatui::Frame, state: &UiState) {
     widgets::task_list::render(f, main_chunks[0], state);
     widgets::log_view::render(f, main_chunks[1], state);
     widgets::help_bar::render(f, chunks[2], state);
+
+    if let Some(pending) = &state.conflict {
+        widgets::conflict::render(f, f.area(), pending);
+    }
 }
 
 fn print_summary(state: &UiState) {
@@ -1,5 +1,6 @@
 use tokio::sync::mpsc;
 
+use crate::engine::conflict::{Answer, Conflict, ConflictPrompt, Resolution};
 use crate::engine::event::TaskEvent;
 use crate::tui::log_display;
 
@@ -78,6 +79,13 @@ pub async fn run(mut event_rx: mpsc::UnboundedReceiver<TaskEvent>) {
             } => {
                 println!("   [{task_name}]   Retry {attempt}/{max_attempts}: {error}");
             }
+            TaskEvent::ConflictPending { conflict } => {
+                println!(
+                    "   [{}]   waiting for an answer: {} exists",
+                    conflict.task_name,
+                    conflict.dest.display()
+                );
+            }
             TaskEvent::AllDone {
                 succeeded,
                 failed,
@@ -88,3 +96,43 @@ pub async fn run(mut event_rx: mpsc::UnboundedReceiver<TaskEvent>) {
         }
     }
 }
+
+/// Plain-mode conflict prompt: prints the diff, then asks with dialoguer on
+/// the terminal. A failed prompt (no terminal) answers skip.
+pub struct PlainConflictPrompt;
+
+impl ConflictPrompt for PlainConflictPrompt {
+    fn ask(&self, conflict: &Conflict) -> Answer {
+        eprintln!(
+            "\n   [{}] {}: {} already exists (source {})",
+            conflict.task_name,
+            conflict.kind,
+            conflict.dest.display(),
+            conflict.src.display()
+        );
+        if let Some(diff) = &conflict.diff {
+            eprint!("{diff}");
+        }
+        let labels: Vec<&str> = Resolution::ALL.iter().map(|r| r.label()).collect();
+        let Ok(pick) = dialoguer::Select::new()
+            .with_prompt("Replace it?")
+            .items(&labels)
+            .default(2)
+            .interact()
+        else {
+            return Answer {
+                resolution: Resolution::Skip,
+                apply_to_all: true,
+            };
+        };
+        let apply_to_all = dialoguer::Confirm::new()
+            .with_prompt("Apply to all remaining conflicts?")
+            .default(false)
+            .interact()
+            .unwrap_or(false);
+        Answer {
+            resolution: Resolution::ALL[pick],
+            apply_to_all,
+        }
+    }
+}
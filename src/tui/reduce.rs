@@ -1,3 +1,4 @@
+use crate::engine::conflict::Answer;
 use crate::engine::event::TaskEvent;
 use crate::engine::output::OutputKind;
 
@@ -6,7 +7,7 @@ use super::parallel_burst::{
     self, clear_command_progress, find_or_create_task, set_command_progress, BurstContext,
     SoftSelect,
 };
-use super::state::{TaskStatus, UiState};
+use super::state::{PendingConflict, TaskStatus, UiState};
 
 /// Pure state transition. No I/O.
 pub fn reduce(mut state: UiState, msg: Message) -> (UiState, Effect) {
@@ -145,6 +146,22 @@ fn apply_engine(state: &mut UiState, event: TaskEvent) {
             state.ensure_task_color(&name);
             (name.clone(), SoftSelect::Prefer(name), false)
         }
+        TaskEvent::ConflictPending { conflict } => {
+            let name = conflict.task_name.clone();
+            let task = find_or_create_task(state, &name);
+            task.push_log(
+                OutputKind::Info,
+                format!(
+                    "Waiting for an answer: {} exists",
+                    crate::utils::path::shorten_path(&conflict.dest)
+                ),
+            );
+            state.conflict = Some(PendingConflict {
+                conflict,
+                apply_to_all: false,
+            });
+            (name, SoftSelect::None, false)
+        }
         TaskEvent::AllDone { .. } => {
             state.done = true;
             state.freeze_run_elapsed();
@@ -253,6 +270,35 @@ fn apply_input(mut state: UiState, input: Input) -> (UiState, Effect) {
             parallel_burst::stick_log_to_end(&mut state);
             (state, Effect::None)
         }
+        Input::ResolveConflict(resolution) => {
+            let Some(pending) = state.conflict.take() else {
+                return (state, Effect::None);
+            };
+            let all = if pending.apply_to_all {
+                " (all remaining)"
+            } else {
+                ""
+            };
+            find_or_create_task(&mut state, &pending.conflict.task_name).push_log(
+                OutputKind::Info,
+                format!(
+                    "{} {}{all}",
+                    resolution.label(),
+                    crate::utils::path::shorten_path(&pending.conflict.dest)
+                ),
+            );
+            let answer = Answer {
+                resolution,
+                apply_to_all: pending.apply_to_all,
+            };
+            (state, Effect::AnswerConflict(answer))
+        }
+        Input::ToggleConflictApplyToAll => {
+            if let Some(pending) = state.conflict.as_mut() {
+                pending.apply_to_all = !pending.apply_to_all;
+            }
+            (state, Effect::None)
+        }
     }
 }
 
@@ -604,4 +650,40 @@ mod tests {
         assert_eq!(state.selected, 1);
         assert!(state.in_burst_mode());
     }
+
+    #[test]
+    fn conflict_answer_carries_apply_to_all_and_clears_prompt() {
+        let state = state_with(&["a"]);
+        let (state, _) = reduce(
+            state,
+            Message::Engine(TaskEvent::ConflictPending {
+                conflict: crate::engine::conflict::Conflict {
+                    task_name: "a".into(),
+                    kind: "symlink",
+                    src: "/repo/.bashrc".into(),
+                    dest: "/home/u/.bashrc".into(),
+                    diff: None,
+                },
+            }),
+        );
+        assert!(state.conflict.is_some());
+        let (state, _) = reduce(state, Message::Input(Input::ToggleConflictApplyToAll));
+        let (state, effect) = reduce(
+            state,
+            Message::Input(Input::ResolveConflict(
+                crate::engine::conflict::Resolution::Backup,
+            )),
+        );
+        assert_eq!(
+            effect,
+            Effect::AnswerConflict(Answer {
+                resolution: crate::engine::conflict::Resolution::Backup,
+                apply_to_all: true,
+            })
+        );
+        assert!(state.conflict.is_none());
+        let last = state.tasks[0].log_lines.last().expect("log");
+        assert!(last.text.starts_with("backup "));
+        assert!(last.text.ends_with("(all remaining)"));
+    }
 }
@@ -1,5 +1,6 @@
 use std::time::{Duration, Instant};
 
+use crate::engine::conflict::Conflict;
 use crate::engine::mode::Mode;
 use crate::engine::output::OutputKind;
 
@@ -136,6 +137,16 @@ pub struct UiState {
     pub details_expanded: bool,
     /// Next palette slot to hand out.
     pub next_color: usize,
+    /// Existing destination the engine is waiting on an answer for.
+    pub conflict: Option<PendingConflict>,
+}
+
+/// A conflict prompt shown over the run view.
+#[derive(Debug, Clone)]
+pub struct PendingConflict {
+    pub conflict: Conflict,
+    /// Answer every later conflict the same way (`Space` toggles).
+    pub apply_to_all: bool,
 }
 
 impl UiState {
@@ -162,6 +173,7 @@ impl UiState {
             burst_failed: Vec::new(),
             details_expanded: false,
             next_color: 0,
+            conflict: None,
         }
     }
 
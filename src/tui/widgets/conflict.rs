@@ -0,0 +1,86 @@
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
+use ratatui::Frame;
+
+use crate::tui::state::PendingConflict;
+use crate::utils::path::shorten_path;
+
+/// Centered prompt over the run view: what exists, the diff, and the keys.
+pub fn render(f: &mut Frame, area: Rect, pending: &PendingConflict) {
+    let popup = centered(area, 80, 80);
+    let conflict = &pending.conflict;
+
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("exists  ", Style::default().fg(Color::DarkGray)),
+            Span::styled(
+                shorten_path(&conflict.dest),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("source  ", Style::default().fg(Color::DarkGray)),
+            Span::raw(shorten_path(&conflict.src)),
+        ]),
+        Line::default(),
+    ];
+    match &conflict.diff {
+        Some(diff) => lines.extend(diff.lines().map(diff_line)),
+        None => lines.push(Line::styled(
+            "(no text diff: binary file or directory)",
+            Style::default().fg(Color::DarkGray),
+        )),
+    }
+
+    let check = if pending.apply_to_all { "[x]" } else { "[ ]" };
+    let title = format!(
+        " {} [{}]: replace existing? {check} apply to all ",
+        conflict.kind, conflict.task_name
+    );
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow))
+        .title(Span::styled(
+            title,
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        ));
+
+    f.render_widget(Clear, popup);
+    f.render_widget(
+        Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false }),
+        popup,
+    );
+}
+
+fn diff_line(line: &str) -> Line<'static> {
+    let color = if line.starts_with("+++") || line.starts_with("---") {
+        Color::DarkGray
+    } else if line.starts_with('+') {
+        Color::Green
+    } else if line.starts_with('-') {
+        Color::Red
+    } else if line.starts_with("@@") {
+        Color::Cyan
+    } else {
+        Color::Reset
+    };
+    Line::styled(line.to_string(), Style::default().fg(color))
+}
+
+/// `percent_x` × `percent_y` of `area`, centered.
+fn centered(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
+    let width = area.width * percent_x / 100;
+    let height = area.height * percent_y / 100;
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
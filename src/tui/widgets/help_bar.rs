@@ -7,7 +7,15 @@ use ratatui::Frame;
 use crate::tui::state::UiState;
 
 pub fn render(f: &mut Frame, area: Rect, state: &UiState) {
-    let mut keys = if state.search_mode {
+    let mut keys = if state.conflict.is_some() {
+        vec![
+            key_hint("o", "overwrite"),
+            key_hint("b", "backup"),
+            key_hint("s", "skip"),
+            key_hint("a", "adopt"),
+            key_hint("Space", "apply to all"),
+        ]
+    } else if state.search_mode {
         vec![
             key_hint("Esc", "cancel"),
             key_hint("Enter", "apply"),
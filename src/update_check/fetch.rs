@@ -2,10 +2,9 @@
 
 use std::time::Duration;
 
-use serde::Deserialize;
-use ureq::Agent;
-
 use crate::error::{Error, Result};
+use crate::utils::http;
+use serde::Deserialize;
 
 const RELEASES_LATEST: &str =
     "https://api.github.com/repos/timopruesse/machine_setup/releases/latest";
@@ -17,16 +16,12 @@ struct LatestRelease {
 
 /// Fetch latest release tag (e.g. `v2.7.0`). Timeout ~2s.
 pub fn fetch_latest_tag() -> Result<String> {
-    let agent: Agent = Agent::config_builder()
-        .timeout_global(Some(Duration::from_secs(2)))
-        .http_status_as_error(true)
-        .build()
-        .into();
-
-    let body = agent
-        .get(RELEASES_LATEST)
+    let body = http::get(RELEASES_LATEST)?
         .header("User-Agent", "machine_setup-update-check")
         .header("Accept", "application/vnd.github+json")
+        .config()
+        .timeout_global(Some(Duration::from_secs(2)))
+        .build()
         .call()
         .map_err(|e| Error::Other(format!("update check fetch failed: {e}")))?
         .into_body()
@@ -0,0 +1,427 @@
+//! Archive extraction for the `archive` Command entry.
+//!
+//! Extracts into an unprivileged staging directory; placing files into the
+//! real target (and any `sudo`) is the executor's job via File ops. Member
+//! paths are checked here, so an archive can never write outside `dest`:
+//! absolute and `..` members are refused, no member is written through a
+//! symlink (a `lib -> /etc` member followed by `lib/passwd`), and symlink
+//! members must point inside `dest`.
+
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Component, Path, PathBuf};
+
+use crate::error::{Error, Result};
+
+/// Supported archive formats, detected from the file name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    TarGz,
+    TarXz,
+    Zip,
+}
+
+impl Format {
+    /// Detect from a file name or URL (query string and fragment ignored).
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.split(['?', '#']).next().unwrap_or(name);
+        let lower = name.to_ascii_lowercase();
+        if lower.ends_with(".tar.gz") || lower.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if lower.ends_with(".tar.xz") || lower.ends_with(".txz") {
+            Some(Self::TarXz)
+        } else if lower.ends_with(".zip") {
+            Some(Self::Zip)
+        } else {
+            None
+        }
+    }
+
+    /// Canonical extension, used to name downloaded archives.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::TarGz => "tar.gz",
+            Self::TarXz => "tar.xz",
+            Self::Zip => "zip",
+        }
+    }
+}
+
+/// Extract `archive` into `dest`, dropping the first `strip_components`
+/// components of every member path. Members that strip to nothing are skipped.
+pub fn extract(archive: &Path, format: Format, dest: &Path, strip_components: usize) -> Result<()> {
+    std::fs::create_dir_all(dest)?;
+    let file = BufReader::new(File::open(archive)?);
+    match format {
+        Format::TarGz => extract_tar(flate2::read::GzDecoder::new(file), dest, strip_components),
+        Format::TarXz => extract_tar(xz2::read::XzDecoder::new(file), dest, strip_components),
+        Format::Zip => extract_zip(archive, dest, strip_components),
+    }
+}
+
+fn extract_tar(reader: impl Read, dest: &Path, strip_components: usize) -> Result<()> {
+    let mut tar = tar::Archive::new(reader);
+    tar.set_preserve_permissions(true);
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        let member = entry.path()?.into_owned();
+        let Some(relative) = stripped(&member, strip_components)? else {
+            continue;
+        };
+        let out = prepare(dest, &relative)?;
+        let kind = entry.header().entry_type();
+
+        if kind.is_symlink() {
+            let target = entry.link_name()?.ok_or_else(|| {
+                Error::Other(format!("{}: symlink without target", member.display()))
+            })?;
+            check_link(&relative, &target)?;
+        }
+
+        if kind.is_hard_link() {
+            // `unpack` resolves hard-link names against the CWD; resolve them
+            // against the (stripped) staging tree instead.
+            let link = entry
+                .link_name()?
+                .ok_or_else(|| {
+                    Error::Other(format!("{}: hard link without target", member.display()))
+                })?
+                .into_owned();
+            let Some(link) = stripped(&link, strip_components)? else {
+                continue;
+            };
+            std::fs::copy(prepare(dest, &link)?, &out)?;
+            continue;
+        }
+
+        if strip_components == 0 {
+            // The member's own path is `relative`: let tar check it again.
+            entry.unpack_in(dest)?;
+        } else {
+            entry.unpack(&out)?;
+        }
+    }
+    Ok(())
+}
+
+fn extract_zip(archive: &Path, dest: &Path, strip_components: usize) -> Result<()> {
+    let mut zip = zip::ZipArchive::new(File::open(archive)?).map_err(zip_error)?;
+    for i in 0..zip.len() {
+        let mut member = zip.by_index(i).map_err(zip_error)?;
+        let name = member
+            .enclosed_name()
+            .ok_or_else(|| Error::Other(format!("unsafe archive member: {}", member.name())))?;
+        let Some(relative) = stripped(&name, strip_components)? else {
+            continue;
+        };
+        let out = prepare(dest, &relative)?;
+        if member.is_dir() {
+            std::fs::create_dir_all(&out)?;
+            continue;
+        }
+
+        #[cfg(unix)]
+        if member.is_symlink() {
+            let mut link = String::new();
+            member.read_to_string(&mut link)?;
+            check_link(&relative, Path::new(&link))?;
+            std::os::unix::fs::symlink(link, &out)?;
+            continue;
+        }
+
+        let mut file = File::create(&out)?;
+        std::io::copy(&mut member, &mut file)?;
+
+        #[cfg(unix)]
+        if let Some(mode) = member.unix_mode() {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&out, std::fs::Permissions::from_mode(mode & 0o7777))?;
+        }
+    }
+    Ok(())
+}
+
+/// `dest/relative`, with its parent directories created. Refuses a parent, or
+/// an existing `relative` itself, that is a symlink: writing (or, for a hard
+/// link, reading) through it could reach outside `dest`.
+fn prepare(dest: &Path, relative: &Path) -> Result<PathBuf> {
+    let mut path = dest.to_path_buf();
+    for part in relative.iter() {
+        path.push(part);
+        match path.symlink_metadata() {
+            Ok(meta) if meta.file_type().is_symlink() => {
+                return Err(Error::Other(format!(
+                    "unsafe archive member: {} goes through the symlink {}",
+                    relative.display(),
+                    path.strip_prefix(dest).unwrap_or(&path).display()
+                )));
+            }
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                if path.as_path() != dest.join(relative) {
+                    std::fs::create_dir(&path)?;
+                }
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(path)
+}
+
+/// Refuse a symlink member at `relative` whose `target` is absolute or
+/// leaves `dest` once resolved against the member's directory.
+fn check_link(relative: &Path, target: &Path) -> Result<()> {
+    let mut depth = relative.components().count().saturating_sub(1) as isize;
+    for component in target.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir => depth -= 1,
+            Component::RootDir | Component::Prefix(_) => depth = -1,
+        }
+        if depth < 0 {
+            return Err(Error::Other(format!(
+                "unsafe archive member: {} links outside the archive ({})",
+                relative.display(),
+                target.display()
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn zip_error(err: zip::result::ZipError) -> Error {
+    Error::Other(format!("zip: {err}"))
+}
+
+/// Member path with the first `n` components removed. `None` when nothing is
+/// left; an error for absolute paths or `..` components.
+fn stripped(member: &Path, n: usize) -> Result<Option<PathBuf>> {
+    let mut parts = Vec::new();
+    for component in member.components() {
+        match component {
+            Component::Normal(part) => parts.push(part),
+            Component::CurDir => {}
+            _ => {
+                return Err(Error::Other(format!(
+                    "unsafe archive member: {}",
+                    member.display()
+                )))
+            }
+        }
+    }
+    if parts.len() <= n {
+        return Ok(None);
+    }
+    Ok(Some(parts[n..].iter().collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn write_tar_gz(path: &Path, files: &[(&str, &str)]) {
+        let gz = flate2::write::GzEncoder::new(
+            File::create(path).unwrap(),
+            flate2::Compression::default(),
+        );
+        let mut builder = tar::Builder::new(gz);
+        for (name, body) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(body.len() as u64);
+            header.set_mode(0o755);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, name, body.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+    }
+
+    /// A tarball of `(name, Ok(body))` files and `(name, Err(target))` symlinks.
+    fn write_hostile_tar_gz(path: &Path, members: &[(&str, std::result::Result<&str, &str>)]) {
+        let gz = flate2::write::GzEncoder::new(
+            File::create(path).unwrap(),
+            flate2::Compression::default(),
+        );
+        let mut builder = tar::Builder::new(gz);
+        for (name, member) in members {
+            let mut header = tar::Header::new_gnu();
+            header.set_mode(0o644);
+            match member {
+                Ok(body) => {
+                    header.set_size(body.len() as u64);
+                    header.set_cksum();
+                    builder
+                        .append_data(&mut header, name, body.as_bytes())
+                        .unwrap();
+                }
+                Err(target) => {
+                    header.set_entry_type(tar::EntryType::Symlink);
+                    header.set_size(0);
+                    builder.append_link(&mut header, name, target).unwrap();
+                }
+            }
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+    }
+
+    #[test]
+    fn tar_symlink_members_cannot_reach_outside_dest() {
+        let dir = tempdir().unwrap();
+        let outside = dir.path().join("outside");
+        std::fs::create_dir_all(&outside).unwrap();
+        let escape = format!("{}", outside.display());
+        let cases: Vec<Vec<(&str, std::result::Result<&str, &str>)>> = vec![
+            // Absolute target, then a member written through it.
+            vec![("lib", Err(&escape)), ("lib/passwd", Ok("owned"))],
+            // Relative target leaving dest.
+            vec![("a/lib", Err("../../outside"))],
+            // A harmless link is kept, but nothing is written through it.
+            vec![
+                ("data", Ok("x")),
+                ("sub/keep", Ok("y")),
+                ("lib", Err("sub")),
+                ("lib/passwd", Ok("owned")),
+            ],
+        ];
+        for (n, members) in cases.iter().enumerate() {
+            let archive = dir.path().join(format!("hostile{n}.tar.gz"));
+            write_hostile_tar_gz(&archive, members);
+            let dest = dir.path().join(format!("out{n}"));
+            let err = extract(&archive, Format::TarGz, &dest, 0).unwrap_err();
+            assert!(err.to_string().contains("unsafe archive member"), "{err}");
+            assert!(!outside.join("passwd").exists());
+            assert!(!dest.join("sub/passwd").exists());
+        }
+    }
+
+    #[test]
+    fn links_inside_dest_are_extracted() {
+        let dir = tempdir().unwrap();
+        let archive = dir.path().join("sdk.tar.gz");
+        write_hostile_tar_gz(
+            &archive,
+            &[
+                ("sdk/bin/tool-1.0", Ok("#!/bin/sh\n")),
+                ("sdk/bin/tool", Err("tool-1.0")),
+                ("sdk/lib/tool", Err("../bin/tool-1.0")),
+            ],
+        );
+        let dest = dir.path().join("out");
+
+        extract(&archive, Format::TarGz, &dest, 1).unwrap();
+
+        assert_eq!(
+            std::fs::read_link(dest.join("bin/tool")).unwrap(),
+            PathBuf::from("tool-1.0")
+        );
+        assert!(dest.join("lib/tool").is_file());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn zip_symlink_members_cannot_reach_outside_dest() {
+        let dir = tempdir().unwrap();
+        let outside = dir.path().join("outside");
+        std::fs::create_dir_all(&outside).unwrap();
+        for (n, target) in [outside.display().to_string(), "../outside".to_string()]
+            .iter()
+            .enumerate()
+        {
+            let archive = dir.path().join(format!("hostile{n}.zip"));
+            {
+                let mut zip = zip::ZipWriter::new(File::create(&archive).unwrap());
+                let options = zip::write::SimpleFileOptions::default();
+                zip.add_symlink("lib", target, options).unwrap();
+                zip.start_file("lib/passwd", options).unwrap();
+                std::io::Write::write_all(&mut zip, b"owned").unwrap();
+                zip.finish().unwrap();
+            }
+            let dest = dir.path().join(format!("out{n}"));
+
+            let err = extract(&archive, Format::Zip, &dest, 0).unwrap_err();
+
+            assert!(err.to_string().contains("unsafe archive member"), "{err}");
+            assert!(!outside.join("passwd").exists());
+        }
+    }
+
+    #[test]
+    fn format_from_name() {
+        assert_eq!(Format::from_name("sdk.tar.gz"), Some(Format::TarGz));
+        assert_eq!(Format::from_name("sdk.TGZ"), Some(Format::TarGz));
+        assert_eq!(
+            Format::from_name("https://x/sdk.tar.xz?sig=1"),
+            Some(Format::TarXz)
+        );
+        assert_eq!(Format::from_name("sdk.zip"), Some(Format::Zip));
+        assert_eq!(Format::from_name("sdk.tar.bz2"), None);
+    }
+
+    #[test]
+    fn stripped_drops_leading_components() {
+        assert_eq!(
+            stripped(Path::new("sdk-1.0/bin/tool"), 1).unwrap(),
+            Some(PathBuf::from("bin/tool"))
+        );
+        assert_eq!(stripped(Path::new("sdk-1.0/"), 1).unwrap(), None);
+        assert_eq!(
+            stripped(Path::new("./a/b"), 0).unwrap(),
+            Some(PathBuf::from("a/b"))
+        );
+    }
+
+    #[test]
+    fn stripped_rejects_escaping_paths() {
+        assert!(stripped(Path::new("../etc/passwd"), 0).is_err());
+        assert!(stripped(Path::new("/etc/passwd"), 0).is_err());
+    }
+
+    #[test]
+    fn extract_tar_gz_with_strip_components() {
+        let dir = tempdir().unwrap();
+        let archive = dir.path().join("sdk.tar.gz");
+        write_tar_gz(
+            &archive,
+            &[
+                ("sdk-1.0/bin/tool", "#!/bin/sh\n"),
+                ("sdk-1.0/README", "hi"),
+            ],
+        );
+        let dest = dir.path().join("out");
+
+        extract(&archive, Format::TarGz, &dest, 1).unwrap();
+
+        assert_eq!(std::fs::read_to_string(dest.join("README")).unwrap(), "hi");
+        assert!(dest.join("bin/tool").is_file());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(dest.join("bin/tool"))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o111, 0o111);
+        }
+    }
+
+    #[test]
+    fn extract_zip_roundtrip() {
+        let dir = tempdir().unwrap();
+        let archive = dir.path().join("sdk.zip");
+        {
+            let mut zip = zip::ZipWriter::new(File::create(&archive).unwrap());
+            let options = zip::write::SimpleFileOptions::default();
+            zip.start_file("top/a.txt", options).unwrap();
+            std::io::Write::write_all(&mut zip, b"a").unwrap();
+            zip.finish().unwrap();
+        }
+        let dest = dir.path().join("out");
+
+        extract(&archive, Format::Zip, &dest, 1).unwrap();
+
+        assert_eq!(std::fs::read_to_string(dest.join("a.txt")).unwrap(), "a");
+    }
+}
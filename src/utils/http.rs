@@ -0,0 +1,358 @@
+//! The HTTP agent behind every network request: Config documents and their
+//! signatures, archive downloads, the update check.
+//!
+//! The agent honours `HTTPS_PROXY` / `HTTP_PROXY` / `ALL_PROXY` and
+//! `NO_PROXY`, and when `MACHINE_SETUP_CA_BUNDLE` names a PEM file it trusts
+//! the certificates in it instead of the built-in roots (a corporate TLS
+//! proxy). Config fetches also carry [`credentials`], so a private dotfiles
+//! repository works with `-c`.
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ureq::tls::{parse_pem, PemItem, RootCerts, TlsConfig};
+use ureq::typestate::WithoutBody;
+use ureq::{Agent, Proxy, RequestBuilder};
+
+use crate::error::{Error, Result};
+
+/// Bearer token sent to the Config source host named by [`TOKEN_HOST_ENV`].
+pub const TOKEN_ENV: &str = "MACHINE_SETUP_TOKEN";
+/// The one host [`TOKEN_ENV`] is sent to.
+pub const TOKEN_HOST_ENV: &str = "MACHINE_SETUP_TOKEN_HOST";
+/// Bearer token sent to GitHub hosts only.
+pub const GITHUB_TOKEN_ENV: &str = "GITHUB_TOKEN";
+/// PEM bundle of CA certificates to trust instead of the built-in roots.
+pub const CA_BUNDLE_ENV: &str = "MACHINE_SETUP_CA_BUNDLE";
+/// Location of the netrc file (default `~/.netrc`).
+pub const NETRC_ENV: &str = "NETRC";
+
+const GITHUB_HOSTS: &[&str] = &[
+    "github.com",
+    "api.github.com",
+    "raw.githubusercontent.com",
+    "gist.githubusercontent.com",
+];
+
+/// How a Config fetch authenticates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Credentials {
+    Bearer(String),
+    Basic { login: String, password: String },
+}
+
+impl Credentials {
+    fn header(&self) -> String {
+        match self {
+            Credentials::Bearer(token) => format!("Bearer {token}"),
+            Credentials::Basic { login, password } => {
+                format!("Basic {}", STANDARD.encode(format!("{login}:{password}")))
+            }
+        }
+    }
+}
+
+/// The shared agent, built once per process.
+pub fn agent() -> Result<Agent> {
+    static AGENT: OnceLock<std::result::Result<Agent, String>> = OnceLock::new();
+    AGENT
+        .get_or_init(|| {
+            let bundle = std::env::var_os(CA_BUNDLE_ENV).map(PathBuf::from);
+            build_agent(bundle.as_deref()).map_err(|e| e.to_string())
+        })
+        .clone()
+        .map_err(Error::Other)
+}
+
+/// A GET of `url` on the shared agent, without credentials.
+pub fn get(url: &str) -> Result<RequestBuilder<WithoutBody>> {
+    Ok(agent()?.get(url))
+}
+
+/// A GET of the Config document at `url`, with its [`credentials`].
+pub fn get_config(url: &str) -> Result<RequestBuilder<WithoutBody>> {
+    Ok(authorize(get(url)?, credentials(url).as_ref()))
+}
+
+fn authorize(
+    request: RequestBuilder<WithoutBody>,
+    credentials: Option<&Credentials>,
+) -> RequestBuilder<WithoutBody> {
+    match credentials {
+        Some(credentials) => request.header("Authorization", credentials.header()),
+        None => request,
+    }
+}
+
+fn build_agent(ca_bundle: Option<&Path>) -> Result<Agent> {
+    let mut config = Agent::config_builder().proxy(Proxy::try_from_env());
+    if let Some(path) = ca_bundle {
+        let roots = read_ca_bundle(path)?;
+        config = config.tls_config(TlsConfig::builder().root_certs(roots).build());
+    }
+    Ok(config.build().into())
+}
+
+fn read_ca_bundle(path: &Path) -> Result<RootCerts> {
+    let pem = std::fs::read(path)
+        .map_err(|e| Error::Other(format!("{CA_BUNDLE_ENV}: {}: {e}", path.display())))?;
+    let mut certs = Vec::new();
+    for item in parse_pem(&pem) {
+        match item {
+            Ok(PemItem::Certificate(cert)) => certs.push(cert),
+            Ok(_) => {}
+            Err(e) => {
+                return Err(Error::Other(format!(
+                    "{CA_BUNDLE_ENV}: {}: {e}",
+                    path.display()
+                )))
+            }
+        }
+    }
+    if certs.is_empty() {
+        return Err(Error::Other(format!(
+            "{CA_BUNDLE_ENV}: {}: no certificates",
+            path.display()
+        )));
+    }
+    Ok(RootCerts::new_with_certs(&certs))
+}
+
+/// Credentials for fetching the Config document at `url`: `MACHINE_SETUP_TOKEN`
+/// for the `MACHINE_SETUP_TOKEN_HOST` host, else a netrc entry for the host,
+/// else `GITHUB_TOKEN` for GitHub hosts. Nothing is sent over plain HTTP,
+/// except to the loopback interface.
+pub fn credentials(url: &str) -> Option<Credentials> {
+    let netrc = std::env::var_os(NETRC_ENV)
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".netrc")))
+        .and_then(|path| std::fs::read_to_string(path).ok());
+    let env = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+    if env(TOKEN_ENV).is_some() && env(TOKEN_HOST_ENV).is_none() {
+        static WARNED: OnceLock<()> = OnceLock::new();
+        WARNED.get_or_init(|| {
+            eprintln!(
+                "warning: {TOKEN_ENV} is not sent anywhere until {TOKEN_HOST_ENV} names its host"
+            );
+        });
+    }
+    resolve(url, &env, netrc.as_deref())
+}
+
+fn resolve(
+    url: &str,
+    env: &dyn Fn(&str) -> Option<String>,
+    netrc: Option<&str>,
+) -> Option<Credentials> {
+    let (scheme, host) = scheme_and_host(url)?;
+    if scheme != "https" && !matches!(host, "localhost" | "127.0.0.1" | "[::1]") {
+        return None;
+    }
+    let token_host = env(TOKEN_HOST_ENV);
+    if token_host.is_some_and(|h| h.eq_ignore_ascii_case(host)) {
+        if let Some(token) = env(TOKEN_ENV) {
+            return Some(Credentials::Bearer(token));
+        }
+    }
+    if let Some(found) = netrc.and_then(|text| netrc_entry(text, host)) {
+        return Some(found);
+    }
+    if GITHUB_HOSTS.contains(&host) {
+        return env(GITHUB_TOKEN_ENV).map(Credentials::Bearer);
+    }
+    None
+}
+
+/// `("https", "example.com")` for `https://user@example.com:8443/a`.
+fn scheme_and_host(url: &str) -> Option<(&str, &str)> {
+    let (scheme, rest) = url.split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host_port = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+    let host = match host_port.find(']') {
+        Some(end) => &host_port[..=end],
+        None => host_port.split(':').next()?,
+    };
+    Some((scheme, host))
+}
+
+/// The login of `host` in netrc `text` (or its `default` entry).
+fn netrc_entry(text: &str, host: &str) -> Option<Credentials> {
+    // A `macdef` body runs to the next blank line and is not made of tokens.
+    let mut tokens = Vec::new();
+    let mut in_macro = false;
+    for line in text.lines() {
+        if in_macro {
+            in_macro = !line.trim().is_empty();
+            continue;
+        }
+        let mut words = line.split_whitespace();
+        while let Some(word) = words.next() {
+            if word == "macdef" {
+                words.next();
+                in_macro = true;
+                break;
+            }
+            tokens.push(word);
+        }
+    }
+
+    // (machine, login, password); `default` has no machine.
+    let mut entries: Vec<(Option<&str>, Option<&str>, Option<&str>)> = Vec::new();
+    let mut tokens = tokens.into_iter();
+    while let Some(token) = tokens.next() {
+        match (token, entries.last_mut()) {
+            ("machine", _) => entries.push((Some(tokens.next().unwrap_or_default()), None, None)),
+            ("default", _) => entries.push((None, None, None)),
+            ("login", Some(entry)) => entry.1 = tokens.next(),
+            ("password", Some(entry)) => entry.2 = tokens.next(),
+            ("account", _) => {
+                tokens.next();
+            }
+            _ => {}
+        }
+    }
+    let entry = entries
+        .iter()
+        .find(|(machine, _, _)| *machine == Some(host))
+        .or_else(|| entries.iter().find(|(machine, _, _)| machine.is_none()))?;
+    Some(Credentials::Basic {
+        login: entry.1?.to_string(),
+        password: entry.2?.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use tempfile::tempdir;
+
+    /// Serve one request on a local port with `body`; the request head
+    /// arrives on the returned channel.
+    fn stand_in(body: &'static str) -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/setup.yaml", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut head = String::new();
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap() == 0 || line == "\r\n" {
+                    break;
+                }
+                head.push_str(&line);
+            }
+            let mut stream = reader.into_inner();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .unwrap();
+            tx.send(head).unwrap();
+        });
+        (url, rx)
+    }
+
+    fn env<'a>(vars: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn request_carries_the_authorization_header() {
+        let (url, head) = stand_in("tasks: {}\n");
+        let vars = [(TOKEN_ENV, "s3cret"), (TOKEN_HOST_ENV, "127.0.0.1")];
+        let credentials = resolve(&url, &env(&vars), None);
+        let body = authorize(build_agent(None).unwrap().get(&url), credentials.as_ref())
+            .call()
+            .unwrap()
+            .into_body()
+            .read_to_string()
+            .unwrap();
+
+        assert_eq!(body, "tasks: {}\n");
+        let head = head.recv().unwrap().to_ascii_lowercase();
+        assert!(head.contains("authorization: bearer s3cret"), "{head}");
+    }
+
+    #[test]
+    fn token_is_not_sent_to_a_foreign_host() {
+        let (url, head) = stand_in("tasks: {}\n");
+        let vars = [(TOKEN_ENV, "s3cret"), (TOKEN_HOST_ENV, "dots.example.com")];
+        let credentials = resolve(&url, &env(&vars), None);
+        authorize(build_agent(None).unwrap().get(&url), credentials.as_ref())
+            .call()
+            .unwrap();
+
+        let head = head.recv().unwrap().to_ascii_lowercase();
+        assert!(!head.contains("authorization"), "{head}");
+        assert_eq!(resolve(&url, &env(&[(TOKEN_ENV, "s3cret")]), None), None);
+    }
+
+    #[test]
+    fn github_token_is_only_sent_to_github_over_https() {
+        let vars = [(GITHUB_TOKEN_ENV, "gh")];
+        let github = resolve(
+            "https://raw.githubusercontent.com/me/dots/main/a.yaml",
+            &env(&vars),
+            None,
+        );
+        assert_eq!(github, Some(Credentials::Bearer("gh".into())));
+        assert_eq!(
+            resolve("https://example.com/a.yaml", &env(&vars), None),
+            None
+        );
+
+        let token = [(TOKEN_ENV, "t"), (TOKEN_HOST_ENV, "example.com")];
+        assert_eq!(
+            resolve("http://example.com/a.yaml", &env(&token), None),
+            None
+        );
+        let loopback = [(TOKEN_ENV, "t"), (TOKEN_HOST_ENV, "127.0.0.1")];
+        assert!(resolve("http://127.0.0.1:8080/a.yaml", &env(&loopback), None).is_some());
+    }
+
+    #[test]
+    fn netrc_matches_the_host_before_the_default() {
+        let netrc = "machine other.example login x password y\n\
+                     macdef init\nmachine gitlab.example login no password no\n\n\
+                     default login anon password guest\n\
+                     machine gitlab.example\n  login me\n  password pat\n";
+        let basic = |login: &str, password: &str| Credentials::Basic {
+            login: login.into(),
+            password: password.into(),
+        };
+
+        let found = resolve(
+            "https://me@gitlab.example:8443/a.yaml",
+            &env(&[]),
+            Some(netrc),
+        );
+        assert_eq!(found, Some(basic("me", "pat")));
+        let fallback = resolve("https://unknown.example/a.yaml", &env(&[]), Some(netrc));
+        assert_eq!(fallback, Some(basic("anon", "guest")));
+        assert_eq!(
+            basic("me", "pat").header(),
+            format!("Basic {}", STANDARD.encode("me:pat"))
+        );
+    }
+
+    #[test]
+    fn ca_bundle_without_certificates_is_an_error() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("ca.pem");
+        std::fs::write(&path, "not a certificate\n").unwrap();
+        let err = build_agent(Some(&path)).unwrap_err();
+        assert!(err.to_string().contains("no certificates"), "{err}");
+        assert!(build_agent(Some(&dir.path().join("missing.pem"))).is_err());
+    }
+}
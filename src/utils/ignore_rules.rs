@@ -0,0 +1,176 @@
+//! Gitignore-style `ignore` patterns for tree-shaped Command entries.
+//!
+//! Patterns follow `.gitignore` semantics: a bare name (`dist`) matches at
+//! any depth, a leading or inner `/` anchors to the source root, `**` spans
+//! directories, a trailing `/` matches directories only and `!` re-includes.
+//! Patterns from a [`IGNORE_FILE`] in the source root are added after the
+//! entry's own list, so the file can re-include what the entry excluded.
+
+use std::path::{Path, PathBuf};
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use walkdir::WalkDir;
+
+use crate::error::{Error, Result};
+
+/// Per-source ignore file, read from the root of a directory `src`.
+pub const IGNORE_FILE: &str = ".machine_setupignore";
+
+/// Compiled ignore patterns rooted at one source directory.
+pub struct IgnoreRules {
+    matcher: Gitignore,
+}
+
+impl IgnoreRules {
+    /// Compile `patterns` plus `root`'s [`IGNORE_FILE`] (when present).
+    pub fn load(root: &Path, patterns: &[String]) -> Result<Self> {
+        let mut builder = builder_with(root, patterns)?;
+        let file = root.join(IGNORE_FILE);
+        if file.is_file() {
+            if let Some(err) = builder.add(&file) {
+                return Err(Error::Other(format!("{}: {err}", file.display())));
+            }
+        }
+        let matcher = builder
+            .build()
+            .map_err(|e| Error::Other(format!("invalid ignore pattern: {e}")))?;
+        Ok(Self { matcher })
+    }
+
+    /// Compile only `patterns` (no ignore file).
+    pub fn from_patterns(root: &Path, patterns: &[String]) -> Result<Self> {
+        let matcher = builder_with(root, patterns)?
+            .build()
+            .map_err(|e| Error::Other(format!("invalid ignore pattern: {e}")))?;
+        Ok(Self { matcher })
+    }
+
+    /// Whether `relative` (a path below the root) is excluded. The ignore file
+    /// itself is always excluded so it never lands in the target.
+    pub fn is_ignored(&self, relative: &Path, is_dir: bool) -> bool {
+        if !is_dir && relative == Path::new(IGNORE_FILE) {
+            return true;
+        }
+        self.matcher.matched(relative, is_dir).is_ignore()
+    }
+}
+
+fn builder_with(root: &Path, patterns: &[String]) -> Result<GitignoreBuilder> {
+    let mut builder = GitignoreBuilder::new(root);
+    for pattern in patterns {
+        builder
+            .add_line(None, pattern)
+            .map_err(|e| Error::Other(format!("invalid ignore pattern {pattern:?}: {e}")))?;
+    }
+    Ok(builder)
+}
+
+/// Patterns from `patterns` that match no path below `root` — usually typos.
+///
+/// A negated pattern counts as matching when it would re-include something.
+/// Invalid patterns are returned too (they can never match).
+pub fn unmatched_patterns(root: &Path, patterns: &[String]) -> Vec<String> {
+    let mut pending: Vec<(&String, Option<Gitignore>)> = patterns
+        .iter()
+        .map(|pattern| {
+            let single = builder_with(root, std::slice::from_ref(pattern))
+                .ok()
+                .and_then(|b| b.build().ok());
+            (pattern, single)
+        })
+        .collect();
+
+    for entry in WalkDir::new(root).min_depth(1).into_iter().flatten() {
+        if pending.iter().all(|(_, m)| m.is_none()) {
+            break;
+        }
+        let relative: PathBuf = entry
+            .path()
+            .strip_prefix(root)
+            .unwrap_or(entry.path())
+            .into();
+        let is_dir = entry.file_type().is_dir();
+        pending.retain(|(_, matcher)| {
+            matcher
+                .as_ref()
+                .is_none_or(|m| m.matched(&relative, is_dir).is_none())
+        });
+    }
+    pending.into_iter().map(|(p, _)| p.clone()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn rules(patterns: &[&str]) -> IgnoreRules {
+        let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+        IgnoreRules::from_patterns(Path::new("/src"), &patterns).unwrap()
+    }
+
+    #[test]
+    fn bare_name_matches_whole_component_not_substring() {
+        let r = rules(&["md"]);
+        assert!(r.is_ignored(Path::new("docs/md"), true));
+        assert!(!r.is_ignored(Path::new("cmd"), true));
+        assert!(!r.is_ignored(Path::new("README.md"), false));
+    }
+
+    #[test]
+    fn file_name_matches_at_any_depth() {
+        let r = rules(&["README.md"]);
+        assert!(r.is_ignored(Path::new("path/to/README.md"), false));
+        assert!(!r.is_ignored(Path::new("path/to/config.yaml"), false));
+    }
+
+    #[test]
+    fn double_star_matches_at_the_top_level_too() {
+        let r = rules(&["**/*.swp"]);
+        assert!(r.is_ignored(Path::new("b.swp"), false));
+        assert!(r.is_ignored(Path::new("a/b.swp"), false));
+        assert!(!r.is_ignored(Path::new("a/b.swo"), false));
+    }
+
+    #[test]
+    fn double_star_and_negation() {
+        let r = rules(&["**/*.swp", "*.log", "!keep.log"]);
+        assert!(r.is_ignored(Path::new("a/b/.x.swp"), false));
+        assert!(r.is_ignored(Path::new("debug.log"), false));
+        assert!(!r.is_ignored(Path::new("keep.log"), false));
+    }
+
+    #[test]
+    fn anchored_and_directory_only_patterns() {
+        let r = rules(&["/build", "cache/"]);
+        assert!(r.is_ignored(Path::new("build"), true));
+        assert!(!r.is_ignored(Path::new("nested/build"), true));
+        assert!(r.is_ignored(Path::new("nested/cache"), true));
+        assert!(!r.is_ignored(Path::new("cache"), false));
+    }
+
+    #[test]
+    fn ignore_file_is_read_and_never_copied() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join(IGNORE_FILE), "*.bak\n").unwrap();
+        let r = IgnoreRules::load(dir.path(), &[]).unwrap();
+        assert!(r.is_ignored(Path::new("x.bak"), false));
+        assert!(r.is_ignored(Path::new(IGNORE_FILE), false));
+        assert!(!r.is_ignored(Path::new("x.txt"), false));
+    }
+
+    #[test]
+    fn unmatched_patterns_reports_typos() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("node_modules/pkg")).unwrap();
+        std::fs::write(dir.path().join("a.swp"), "").unwrap();
+        let patterns: Vec<String> = ["node_modules", "*.swp", "nod_modules", "!a.swp"]
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        assert_eq!(
+            unmatched_patterns(dir.path(), &patterns),
+            vec!["nod_modules".to_string()]
+        );
+    }
+}
@@ -0,0 +1,182 @@
+//! Several machine_setup processes on one `temp_dir` — a `schedule run` timer
+//! firing during an interactive `update`:
+//!
+//! - [`write_atomic`] replaces a file through a temporary sibling and a
+//!   rename, so a reader never sees it half-written;
+//! - [`FileLock`] is an advisory lock around History's re-read, merge and
+//!   write;
+//! - [`RunLock`] on `temp_dir/run.lock` lets one run execute Tasks at a time
+//!   and records the PID and command of the run holding it.
+
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+
+use crate::error::{Error, Result};
+
+/// Run lock file inside `temp_dir`.
+pub const RUN_LOCK: &str = "run.lock";
+
+/// Write `contents` to `path` through a temporary file in the same directory
+/// and a rename.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let tmp = path.with_file_name(format!(".{name}.{}.tmp", std::process::id()));
+    let written = File::create(&tmp).and_then(|mut file| {
+        file.write_all(contents)?;
+        file.sync_all()
+    });
+    let result = written.and_then(|()| std::fs::rename(&tmp, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    result
+}
+
+fn open(path: &Path) -> std::io::Result<File> {
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+}
+
+/// An exclusive advisory lock on `path` (created if missing), held until
+/// dropped. Other processes and other open handles of this one wait for it.
+pub struct FileLock {
+    _file: File,
+}
+
+impl FileLock {
+    pub fn exclusive(path: &Path) -> Result<Self> {
+        let file = open(path)?;
+        file.lock()?;
+        Ok(Self { _file: file })
+    }
+}
+
+/// The lock of one run on a `temp_dir`, held until dropped.
+pub struct RunLock {
+    file: File,
+}
+
+impl RunLock {
+    /// Take the run lock of `temp_dir`. When another run holds it, wait for
+    /// that run to finish if `wait`, else fail with [`Error::RunLocked`].
+    pub fn acquire(temp_dir: &Path, wait: bool) -> Result<Self> {
+        std::fs::create_dir_all(temp_dir)?;
+        let path = temp_dir.join(RUN_LOCK);
+        let mut file = open(&path)?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let holder = holder(&mut file);
+                if !wait {
+                    return Err(Error::RunLocked(path.display().to_string(), holder));
+                }
+                eprintln!("Waiting for {holder} to finish...");
+                file.lock()?;
+            }
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+
+        let command: Vec<String> = std::env::args().collect();
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        write!(
+            file,
+            "{}\n{}\n{}\n",
+            std::process::id(),
+            Utc::now().format("%Y-%m-%d %H:%M UTC"),
+            command.join(" ")
+        )?;
+        Ok(Self { file })
+    }
+
+    /// [`RunLock::acquire`] on a blocking thread, so waiting for another run
+    /// does not stall the async runtime.
+    pub async fn acquire_async(temp_dir: PathBuf, wait: bool) -> Result<Self> {
+        tokio::task::spawn_blocking(move || Self::acquire(&temp_dir, wait))
+            .await
+            .map_err(|e| Error::Other(format!("waiting for the run lock: {e}")))?
+    }
+}
+
+impl Drop for RunLock {
+    fn drop(&mut self) {
+        let _ = self.file.set_len(0);
+    }
+}
+
+/// `PID 1234 (machine_setup update, since …)` from the lock file, as far as
+/// the holder has written it.
+fn holder(file: &mut File) -> String {
+    let mut content = String::new();
+    let _ = file.read_to_string(&mut content);
+    let mut lines = content.lines();
+    match (lines.next(), lines.next(), lines.next()) {
+        (Some(pid), Some(since), Some(command)) => {
+            format!("PID {pid} (`{command}`, since {since})")
+        }
+        _ => "another machine_setup run".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn write_atomic_replaces_the_file_and_leaves_no_temporary() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("history.json");
+        std::fs::write(&path, "old").unwrap();
+
+        write_atomic(&path, b"new").unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+        let names: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(names, vec!["history.json"]);
+    }
+
+    #[test]
+    fn second_run_lock_names_the_holder_until_released() {
+        let dir = tempdir().unwrap();
+        let held = RunLock::acquire(dir.path(), false).unwrap();
+
+        let err = RunLock::acquire(dir.path(), false).err().unwrap();
+        let message = err.to_string();
+        assert!(
+            message.contains(&format!("PID {}", std::process::id())),
+            "{message}"
+        );
+        assert!(message.contains("--wait"), "{message}");
+
+        drop(held);
+        assert!(RunLock::acquire(dir.path(), false).is_ok());
+    }
+
+    #[tokio::test]
+    async fn waiting_for_the_run_lock_leaves_the_runtime_free() {
+        let dir = tempdir().unwrap();
+        let held = RunLock::acquire(dir.path(), false).unwrap();
+
+        let waiter = tokio::spawn(RunLock::acquire_async(dir.path().to_path_buf(), true));
+        // This task still gets to run while the other one waits.
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(!waiter.is_finished());
+
+        drop(held);
+        assert!(waiter.await.unwrap().is_ok());
+    }
+}
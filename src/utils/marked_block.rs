@@ -0,0 +1,81 @@
+//! Marker-delimited blocks inside text files.
+//!
+//! Shared by the schedule shell-hook stub and the `block_in_file` Command
+//! entry: both own a block between a begin and an end marker line and must
+//! insert, refresh or strip it without touching the rest of the file.
+
+/// Replace the block between `begin` and `end` (inclusive, plus the trailing
+/// newline) with `new_block`. `None` when no complete block exists.
+pub fn replace(content: &str, begin: &str, end: &str, new_block: &str) -> Option<String> {
+    let (start, stop) = find(content, begin, end)?;
+    let mut out = String::with_capacity(content.len() + new_block.len());
+    out.push_str(&content[..start]);
+    out.push_str(new_block);
+    out.push_str(&content[stop..]);
+    Some(out)
+}
+
+/// Remove the block between `begin` and `end`. `None` when no complete block exists.
+pub fn strip(content: &str, begin: &str, end: &str) -> Option<String> {
+    let (start, stop) = find(content, begin, end)?;
+    let mut out = String::with_capacity(content.len());
+    out.push_str(&content[..start]);
+    out.push_str(&content[stop..]);
+    Some(out)
+}
+
+/// Refresh the block in place, or append it (on its own line) when absent.
+pub fn upsert(content: &str, begin: &str, end: &str, new_block: &str) -> String {
+    if let Some(updated) = replace(content, begin, end, new_block) {
+        return updated;
+    }
+    let mut out = content.to_string();
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+    out.push_str(new_block);
+    out
+}
+
+/// Byte range of the block, including one trailing newline after `end`.
+fn find(content: &str, begin: &str, end: &str) -> Option<(usize, usize)> {
+    let start = content.find(begin)?;
+    let end_rel = content[start..].find(end)?;
+    let mut stop = start + end_rel + end.len();
+    if content[stop..].starts_with('\n') {
+        stop += 1;
+    }
+    Some((start, stop))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const B: &str = "# >>> b >>>";
+    const E: &str = "# <<< b <<<";
+
+    #[test]
+    fn upsert_appends_then_replaces() {
+        let block = format!("{B}\none\n{E}\n");
+        let once = upsert("keep", B, E, &block);
+        assert_eq!(once, format!("keep\n{block}"));
+
+        let block2 = format!("{B}\ntwo\n{E}\n");
+        let twice = upsert(&once, B, E, &block2);
+        assert_eq!(twice, format!("keep\n{block2}"));
+    }
+
+    #[test]
+    fn strip_removes_block_and_trailing_newline() {
+        let content = format!("a\n{B}\nx\n{E}\nb\n");
+        assert_eq!(strip(&content, B, E).unwrap(), "a\nb\n");
+    }
+
+    #[test]
+    fn incomplete_block_is_not_matched() {
+        let content = format!("a\n{B}\nx\n");
+        assert!(strip(&content, B, E).is_none());
+        assert!(replace(&content, B, E, "new").is_none());
+    }
+}
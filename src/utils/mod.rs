@@ -1,4 +1,10 @@
+pub mod archive;
+pub mod http;
+pub mod ignore_rules;
+pub mod lock;
+pub mod marked_block;
 pub mod path;
 pub mod process;
 pub mod shell;
 pub mod sudo;
+pub mod template;
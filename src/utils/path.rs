@@ -1,8 +1,9 @@
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
 use walkdir::{DirEntry, WalkDir};
 
 use crate::error::Result;
+use crate::utils::ignore_rules::IgnoreRules;
 
 /// Expand `~` to the user's home directory, `$VAR` to environment variables,
 /// and resolve relative paths against the base directory.
@@ -81,9 +82,10 @@ fn expand_env_vars(input: &str) -> String {
 }
 
 /// Walk `src` and invoke `f` for each entry that isn't filtered out by
-/// `ignore_list`. The closure receives the raw `DirEntry` plus the
-/// precomputed destination path (`target` joined with the entry's
-/// `src`-relative suffix).
+/// `ignore_list` (gitignore semantics, plus `src`'s `.machine_setupignore` —
+/// see [`crate::utils::ignore_rules`]). The closure receives the raw
+/// `DirEntry` plus the precomputed destination path (`target` joined with the
+/// entry's `src`-relative suffix).
 ///
 /// Ignored **directories** are not descended into; ignored **files** are
 /// skipped. The walk root (`src`) is never pruned by ignore.
@@ -94,13 +96,14 @@ pub fn walk_relative<F>(src: &Path, target: &Path, ignore_list: &[String], mut f
 where
     F: FnMut(&DirEntry, &Path) -> Result<()>,
 {
+    let rules = IgnoreRules::load(src, ignore_list)?;
     let walker = WalkDir::new(src).into_iter().filter_entry(|entry| {
         // Never prune the walk root — ignore applies only to descendants.
         if entry.depth() == 0 {
             return true;
         }
         let relative = entry.path().strip_prefix(src).unwrap_or(entry.path());
-        !should_ignore(relative, ignore_list)
+        !rules.is_ignored(relative, entry.file_type().is_dir())
     });
 
     for entry in walker.filter_map(|e| e.ok()) {
@@ -111,17 +114,6 @@ where
     Ok(())
 }
 
-/// Check if a path should be ignored based on the ignore list.
-pub fn should_ignore(path: &Path, ignore_list: &[String]) -> bool {
-    let path_str = path.to_string_lossy();
-    ignore_list.iter().any(|pattern| {
-        // Simple string matching — check if the file/dir name matches
-        path.file_name()
-            .is_some_and(|name| name.to_string_lossy() == *pattern)
-            || path_str.contains(pattern.as_str())
-    })
-}
-
 /// Compact path for log output: replace home directory prefix with `~/`.
 pub fn shorten_path(path: &Path) -> String {
     if let Some(home) = dirs::home_dir() {
@@ -135,6 +127,47 @@ pub fn shorten_path(path: &Path) -> String {
     path.display().to_string()
 }
 
+/// `path` expressed relative to the directory `base` (lexically, after
+/// dropping `.` and resolving `..`). `None` when the two do not share a root —
+/// a relative path, or another drive on Windows.
+pub fn relative_to(path: &Path, base: &Path) -> Option<PathBuf> {
+    let path = normalize(path);
+    let base = normalize(base);
+    if !path.has_root() || !base.has_root() {
+        return None;
+    }
+    let mut path_parts = path.components().peekable();
+    let mut base_parts = base.components().peekable();
+    if path_parts.peek() != base_parts.peek() {
+        return None;
+    }
+    while path_parts.peek().is_some() && path_parts.peek() == base_parts.peek() {
+        path_parts.next();
+        base_parts.next();
+    }
+    let mut relative: PathBuf = base_parts.map(|_| Component::ParentDir).collect();
+    relative.extend(path_parts);
+    if relative.as_os_str().is_empty() {
+        relative.push(".");
+    }
+    Some(relative)
+}
+
+/// `path` with `.` and `..` resolved lexically, without touching the disk.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -203,18 +236,6 @@ mod tests {
         assert!(expanded.to_string_lossy().contains(".config"));
     }
 
-    #[test]
-    fn test_should_ignore() {
-        assert!(should_ignore(
-            Path::new("/path/to/README.md"),
-            &["README.md".to_string()]
-        ));
-        assert!(!should_ignore(
-            Path::new("/path/to/config.yaml"),
-            &["README.md".to_string()]
-        ));
-    }
-
     #[test]
     fn walk_relative_prunes_ignored_directory_children() {
         let dir = tempfile::tempdir().unwrap();
@@ -252,4 +273,24 @@ mod tests {
             .any(|p| p == "root.txt" || p.ends_with("root.txt")));
         assert!(seen.iter().any(|p| p.contains("a.txt")));
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_relative_to() {
+        let rel = |p: &str, b: &str| relative_to(Path::new(p), Path::new(b));
+        assert_eq!(
+            rel("/home/me/dotfiles/.bashrc", "/home/me"),
+            Some(PathBuf::from("dotfiles/.bashrc"))
+        );
+        assert_eq!(
+            rel("/home/me/dotfiles/nvim", "/home/me/.config"),
+            Some(PathBuf::from("../dotfiles/nvim"))
+        );
+        assert_eq!(
+            rel("/opt/x", "/home/me/./a/../b"),
+            Some(PathBuf::from("../../../opt/x"))
+        );
+        assert_eq!(rel("/a", "/a"), Some(PathBuf::from(".")));
+        assert_eq!(rel("a/b", "/a"), None);
+    }
 }
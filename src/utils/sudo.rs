@@ -43,6 +43,20 @@ pub enum SudoOp {
     Symlink { src: PathBuf, dest: PathBuf },
     Remove(PathBuf),
     RemoveDir(PathBuf),
+    RemoveEmptyDir(PathBuf),
+    Chmod { path: PathBuf, mode: u32 },
+    Chown { path: PathBuf, spec: String },
+    Move { src: PathBuf, dest: PathBuf },
+}
+
+/// `chown` argument for an owner and/or group: `owner:group`, `owner` or `:group`.
+pub fn chown_spec(owner: Option<&str>, group: Option<&str>) -> String {
+    match (owner, group) {
+        (Some(owner), Some(group)) => format!("{owner}:{group}"),
+        (Some(owner), None) => owner.to_string(),
+        (None, Some(group)) => format!(":{group}"),
+        (None, None) => String::new(),
+    }
 }
 
 /// Shell-escape a path for single-quoted use in a bash script.
@@ -71,6 +85,22 @@ pub fn build_sudo_script(ops: &[SudoOp]) -> String {
             SudoOp::RemoveDir(path) => {
                 script.push_str(&format!("rm -rf {}\n", sh_quote(path)));
             }
+            SudoOp::RemoveEmptyDir(path) => {
+                script.push_str(&format!("rmdir {} 2>/dev/null || true\n", sh_quote(path)));
+            }
+            SudoOp::Chmod { path, mode } => {
+                script.push_str(&format!("chmod {mode:04o} {}\n", sh_quote(path)));
+            }
+            SudoOp::Chown { path, spec } => {
+                script.push_str(&format!(
+                    "chown {} {}\n",
+                    sh_quote(Path::new(spec)),
+                    sh_quote(path)
+                ));
+            }
+            SudoOp::Move { src, dest } => {
+                script.push_str(&format!("mv -f {} {}\n", sh_quote(src), sh_quote(dest)));
+            }
         }
     }
     script
@@ -133,6 +163,13 @@ mod tests {
         assert_eq!(sh_quote(Path::new("a'b")), r"'a'\''b'");
     }
 
+    #[test]
+    fn chown_spec_combines_owner_and_group() {
+        assert_eq!(chown_spec(Some("me"), Some("staff")), "me:staff");
+        assert_eq!(chown_spec(Some("me"), None), "me");
+        assert_eq!(chown_spec(None, Some("staff")), ":staff");
+    }
+
     #[test]
     fn build_sudo_script_orders_ops() {
         let script = build_sudo_script(&[
@@ -147,12 +184,29 @@ mod tests {
             },
             SudoOp::Remove(PathBuf::from("/t/old")),
             SudoOp::RemoveDir(PathBuf::from("/t/dir")),
+            SudoOp::RemoveEmptyDir(PathBuf::from("/t/empty")),
+            SudoOp::Chmod {
+                path: PathBuf::from("/t/f"),
+                mode: 0o600,
+            },
+            SudoOp::Chown {
+                path: PathBuf::from("/t/f"),
+                spec: "me:staff".to_string(),
+            },
+            SudoOp::Move {
+                src: PathBuf::from("/t/f"),
+                dest: PathBuf::from("/b/f"),
+            },
         ]);
         assert!(script.contains("mkdir -p '/t'\n"));
         assert!(script.contains("cp -f '/s/f' '/t/f'\n"));
         assert!(script.contains("ln -sf '/s/l' '/t/l'\n"));
         assert!(script.contains("rm -f '/t/old'\n"));
         assert!(script.contains("rm -rf '/t/dir'\n"));
+        assert!(script.contains("rmdir '/t/empty' 2>/dev/null || true\n"));
+        assert!(script.contains("chmod 0600 '/t/f'\n"));
+        assert!(script.contains("chown 'me:staff' '/t/f'\n"));
+        assert!(script.contains("mv -f '/t/f' '/b/f'\n"));
         assert!(script.starts_with("set -euo pipefail\n"));
     }
 }
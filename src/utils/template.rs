@@ -0,0 +1,112 @@
+//! Minimal `{{ name }}` substitution for the `template` Command entry.
+//!
+//! Deliberately not a template language: no conditionals, loops or filters.
+//! Files that need logic belong in a `run` step; this covers the common
+//! "same file, different email/hostname" case without a new dependency.
+
+use indexmap::IndexMap;
+
+use crate::error::{Error, Result};
+
+/// Machine facts available to every template (`hostname`, `os`, `user`, `home`).
+pub fn facts() -> IndexMap<String, String> {
+    let mut facts = IndexMap::new();
+    facts.insert(
+        "hostname".to_string(),
+        gethostname::gethostname().to_string_lossy().into_owned(),
+    );
+    facts.insert(
+        "os".to_string(),
+        crate::config::os::Os::current()
+            .map(|os| os.to_string())
+            .unwrap_or_else(|| std::env::consts::OS.to_string()),
+    );
+    facts.insert(
+        "user".to_string(),
+        std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .unwrap_or_default(),
+    );
+    facts.insert(
+        "home".to_string(),
+        dirs::home_dir()
+            .map(|home| home.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+    );
+    facts
+}
+
+/// Replace every `{{ name }}` in `template` with its value from `vars`.
+///
+/// Unknown names and unterminated tags are errors, so a typo never renders an
+/// empty string into a config file.
+pub fn render(template: &str, vars: &IndexMap<String, String>) -> Result<String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            return Err(Error::Other(
+                "template: unterminated `{{` (missing `}}`)".to_string(),
+            ));
+        };
+        let name = after[..end].trim();
+        let value = vars
+            .get(name)
+            .ok_or_else(|| Error::Other(format!("template: unknown variable `{name}`")))?;
+        out.push_str(value);
+        rest = &after[end + 2..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> IndexMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn renders_variables_with_and_without_spaces() {
+        let out = render(
+            "[user]\n  email = {{ email }}\n  name = {{name}}\n",
+            &vars(&[("email", "me@example.com"), ("name", "Me")]),
+        )
+        .unwrap();
+        assert_eq!(out, "[user]\n  email = me@example.com\n  name = Me\n");
+    }
+
+    #[test]
+    fn unknown_variable_is_an_error() {
+        let err = render("{{ missing }}", &vars(&[])).unwrap_err();
+        assert!(err.to_string().contains("missing"));
+    }
+
+    #[test]
+    fn unterminated_tag_is_an_error() {
+        assert!(render("a {{ b", &vars(&[("b", "x")])).is_err());
+    }
+
+    #[test]
+    fn text_without_tags_is_unchanged() {
+        assert_eq!(
+            render("plain } { text", &vars(&[])).unwrap(),
+            "plain } { text"
+        );
+    }
+
+    #[test]
+    fn facts_include_core_keys() {
+        let facts = facts();
+        for key in ["hostname", "os", "user", "home"] {
+            assert!(facts.contains_key(key), "missing fact {key}");
+        }
+    }
+}
@@ -1491,6 +1491,46 @@ tasks:
     assert!(src_dir.join("dotfile").exists());
 }
 
+#[tokio::test]
+async fn test_symlink_uninstall_keeps_a_file_that_replaced_the_link() {
+    let dir = tempdir().unwrap();
+    let src_dir = dir.path().join("source");
+    let target_dir = dir.path().join("target");
+    fs::create_dir_all(&src_dir).unwrap();
+    fs::write(src_dir.join("dotfile"), "content").unwrap();
+
+    let config_path = dir.path().join("config.yaml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"
+temp_dir: "{}"
+tasks:
+  link_task:
+    commands:
+      - symlink:
+          src: "{}"
+          target: "{}"
+"#,
+            dir.path()
+                .join(".ms_temp")
+                .to_string_lossy()
+                .replace('\\', "/"),
+            src_dir.to_string_lossy().replace('\\', "/"),
+            target_dir.to_string_lossy().replace('\\', "/"),
+        ),
+    )
+    .unwrap();
+
+    run_at(&config_path, dir.path(), Mode::Install).await;
+    let link = target_dir.join("dotfile");
+    fs::remove_file(&link).unwrap();
+    fs::write(&link, "mine").unwrap();
+
+    run_at(&config_path, dir.path(), Mode::Uninstall).await;
+    assert_eq!(fs::read_to_string(&link).unwrap(), "mine");
+}
+
 #[tokio::test]
 async fn test_symlink_force_overwrites_existing_file() {
     let dir = tempdir().unwrap();
@@ -1507,66 +1547,1084 @@ async fn test_symlink_force_overwrites_existing_file() {
         &config_path,
         format!(
             r#"
-tasks:
-  link_task:
-    commands:
-      - symlink:
-          src: "{}"
-          target: "{}"
-          force: true
+tasks:
+  link_task:
+    commands:
+      - symlink:
+          src: "{}"
+          target: "{}"
+          force: true
+"#,
+            src_dir.to_string_lossy().replace('\\', "/"),
+            target_dir.to_string_lossy().replace('\\', "/"),
+        ),
+    )
+    .unwrap();
+
+    run_at(&config_path, dir.path(), Mode::Install).await;
+
+    let link = target_dir.join("dotfile");
+    let meta = link.symlink_metadata().unwrap();
+    assert!(
+        meta.file_type().is_symlink(),
+        "force should replace the file with a symlink"
+    );
+    // Reading through the link yields the source content.
+    assert_eq!(fs::read_to_string(&link).unwrap(), "from-source");
+}
+
+#[tokio::test]
+async fn test_symlink_without_force_skips_existing_file() {
+    let dir = tempdir().unwrap();
+    let src_dir = dir.path().join("source");
+    let target_dir = dir.path().join("target");
+    fs::create_dir_all(&src_dir).unwrap();
+    fs::create_dir_all(&target_dir).unwrap();
+    fs::write(src_dir.join("dotfile"), "from-source").unwrap();
+    fs::write(target_dir.join("dotfile"), "pre-existing").unwrap();
+
+    let config_path = dir.path().join("config.yaml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"
+tasks:
+  link_task:
+    commands:
+      - symlink:
+          src: "{}"
+          target: "{}"
+"#,
+            src_dir.to_string_lossy().replace('\\', "/"),
+            target_dir.to_string_lossy().replace('\\', "/"),
+        ),
+    )
+    .unwrap();
+
+    run_at(&config_path, dir.path(), Mode::Install).await;
+
+    // Without force, the pre-existing real file is left in place.
+    let link = target_dir.join("dotfile");
+    assert!(!link.symlink_metadata().unwrap().file_type().is_symlink());
+    assert_eq!(fs::read_to_string(&link).unwrap(), "pre-existing");
+}
+
+#[tokio::test]
+async fn test_symlink_force_backup_restores_on_uninstall() {
+    let dir = tempdir().unwrap();
+    let src_dir = dir.path().join("source");
+    let target_dir = dir.path().join("target");
+    let temp_dir = dir.path().join(".ms_temp");
+    fs::create_dir_all(&src_dir).unwrap();
+    fs::create_dir_all(&target_dir).unwrap();
+    fs::write(src_dir.join("dotfile"), "from-source").unwrap();
+    fs::write(target_dir.join("dotfile"), "pre-existing").unwrap();
+
+    let config_path = dir.path().join("config.yaml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"
+temp_dir: "{}"
+tasks:
+  link_task:
+    commands:
+      - symlink:
+          src: "{}"
+          target: "{}"
+          force: true
+          backup: true
+"#,
+            temp_dir.to_string_lossy().replace('\\', "/"),
+            src_dir.to_string_lossy().replace('\\', "/"),
+            target_dir.to_string_lossy().replace('\\', "/"),
+        ),
+    )
+    .unwrap();
+
+    run_at(&config_path, dir.path(), Mode::Install).await;
+    let link = target_dir.join("dotfile");
+    assert!(link.symlink_metadata().unwrap().file_type().is_symlink());
+    let backups = machine_setup::engine::commands::backup::list(&temp_dir).unwrap();
+    assert_eq!(backups.len(), 1);
+    assert_eq!(backups[0].record.task, "link_task");
+    assert_eq!(
+        fs::read_to_string(&backups[0].record.files[0].stored).unwrap(),
+        "pre-existing"
+    );
+
+    // A second install replaces only its own link: nothing new is backed up.
+    run_at(&config_path, dir.path(), Mode::Install).await;
+    assert_eq!(
+        machine_setup::engine::commands::backup::list(&temp_dir)
+            .unwrap()
+            .len(),
+        1
+    );
+
+    run_at(&config_path, dir.path(), Mode::Uninstall).await;
+    assert!(!link.symlink_metadata().unwrap().file_type().is_symlink());
+    assert_eq!(fs::read_to_string(&link).unwrap(), "pre-existing");
+    assert!(machine_setup::engine::commands::backup::list(&temp_dir)
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_copy_update_without_a_manifest_pushes_source_edits() {
+    let dir = tempdir().unwrap();
+    let src_dir = dir.path().join("source");
+    let target_dir = dir.path().join("target");
+    let temp_dir = dir.path().join(".ms_temp");
+    fs::create_dir_all(&src_dir).unwrap();
+    fs::write(src_dir.join("dotfile"), "v1").unwrap();
+    let config_path = dir.path().join("config.yaml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"
+temp_dir: "{}"
+tasks:
+  copy_task:
+    commands:
+      - copy:
+          src: "{}"
+          target: "{}"
+"#,
+            temp_dir.to_string_lossy().replace('\\', "/"),
+            src_dir.to_string_lossy().replace('\\', "/"),
+            target_dir.to_string_lossy().replace('\\', "/"),
+        ),
+    )
+    .unwrap();
+
+    // An install from before Entry manifests were written.
+    run_at(&config_path, dir.path(), Mode::Install).await;
+    fs::remove_dir_all(temp_dir.join("manifests")).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    fs::write(src_dir.join("dotfile"), "v2").unwrap();
+
+    let config = config::load_config(config_path.to_str().unwrap()).unwrap();
+    let (events, _rx) = machine_setup::engine::sink::ChannelSink::channel();
+    let runner =
+        TaskRunner::new(config, Mode::Update, events).with_config_dir(dir.path().to_path_buf());
+    runner.run_all(true).await.unwrap();
+
+    assert_eq!(
+        fs::read_to_string(target_dir.join("dotfile")).unwrap(),
+        "v2"
+    );
+}
+
+#[tokio::test]
+async fn test_copy_force_backup_restores_on_uninstall() {
+    let dir = tempdir().unwrap();
+    let src_dir = dir.path().join("source");
+    let target_dir = dir.path().join("target");
+    let temp_dir = dir.path().join(".ms_temp");
+    fs::create_dir_all(&src_dir).unwrap();
+    fs::create_dir_all(&target_dir).unwrap();
+    fs::write(target_dir.join("dotfile"), "pre-existing").unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    fs::write(src_dir.join("dotfile"), "from-source").unwrap();
+
+    let config_path = dir.path().join("config.yaml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"
+temp_dir: "{}"
+tasks:
+  copy_task:
+    commands:
+      - copy:
+          src: "{}"
+          target: "{}"
+          force: true
+          backup: true
+"#,
+            temp_dir.to_string_lossy().replace('\\', "/"),
+            src_dir.to_string_lossy().replace('\\', "/"),
+            target_dir.to_string_lossy().replace('\\', "/"),
+        ),
+    )
+    .unwrap();
+
+    run_at(&config_path, dir.path(), Mode::Install).await;
+    let copy = target_dir.join("dotfile");
+    assert_eq!(fs::read_to_string(&copy).unwrap(), "from-source");
+    let backups = machine_setup::engine::commands::backup::list(&temp_dir).unwrap();
+    assert_eq!(backups.len(), 1);
+    assert_eq!(
+        fs::read_to_string(&backups[0].record.files[0].stored).unwrap(),
+        "pre-existing"
+    );
+
+    // Its own copy is updated in place: nothing new is backed up.
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    fs::write(src_dir.join("dotfile"), "edited-source").unwrap();
+    run_at(&config_path, dir.path(), Mode::Install).await;
+    assert_eq!(fs::read_to_string(&copy).unwrap(), "edited-source");
+    assert_eq!(
+        machine_setup::engine::commands::backup::list(&temp_dir)
+            .unwrap()
+            .len(),
+        1
+    );
+
+    run_at(&config_path, dir.path(), Mode::Uninstall).await;
+    assert_eq!(fs::read_to_string(&copy).unwrap(), "pre-existing");
+    assert!(machine_setup::engine::commands::backup::list(&temp_dir)
+        .unwrap()
+        .is_empty());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_symlink_fold_shares_directory_between_entries() {
+    let dir = tempdir().unwrap();
+    let nvim_src = dir.path().join("nvim_repo");
+    let fish_src = dir.path().join("fish_repo");
+    let home = dir.path().join("home");
+    fs::create_dir_all(nvim_src.join(".config/nvim/lua")).unwrap();
+    fs::create_dir_all(fish_src.join(".config/fish")).unwrap();
+    fs::create_dir_all(&home).unwrap();
+    fs::write(nvim_src.join(".config/nvim/init.lua"), "init").unwrap();
+    fs::write(nvim_src.join(".config/nvim/lua/a.lua"), "a").unwrap();
+    fs::write(fish_src.join(".config/fish/config.fish"), "fish").unwrap();
+
+    let config_path = dir.path().join("config.yaml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"
+temp_dir: "{}"
+tasks:
+  nvim:
+    commands:
+      - symlink:
+          src: "{}"
+          target: "{}"
+          fold: true
+  fish:
+    depends_on: [nvim]
+    commands:
+      - symlink:
+          src: "{}"
+          target: "{}"
+          fold: true
+"#,
+            dir.path().join(".ms_temp").to_string_lossy(),
+            nvim_src.to_string_lossy(),
+            home.to_string_lossy(),
+            fish_src.to_string_lossy(),
+            home.to_string_lossy(),
+        ),
+    )
+    .unwrap();
+
+    run_at(&config_path, dir.path(), Mode::Install).await;
+
+    let config = home.join(".config");
+    assert!(config.symlink_metadata().unwrap().is_dir());
+    assert_eq!(
+        fs::read_link(config.join("nvim")).unwrap(),
+        nvim_src.join(".config/nvim")
+    );
+    assert_eq!(
+        fs::read_link(config.join("fish")).unwrap(),
+        fish_src.join(".config/fish")
+    );
+    fs::write(nvim_src.join(".config/nvim/lua/b.lua"), "b").unwrap();
+    assert_eq!(
+        fs::read_to_string(config.join("nvim/lua/b.lua")).unwrap(),
+        "b"
+    );
+
+    run_at(&config_path, dir.path(), Mode::Uninstall).await;
+    assert!(config.join("nvim").symlink_metadata().is_err());
+    assert!(config.join("fish").symlink_metadata().is_err());
+    assert!(nvim_src.join(".config/nvim/lua/b.lua").exists());
+    assert!(fish_src.join(".config/fish/config.fish").exists());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_symlink_relative_links_survive_moving_the_tree() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("root");
+    let src_dir = root.join("dotfiles");
+    let home = root.join("home");
+    fs::create_dir_all(src_dir.join("nested")).unwrap();
+    fs::create_dir_all(&home).unwrap();
+    fs::write(src_dir.join(".bashrc"), "bashrc").unwrap();
+    fs::write(src_dir.join("nested/file"), "nested").unwrap();
+
+    let config_path = dir.path().join("config.yaml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"
+temp_dir: "{}"
+tasks:
+  link_task:
+    commands:
+      - symlink:
+          src: "{}"
+          target: "{}"
+          relative: true
+"#,
+            dir.path().join(".ms_temp").to_string_lossy(),
+            src_dir.to_string_lossy(),
+            home.to_string_lossy(),
+        ),
+    )
+    .unwrap();
+
+    run_at(&config_path, dir.path(), Mode::Install).await;
+    assert_eq!(
+        fs::read_link(home.join(".bashrc")).unwrap(),
+        std::path::PathBuf::from("../dotfiles/.bashrc")
+    );
+    assert_eq!(
+        fs::read_link(home.join("nested/file")).unwrap(),
+        std::path::PathBuf::from("../../dotfiles/nested/file")
+    );
+
+    // Same layout under another prefix: links still resolve.
+    let moved = dir.path().join("mnt");
+    fs::rename(&root, &moved).unwrap();
+    assert_eq!(
+        fs::read_to_string(moved.join("home/.bashrc")).unwrap(),
+        "bashrc"
+    );
+    fs::rename(&moved, &root).unwrap();
+
+    run_at(&config_path, dir.path(), Mode::Uninstall).await;
+    assert!(home.join(".bashrc").symlink_metadata().is_err());
+    assert!(src_dir.join(".bashrc").exists());
+}
+
+#[tokio::test]
+async fn test_prune_removes_stale_links_and_copies_only() {
+    let dir = tempdir().unwrap();
+    let src_dir = dir.path().join("dotfiles");
+    let links = dir.path().join("links");
+    let copies = dir.path().join("copies");
+    fs::create_dir_all(src_dir.join("nested")).unwrap();
+    fs::write(src_dir.join("keep"), "keep").unwrap();
+    fs::write(src_dir.join("nested/gone"), "gone").unwrap();
+
+    let config_path = dir.path().join("config.yaml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"
+temp_dir: "{}"
+tasks:
+  prune_task:
+    commands:
+      - symlink:
+          src: "{}"
+          target: "{}"
+          prune: true
+      - copy:
+          src: "{}"
+          target: "{}"
+          prune: true
+"#,
+            dir.path().join(".ms_temp").to_string_lossy(),
+            src_dir.to_string_lossy(),
+            links.to_string_lossy(),
+            src_dir.to_string_lossy(),
+            copies.to_string_lossy(),
+        ),
+    )
+    .unwrap();
+
+    run_at(&config_path, dir.path(), Mode::Install).await;
+    assert!(links.join("nested/gone").is_symlink());
+    assert!(copies.join("nested/gone").is_file());
+
+    // Files the entries never placed stay put.
+    fs::write(links.join("nested/mine"), "mine").unwrap();
+    fs::write(copies.join("mine"), "mine").unwrap();
+    fs::remove_file(src_dir.join("nested/gone")).unwrap();
+
+    run_at(&config_path, dir.path(), Mode::Update).await;
+    assert!(links.join("nested/gone").symlink_metadata().is_err());
+    assert!(copies.join("nested/gone").symlink_metadata().is_err());
+    assert!(links.join("keep").is_symlink());
+    assert_eq!(fs::read_to_string(copies.join("keep")).unwrap(), "keep");
+    assert_eq!(
+        fs::read_to_string(links.join("nested/mine")).unwrap(),
+        "mine"
+    );
+    assert_eq!(fs::read_to_string(copies.join("mine")).unwrap(), "mine");
+
+    // A recorded source deleted before uninstall is still removed.
+    fs::remove_file(src_dir.join("keep")).unwrap();
+    run_at(&config_path, dir.path(), Mode::Uninstall).await;
+    assert!(links.join("keep").symlink_metadata().is_err());
+    assert!(copies.join("keep").symlink_metadata().is_err());
+    assert!(copies.join("mine").exists());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_prune_keeps_edited_copies_and_repointed_links() {
+    let dir = tempdir().unwrap();
+    let src_dir = dir.path().join("dotfiles");
+    let links = dir.path().join("links");
+    let copies = dir.path().join("copies");
+    let elsewhere = dir.path().join("elsewhere");
+    fs::create_dir_all(&src_dir).unwrap();
+    fs::write(src_dir.join("a"), "a").unwrap();
+    fs::write(src_dir.join("b"), "b").unwrap();
+    fs::write(&elsewhere, "elsewhere").unwrap();
+
+    let config_path = dir.path().join("config.yaml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"
+temp_dir: "{}"
+tasks:
+  prune_task:
+    commands:
+      - symlink:
+          src: "{src}"
+          target: "{}"
+          prune: true
+      - copy:
+          src: "{src}"
+          target: "{}"
+          prune: true
+"#,
+            dir.path().join(".ms_temp").to_string_lossy(),
+            links.to_string_lossy(),
+            copies.to_string_lossy(),
+            src = src_dir.to_string_lossy(),
+        ),
+    )
+    .unwrap();
+
+    run_at(&config_path, dir.path(), Mode::Install).await;
+    fs::write(copies.join("a"), "edited after install").unwrap();
+    fs::remove_file(links.join("b")).unwrap();
+    std::os::unix::fs::symlink(&elsewhere, links.join("b")).unwrap();
+    fs::remove_file(src_dir.join("a")).unwrap();
+    fs::remove_file(src_dir.join("b")).unwrap();
+
+    run_at(&config_path, dir.path(), Mode::Update).await;
+    assert_eq!(
+        fs::read_to_string(copies.join("a")).unwrap(),
+        "edited after install"
+    );
+    assert!(copies.join("b").symlink_metadata().is_err());
+    assert_eq!(fs::read_link(links.join("b")).unwrap(), elsewhere);
+    assert!(links.join("a").symlink_metadata().is_err());
+}
+
+#[tokio::test]
+async fn test_copy_uninstall_removes_exactly_what_install_recorded() {
+    let dir = tempdir().unwrap();
+    let src_dir = dir.path().join("source");
+    let target_dir = dir.path().join("target");
+    fs::create_dir_all(src_dir.join("nested/deep")).unwrap();
+    fs::create_dir_all(&target_dir).unwrap();
+    fs::write(src_dir.join("a.txt"), "a").unwrap();
+    fs::write(src_dir.join("edited.txt"), "edited").unwrap();
+    fs::write(src_dir.join("nested/deep/b.txt"), "b").unwrap();
+    fs::write(target_dir.join("theirs.txt"), "theirs").unwrap();
+
+    let config_path = dir.path().join("config.yaml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"
+temp_dir: "{}"
+tasks:
+  copy_task:
+    commands:
+      - copy:
+          src: "{}"
+          target: "{}"
+"#,
+            dir.path().join(".ms_temp").to_string_lossy(),
+            src_dir.to_string_lossy(),
+            target_dir.to_string_lossy(),
+        ),
+    )
+    .unwrap();
+
+    run_at(&config_path, dir.path(), Mode::Install).await;
+    assert!(target_dir.join("nested/deep/b.txt").exists());
+
+    // The repo moves on: a file is deleted, and a new one shares a name with
+    // a file the copy never created. One copied file is edited in place.
+    fs::remove_file(src_dir.join("nested/deep/b.txt")).unwrap();
+    fs::write(src_dir.join("theirs.txt"), "repo").unwrap();
+    fs::write(target_dir.join("edited.txt"), "edited by hand").unwrap();
+
+    run_at(&config_path, dir.path(), Mode::Uninstall).await;
+    assert!(target_dir.join("a.txt").symlink_metadata().is_err());
+    assert!(target_dir.join("nested").symlink_metadata().is_err());
+    assert_eq!(
+        fs::read_to_string(target_dir.join("theirs.txt")).unwrap(),
+        "theirs"
+    );
+    assert_eq!(
+        fs::read_to_string(target_dir.join("edited.txt")).unwrap(),
+        "edited by hand"
+    );
+    // `target` existed before install, so it stays.
+    assert!(target_dir.is_dir());
+}
+
+#[tokio::test]
+async fn test_copy_uninstall_keeps_a_newer_destination_it_skipped() {
+    let dir = tempdir().unwrap();
+    let src_dir = dir.path().join("source");
+    let target_dir = dir.path().join("target");
+    fs::create_dir_all(&src_dir).unwrap();
+    fs::create_dir_all(&target_dir).unwrap();
+    fs::write(src_dir.join("a.txt"), "a").unwrap();
+    fs::write(src_dir.join("conf"), "repo").unwrap();
+    // Same size and newer than the source: the mtime compare skips it.
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    fs::write(target_dir.join("conf"), "mine").unwrap();
+
+    let config_path = dir.path().join("config.yaml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"
+temp_dir: "{}"
+tasks:
+  copy_task:
+    commands:
+      - copy:
+          src: "{}"
+          target: "{}"
+"#,
+            dir.path().join(".ms_temp").to_string_lossy(),
+            src_dir.to_string_lossy(),
+            target_dir.to_string_lossy(),
+        ),
+    )
+    .unwrap();
+
+    run_at(&config_path, dir.path(), Mode::Install).await;
+    assert_eq!(fs::read_to_string(target_dir.join("conf")).unwrap(), "mine");
+
+    run_at(&config_path, dir.path(), Mode::Uninstall).await;
+    assert!(target_dir.join("a.txt").symlink_metadata().is_err());
+    assert_eq!(fs::read_to_string(target_dir.join("conf")).unwrap(), "mine");
+}
+
+#[tokio::test]
+async fn test_copy_compare_hash_leaves_unchanged_files_alone() {
+    let dir = tempdir().unwrap();
+    let src_dir = dir.path().join("source");
+    let target_dir = dir.path().join("target");
+    fs::create_dir_all(&src_dir).unwrap();
+    fs::write(src_dir.join("same.txt"), "same").unwrap();
+    fs::write(src_dir.join("edit.txt"), "v1").unwrap();
+
+    let config_path = dir.path().join("config.yaml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"
+temp_dir: "{}"
+tasks:
+  copy_task:
+    commands:
+      - copy:
+          src: "{}"
+          target: "{}"
+          compare: hash
+"#,
+            dir.path().join(".ms_temp").to_string_lossy(),
+            src_dir.to_string_lossy(),
+            target_dir.to_string_lossy(),
+        ),
+    )
+    .unwrap();
+
+    run_at(&config_path, dir.path(), Mode::Install).await;
+    let placed = fs::metadata(target_dir.join("same.txt"))
+        .unwrap()
+        .modified()
+        .unwrap();
+
+    // A checkout rewrites both sources; only one actually changes.
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    fs::write(src_dir.join("same.txt"), "same").unwrap();
+    fs::write(src_dir.join("edit.txt"), "v2").unwrap();
+
+    run_at(&config_path, dir.path(), Mode::Update).await;
+    assert_eq!(
+        fs::metadata(target_dir.join("same.txt"))
+            .unwrap()
+            .modified()
+            .unwrap(),
+        placed
+    );
+    assert_eq!(
+        fs::read_to_string(target_dir.join("edit.txt")).unwrap(),
+        "v2"
+    );
+}
+
+#[tokio::test]
+async fn test_sub_config_forwards_tasks_env_and_vars() {
+    let dir = tempdir().unwrap();
+    let out = dir.path().join("out");
+    fs::create_dir_all(&out).unwrap();
+    fs::write(dir.path().join("greeting.tmpl"), "hello {{ name }}").unwrap();
+    fs::write(
+        dir.path().join("sub.yaml"),
+        format!(
+            r#"
+temp_dir: "{tmp}/.ms_temp_sub"
+tasks:
+  base:
+    commands:
+      - run:
+          commands: "echo $GREETING > {out}/base.txt"
+  app:
+    depends_on: [base]
+    commands:
+      - template:
+          src: greeting.tmpl
+          target: "{out}/app.txt"
+  other:
+    commands:
+      - run:
+          commands: "touch {out}/other.txt"
+"#,
+            tmp = dir.path().display(),
+            out = out.display()
+        ),
+    )
+    .unwrap();
+    let config_path = dir.path().join("config.yaml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"
+temp_dir: "{tmp}/.ms_temp"
+tasks:
+  parent:
+    commands:
+      - machine_setup:
+          config: sub.yaml
+          tasks: [app]
+          env:
+            GREETING: hi
+          vars:
+            name: sub
+      - machine_setup:
+          config: missing.yaml
+          optional: true
+"#,
+            tmp = dir.path().display()
+        ),
+    )
+    .unwrap();
+
+    run_at(&config_path, dir.path(), Mode::Install).await;
+
+    assert_eq!(
+        fs::read_to_string(out.join("base.txt")).unwrap().trim(),
+        "hi"
+    );
+    assert_eq!(
+        fs::read_to_string(out.join("app.txt")).unwrap(),
+        "hello sub"
+    );
+    assert!(!out.join("other.txt").exists());
+}
+
+#[tokio::test]
+async fn test_depends_on_task_of_another_document() {
+    let dir = tempdir().unwrap();
+    let out = dir.path().join("out");
+    fs::create_dir_all(dir.path().join("langs")).unwrap();
+    fs::create_dir_all(&out).unwrap();
+    fs::write(dir.path().join("langs/rust.tmpl"), "rust {{ os }}").unwrap();
+    fs::write(
+        dir.path().join("langs/langs.yaml"),
+        format!(
+            r#"
+tasks:
+  rust:
+    commands:
+      - template:
+          src: rust.tmpl
+          target: "{out}/rust.txt"
+  go:
+    commands:
+      - run:
+          commands: "touch {out}/go.txt"
+"#,
+            out = out.display()
+        ),
+    )
+    .unwrap();
+    let config_path = dir.path().join("config.yaml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"
+temp_dir: "{tmp}/.ms_temp"
+tasks:
+  editor:
+    depends_on: ["./langs/langs.yaml#rust"]
+    commands:
+      - run:
+          commands: "cp {out}/rust.txt {out}/editor.txt"
+"#,
+            tmp = dir.path().display(),
+            out = out.display()
+        ),
+    )
+    .unwrap();
+
+    run_at(&config_path, dir.path(), Mode::Install).await;
+
+    // `rust` ran first, resolving its template against its own document.
+    assert!(fs::read_to_string(out.join("editor.txt"))
+        .unwrap()
+        .starts_with("rust "));
+    assert!(!out.join("go.txt").exists());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_linked_task_also_run_by_its_sub_config_runs_once() {
+    let dir = tempdir().unwrap();
+    let out = dir.path().join("out");
+    fs::create_dir_all(dir.path().join("langs")).unwrap();
+    fs::create_dir_all(&out).unwrap();
+    fs::write(
+        dir.path().join("langs/langs.yaml"),
+        format!(
+            r#"
+tasks:
+  rust:
+    commands:
+      - run:
+          commands: "echo rust >> {out}/rust.log"
+"#,
+            out = out.display()
+        ),
+    )
+    .unwrap();
+    let config_path = dir.path().join("config.yaml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"
+temp_dir: "{tmp}/.ms_temp"
+tasks:
+  editor:
+    depends_on: ["./langs/langs.yaml#rust"]
+    commands: []
+  langs:
+    depends_on: ["editor"]
+    commands:
+      - machine_setup:
+          config: ./langs/langs.yaml
+"#,
+            tmp = dir.path().display()
+        ),
+    )
+    .unwrap();
+
+    run_at(&config_path, dir.path(), Mode::Install).await;
+
+    // Reached through the link and again through the Sub-config: once.
+    assert_eq!(fs::read_to_string(out.join("rust.log")).unwrap(), "rust\n");
+}
+
+#[tokio::test]
+async fn test_extends_overrides_base_task_and_keeps_its_paths() {
+    let dir = tempdir().unwrap();
+    let out = dir.path().join("out");
+    fs::create_dir_all(dir.path().join("shared")).unwrap();
+    fs::create_dir_all(&out).unwrap();
+    fs::write(dir.path().join("shared/motd.tmpl"), "{{ team }}").unwrap();
+    fs::write(
+        dir.path().join("shared/base.yaml"),
+        format!(
+            r#"
+tasks:
+  motd:
+    commands:
+      - template:
+          src: motd.tmpl
+          target: "{out}/motd.txt"
+          vars:
+            team: base
+  extra:
+    commands:
+      - run:
+          commands: "touch {out}/extra.txt"
+"#,
+            out = out.display()
+        ),
+    )
+    .unwrap();
+    let config_path = dir.path().join("team.yaml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"
+extends: ./shared/base.yaml
+temp_dir: "{tmp}/.ms_temp"
+tasks:
+  extra:
+    skip_if: "{out}"
+  team:
+    depends_on: [motd]
+    commands:
+      - run:
+          commands: "cp {out}/motd.txt {out}/team.txt"
+"#,
+            tmp = dir.path().display(),
+            out = out.display()
+        ),
+    )
+    .unwrap();
+
+    run_at(&config_path, dir.path(), Mode::Install).await;
+
+    assert_eq!(fs::read_to_string(out.join("team.txt")).unwrap(), "base");
+    assert!(!out.join("extra.txt").exists());
+}
+
+#[tokio::test]
+async fn test_sub_config_from_git_repository_copies_sibling_files() {
+    let dir = tempdir().unwrap();
+    let out = dir.path().join("out");
+    let work = dir.path().join("work");
+    fs::create_dir_all(work.join("setup/files")).unwrap();
+    fs::write(work.join("setup/files/gitconfig"), "[user]\n").unwrap();
+    fs::write(
+        work.join("setup/machine.yaml"),
+        format!(
+            r#"
+tasks:
+  git:
+    commands:
+      - copy:
+          src: ./files
+          target: "{out}"
+"#,
+            out = out.display()
+        ),
+    )
+    .unwrap();
+    let git = |cwd: &std::path::Path, args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .args(args)
+            .current_dir(cwd)
+            .env("GIT_AUTHOR_NAME", "t")
+            .env("GIT_AUTHOR_EMAIL", "t@example.com")
+            .env("GIT_COMMITTER_NAME", "t")
+            .env("GIT_COMMITTER_EMAIL", "t@example.com")
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    git(&work, &["init", "--quiet", "--initial-branch=main"]);
+    git(&work, &["add", "-A"]);
+    git(&work, &["commit", "--quiet", "-m", "dotfiles"]);
+    let bare = dir.path().join("dots.git");
+    git(
+        dir.path(),
+        &[
+            "clone",
+            "--quiet",
+            "--bare",
+            work.to_str().unwrap(),
+            bare.to_str().unwrap(),
+        ],
+    );
+
+    let config_path = dir.path().join("config.yaml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"
+temp_dir: "{tmp}/.ms_temp"
+tasks:
+  dotfiles:
+    commands:
+      - machine_setup:
+          config: "git+file://{bare}@main#setup/machine.yaml"
+"#,
+            tmp = dir.path().display(),
+            bare = bare.display()
+        ),
+    )
+    .unwrap();
+
+    run_at(&config_path, dir.path(), Mode::Install).await;
+
+    assert_eq!(
+        fs::read_to_string(out.join("gitconfig")).unwrap(),
+        "[user]\n"
+    );
+}
+
+#[tokio::test]
+async fn test_workspace_runs_documents_together_with_namespaced_tasks() {
+    let dir = tempdir().unwrap();
+    let out = dir.path().join("out");
+    fs::create_dir_all(dir.path().join("personal/files")).unwrap();
+    fs::create_dir_all(dir.path().join("work/files")).unwrap();
+    fs::create_dir_all(&out).unwrap();
+    fs::write(dir.path().join("personal/files/a.txt"), "personal").unwrap();
+    fs::write(dir.path().join("work/files/a.txt"), "work").unwrap();
+    for (member, depends) in [("personal", ""), ("work", "depends_on: [files]")] {
+        fs::write(
+            dir.path().join(member).join("machine_setup.yaml"),
+            format!(
+                r#"
+tasks:
+  files:
+    commands:
+      - copy:
+          src: ./files
+          target: "{out}/{member}"
+  after:
+    {depends}
+    commands:
+      - run:
+          commands: "cp {out}/{member}/a.txt {out}/{member}.done"
+"#,
+                out = out.display()
+            ),
+        )
+        .unwrap();
+    }
+    let config_path = dir.path().join("workspace.yaml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"
+temp_dir: "{tmp}/.ms_temp"
+configs:
+  - ./personal/machine_setup.yaml
+  - ./work/machine_setup.yaml
 "#,
-            src_dir.to_string_lossy().replace('\\', "/"),
-            target_dir.to_string_lossy().replace('\\', "/"),
+            tmp = dir.path().display()
         ),
     )
     .unwrap();
 
+    let config = config::load_config(config_path.to_str().unwrap()).unwrap();
+    let names: Vec<&str> = config.tasks.keys().map(String::as_str).collect();
+    assert_eq!(
+        names,
+        vec![
+            "personal/files",
+            "personal/after",
+            "work/files",
+            "work/after"
+        ]
+    );
+
     run_at(&config_path, dir.path(), Mode::Install).await;
 
-    let link = target_dir.join("dotfile");
-    let meta = link.symlink_metadata().unwrap();
-    assert!(
-        meta.file_type().is_symlink(),
-        "force should replace the file with a symlink"
+    assert_eq!(fs::read_to_string(out.join("work.done")).unwrap(), "work");
+    assert_eq!(
+        fs::read_to_string(out.join("personal/a.txt")).unwrap(),
+        "personal"
     );
-    // Reading through the link yields the source content.
-    assert_eq!(fs::read_to_string(&link).unwrap(), "from-source");
+    let config = config::load_config(config_path.to_str().unwrap()).unwrap();
+    let history =
+        config::history::History::load_for(&dir.path().join(".ms_temp"), &config).unwrap();
+    assert!(history.is_installed("work/after"));
 }
 
 #[tokio::test]
-async fn test_symlink_without_force_skips_existing_file() {
+async fn test_documents_sharing_temp_dir_keep_separate_history() {
     let dir = tempdir().unwrap();
-    let src_dir = dir.path().join("source");
-    let target_dir = dir.path().join("target");
-    fs::create_dir_all(&src_dir).unwrap();
-    fs::create_dir_all(&target_dir).unwrap();
-    fs::write(src_dir.join("dotfile"), "from-source").unwrap();
-    fs::write(target_dir.join("dotfile"), "pre-existing").unwrap();
+    let temp_dir = dir.path().join(".ms_temp");
+    let log = dir.path().join("log");
+    for name in ["personal", "work"] {
+        fs::write(
+            dir.path().join(format!("{name}.yaml")),
+            format!(
+                r#"
+temp_dir: "{tmp}"
+tasks:
+  tools:
+    commands:
+      - run:
+          commands: "echo {name} >> {log}"
+"#,
+                tmp = temp_dir.display(),
+                log = log.display()
+            ),
+        )
+        .unwrap();
+    }
 
+    // `work/tools` runs although `personal/tools` is installed, and a second
+    // `personal` run skips its own installed Task.
+    for name in ["personal", "work", "personal"] {
+        let path = dir.path().join(format!("{name}.yaml"));
+        let config = config::load_config(path.to_str().unwrap()).unwrap();
+        let (events, _rx) = machine_setup::engine::sink::ChannelSink::channel();
+        let runner = TaskRunner::new(config, Mode::Install, events)
+            .with_config_dir(dir.path().to_path_buf());
+        let _ = runner.run_all(false).await;
+    }
+
+    assert_eq!(fs::read_to_string(&log).unwrap(), "personal\nwork\n");
+}
+
+#[tokio::test]
+async fn test_unreadable_history_stops_the_run_and_is_kept() {
+    let dir = tempdir().unwrap();
+    let temp_dir = dir.path().join(".ms_temp");
+    fs::create_dir_all(&temp_dir).unwrap();
+    let history = temp_dir.join("history.json");
+    fs::write(&history, "{ not json").unwrap();
+    let marker = dir.path().join("ran");
     let config_path = dir.path().join("config.yaml");
     fs::write(
         &config_path,
         format!(
             r#"
+temp_dir: "{tmp}"
 tasks:
-  link_task:
+  tools:
     commands:
-      - symlink:
-          src: "{}"
-          target: "{}"
+      - run:
+          commands: "touch {marker}"
 "#,
-            src_dir.to_string_lossy().replace('\\', "/"),
-            target_dir.to_string_lossy().replace('\\', "/"),
+            tmp = temp_dir.display(),
+            marker = marker.display()
         ),
     )
     .unwrap();
 
-    run_at(&config_path, dir.path(), Mode::Install).await;
+    let config = config::load_config(config_path.to_str().unwrap()).unwrap();
+    let (events, _rx) = machine_setup::engine::sink::ChannelSink::channel();
+    let runner =
+        TaskRunner::new(config, Mode::Install, events).with_config_dir(dir.path().to_path_buf());
+    let err = runner.run_all(false).await.unwrap_err();
 
-    // Without force, the pre-existing real file is left in place.
-    let link = target_dir.join("dotfile");
-    assert!(!link.symlink_metadata().unwrap().file_type().is_symlink());
-    assert_eq!(fs::read_to_string(&link).unwrap(), "pre-existing");
+    assert!(err.to_string().contains("history.json"), "{err}");
+    assert!(!marker.exists());
+    assert_eq!(fs::read_to_string(&history).unwrap(), "{ not json");
 }
 
 #[tokio::test]
@@ -1643,6 +2701,92 @@ tasks:
     );
 }
 
+#[tokio::test]
+async fn test_copy_ignore_uses_gitignore_patterns_and_ignore_file() {
+    let dir = tempdir().unwrap();
+    let src_dir = dir.path().join("source");
+    let target_dir = dir.path().join("target");
+    fs::create_dir_all(src_dir.join("cmd")).unwrap();
+    fs::create_dir_all(src_dir.join("nvim/swap")).unwrap();
+    fs::write(src_dir.join("cmd/run.sh"), "run").unwrap();
+    fs::write(src_dir.join("nvim/.init.lua.swp"), "swap").unwrap();
+    fs::write(src_dir.join("nvim/init.lua"), "init").unwrap();
+    fs::write(src_dir.join("a.log"), "log").unwrap();
+    fs::write(src_dir.join("keep.log"), "keep").unwrap();
+    fs::write(src_dir.join(".machine_setupignore"), "swap/\n!keep.log\n").unwrap();
+
+    let config_path = dir.path().join("config.yaml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"
+tasks:
+  copy_task:
+    commands:
+      - copy:
+          src: "{}"
+          target: "{}"
+          ignore: ["md", "**/*.swp", "*.log"]
+"#,
+            src_dir.to_string_lossy().replace('\\', "/"),
+            target_dir.to_string_lossy().replace('\\', "/"),
+        ),
+    )
+    .unwrap();
+
+    run_at(&config_path, dir.path(), Mode::Install).await;
+    assert!(target_dir.join("cmd/run.sh").exists());
+    assert!(target_dir.join("nvim/init.lua").exists());
+    assert!(!target_dir.join("nvim/.init.lua.swp").exists());
+    assert!(!target_dir.join("nvim/swap").exists());
+    assert!(!target_dir.join("a.log").exists());
+    assert!(target_dir.join("keep.log").exists());
+    assert!(!target_dir.join(".machine_setupignore").exists());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_copy_applies_file_and_dir_modes() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempdir().unwrap();
+    let src_dir = dir.path().join("ssh");
+    let target_dir = dir.path().join("home/.ssh");
+    fs::create_dir_all(src_dir.join("keys")).unwrap();
+    fs::write(src_dir.join("keys/id_ed25519"), "secret").unwrap();
+    fs::write(src_dir.join("config"), "Host *").unwrap();
+
+    let config_path = dir.path().join("config.yaml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"
+tasks:
+  ssh:
+    commands:
+      - copy:
+          src: "{}"
+          target: "{}"
+          mode: "0600"
+          dir_mode: "0700"
+"#,
+            src_dir.to_string_lossy(),
+            target_dir.to_string_lossy(),
+        ),
+    )
+    .unwrap();
+
+    run_at(&config_path, dir.path(), Mode::Install).await;
+
+    let mode = |p: &std::path::Path| fs::metadata(p).unwrap().permissions().mode() & 0o7777;
+    assert_eq!(mode(&target_dir), 0o700);
+    assert_eq!(mode(&target_dir.join("keys")), 0o700);
+    assert_eq!(mode(&target_dir.join("config")), 0o600);
+    assert_eq!(mode(&target_dir.join("keys/id_ed25519")), 0o600);
+    // The parent of `target` is not part of the copied tree.
+    assert_ne!(mode(&dir.path().join("home")), 0o700);
+}
+
 #[tokio::test]
 async fn parallel_apt_command_entries_serialize_on_lane() {
     let marker_dir = tempdir().unwrap();
@@ -1745,3 +2889,356 @@ tasks:
         "distinct families must not wait on each other"
     );
 }
+
+// ─── Template command tests ───
+
+#[tokio::test]
+async fn test_template_renders_vars_and_facts_then_uninstall_removes() {
+    let dir = tempdir().unwrap();
+    let tmpl = dir.path().join("gitconfig.tmpl");
+    let target = dir.path().join("out/.gitconfig");
+    fs::write(&tmpl, "email = {{ email }}\nhome = {{ home }}\n").unwrap();
+
+    let config_path = dir.path().join("config.yaml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"
+temp_dir: "{}"
+tasks:
+  gitconfig:
+    commands:
+      - template:
+          src: "{}"
+          target: "{}"
+          vars:
+            email: "me@example.com"
+"#,
+            dir.path()
+                .join(".ms_temp")
+                .to_string_lossy()
+                .replace('\\', "/"),
+            tmpl.to_string_lossy().replace('\\', "/"),
+            target.to_string_lossy().replace('\\', "/"),
+        ),
+    )
+    .unwrap();
+
+    run_at(&config_path, dir.path(), Mode::Install).await;
+    let home = dirs::home_dir().unwrap();
+    assert_eq!(
+        fs::read_to_string(&target).unwrap(),
+        format!("email = me@example.com\nhome = {}\n", home.display())
+    );
+
+    run_at(&config_path, dir.path(), Mode::Uninstall).await;
+    assert!(!target.exists());
+    assert!(tmpl.exists());
+}
+
+// ─── line_in_file / block_in_file tests ───
+
+#[tokio::test]
+async fn test_line_and_block_in_file_install_then_uninstall() {
+    let dir = tempdir().unwrap();
+    let hosts = dir.path().join("hosts");
+    fs::write(&hosts, "127.0.0.1 localhost\n#PermitRootLogin yes\n").unwrap();
+
+    let config_path = dir.path().join("config.yaml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"
+temp_dir: "{temp}"
+tasks:
+  edits:
+    commands:
+      - line_in_file:
+          path: "{path}"
+          line: "PermitRootLogin no"
+          regex: "^#?PermitRootLogin"
+      - line_in_file:
+          path: "{path}"
+          line: "export EDITOR=vim"
+      - block_in_file:
+          path: "{path}"
+          name: lab
+          content: "10.0.0.2 lab"
+"#,
+            temp = dir
+                .path()
+                .join(".ms_temp")
+                .to_string_lossy()
+                .replace('\\', "/"),
+            path = hosts.to_string_lossy().replace('\\', "/"),
+        ),
+    )
+    .unwrap();
+
+    run_at(&config_path, dir.path(), Mode::Install).await;
+    run_at(&config_path, dir.path(), Mode::Install).await;
+    assert_eq!(
+        fs::read_to_string(&hosts).unwrap(),
+        "127.0.0.1 localhost\n\
+         PermitRootLogin no\n\
+         export EDITOR=vim\n\
+         # >>> machine_setup lab >>>\n\
+         10.0.0.2 lab\n\
+         # <<< machine_setup lab <<<\n"
+    );
+
+    run_at(&config_path, dir.path(), Mode::Uninstall).await;
+    assert_eq!(fs::read_to_string(&hosts).unwrap(), "127.0.0.1 localhost\n");
+}
+
+// ─── Package command tests ───
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_package_installs_missing_once_and_removes_on_uninstall() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempdir().unwrap();
+    let bin = dir.path().join("bin");
+    fs::create_dir_all(&bin).unwrap();
+    let state = dir.path().join("installed");
+    let log = dir.path().join("calls.log");
+    fs::write(&state, "").unwrap();
+    // Fake `brew`: `list --versions` queries the state file; install/uninstall edit it.
+    let script = format!(
+        r#"#!/bin/sh
+state="{state}"
+case "$1" in
+  list) grep -qx "$3" "$state" && echo "$3 1.0" ;;
+  install) shift; echo "install $*" >> "{log}"; for p in "$@"; do echo "$p" >> "$state"; done ;;
+  uninstall) shift; echo "uninstall $*" >> "{log}"; for p in "$@"; do grep -vx "$p" "$state" > "$state.tmp"; mv "$state.tmp" "$state"; done ;;
+esac
+"#,
+        state = state.display(),
+        log = log.display(),
+    );
+    let brew = bin.join("brew");
+    fs::write(&brew, script).unwrap();
+    fs::set_permissions(&brew, fs::Permissions::from_mode(0o755)).unwrap();
+    let path = std::env::var("PATH").unwrap_or_default();
+    std::env::set_var("PATH", format!("{}:{path}", bin.display()));
+
+    let config_path = dir.path().join("config.yaml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"
+temp_dir: "{}"
+parallel: true
+tasks:
+  cli:
+    commands:
+      - package:
+          brew: [ms-fake-ripgrep, ms-fake-fd]
+  more:
+    commands:
+      - package:
+          brew: [ms-fake-fd, ms-fake-jq]
+"#,
+            dir.path().join(".ms_temp").display(),
+        ),
+    )
+    .unwrap();
+
+    run_at(&config_path, dir.path(), Mode::Install).await;
+    let installed = fs::read_to_string(&state).unwrap();
+    for package in ["ms-fake-ripgrep", "ms-fake-fd", "ms-fake-jq"] {
+        assert_eq!(installed.lines().filter(|l| *l == package).count(), 1);
+    }
+
+    // Everything is present now: a second install queries and runs nothing.
+    let calls_before = fs::read_to_string(&log).unwrap();
+    run_at(&config_path, dir.path(), Mode::Install).await;
+    assert_eq!(fs::read_to_string(&log).unwrap(), calls_before);
+
+    run_at(&config_path, dir.path(), Mode::Uninstall).await;
+    assert_eq!(fs::read_to_string(&state).unwrap().trim(), "");
+}
+
+// ─── Archive command tests ───
+
+fn write_tar_gz(path: &std::path::Path, files: &[(&str, &str)]) {
+    let gz = flate2::write::GzEncoder::new(
+        fs::File::create(path).unwrap(),
+        flate2::Compression::default(),
+    );
+    let mut builder = tar::Builder::new(gz);
+    for (name, body) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(body.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, name, body.as_bytes())
+            .unwrap();
+    }
+    builder.into_inner().unwrap().finish().unwrap();
+}
+
+#[tokio::test]
+async fn test_archive_extracts_upgrades_and_uninstalls_recorded_files() {
+    let dir = tempdir().unwrap();
+    let archive = dir.path().join("sdk.tar.gz");
+    let target = dir.path().join("opt/sdk");
+    fs::create_dir_all(&target).unwrap();
+    fs::write(target.join("user.txt"), "mine").unwrap();
+    write_tar_gz(
+        &archive,
+        &[
+            ("sdk-1/bin/tool", "v1"),
+            ("sdk-1/lib/core", "core"),
+            ("sdk-1/lib/legacy", "old"),
+        ],
+    );
+
+    let config_path = dir.path().join("config.yaml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"
+temp_dir: "{}"
+tasks:
+  sdk:
+    commands:
+      - archive:
+          src: "{}"
+          target: "{}"
+          strip_components: 1
+"#,
+            dir.path()
+                .join(".ms_temp")
+                .to_string_lossy()
+                .replace('\\', "/"),
+            archive.to_string_lossy().replace('\\', "/"),
+            target.to_string_lossy().replace('\\', "/"),
+        ),
+    )
+    .unwrap();
+
+    run_at(&config_path, dir.path(), Mode::Install).await;
+    assert_eq!(fs::read_to_string(target.join("bin/tool")).unwrap(), "v1");
+    assert!(target.join("lib/legacy").exists());
+
+    // A newer release drops `lib/legacy`; reinstall removes it.
+    write_tar_gz(
+        &archive,
+        &[("sdk-2/bin/tool", "v2"), ("sdk-2/lib/core", "core")],
+    );
+    run_at(&config_path, dir.path(), Mode::Update).await;
+    assert_eq!(fs::read_to_string(target.join("bin/tool")).unwrap(), "v2");
+    assert!(!target.join("lib/legacy").exists());
+
+    run_at(&config_path, dir.path(), Mode::Uninstall).await;
+    assert!(!target.join("bin").exists());
+    assert!(!target.join("lib").exists());
+    assert_eq!(fs::read_to_string(target.join("user.txt")).unwrap(), "mine");
+}
+
+#[tokio::test]
+async fn test_archive_from_a_new_release_url_replaces_the_old_release() {
+    let dir = tempdir().unwrap();
+    let v1 = dir.path().join("v1.tar.gz");
+    let v2 = dir.path().join("v2.tar.gz");
+    let target = dir.path().join("opt/sdk");
+    write_tar_gz(&v1, &[("bin/tool", "v1"), ("lib/legacy", "old")]);
+    write_tar_gz(&v2, &[("bin/tool", "v2")]);
+    let config_path = dir.path().join("config.yaml");
+    let write_config = |archive: &std::path::Path| {
+        fs::write(
+            &config_path,
+            format!(
+                r#"
+temp_dir: "{}"
+tasks:
+  sdk:
+    commands:
+      - archive:
+          src: "{}"
+          target: "{}"
+"#,
+                dir.path()
+                    .join(".ms_temp")
+                    .to_string_lossy()
+                    .replace('\\', "/"),
+                archive.to_string_lossy().replace('\\', "/"),
+                target.to_string_lossy().replace('\\', "/"),
+            ),
+        )
+        .unwrap();
+    };
+
+    write_config(&v1);
+    run_at(&config_path, dir.path(), Mode::Install).await;
+    assert!(target.join("lib/legacy").exists());
+
+    write_config(&v2);
+    run_at(&config_path, dir.path(), Mode::Install).await;
+    assert_eq!(fs::read_to_string(target.join("bin/tool")).unwrap(), "v2");
+    assert!(!target.join("lib/legacy").exists());
+}
+
+/// Serve `bodies` to one request each on a local port; the URL of the
+/// document.
+fn serve(bodies: Vec<String>) -> String {
+    use std::io::{BufRead, BufReader, Write};
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/setup.yaml", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for body in bodies {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 0 && line != "\r\n" {
+                line.clear();
+            }
+            let mut stream = reader.into_inner();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .unwrap();
+        }
+    });
+    url
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_failed_run_of_a_changed_url_config_is_asked_about_again() {
+    use machine_setup::config::remote::{OnChange, Remote};
+
+    let dir = tempdir().unwrap();
+    let v1 = "tasks:\n  t:\n    commands:\n      - run:\n          commands: \"true\"\n";
+    let v2 = "tasks:\n  t:\n    commands:\n      - run:\n          commands: \"exit 1\"\n";
+    let url = serve(vec![v1.to_string(), v2.to_string(), v2.to_string()]);
+    let run = |on_change| {
+        let remote = Remote {
+            temp_dir: dir.path(),
+            on_change,
+        };
+        let mut config = config::load_config_remote(&url, &remote)?;
+        config.temp_dir = dir.path().join(".ms_temp").to_string_lossy().to_string();
+        let (events, _rx) = machine_setup::engine::sink::ChannelSink::channel();
+        Ok::<_, machine_setup::error::Error>(
+            TaskRunner::new(config, Mode::Install, events)
+                .with_config_dir(dir.path().to_path_buf())
+                .with_remote_dir(dir.path().to_path_buf()),
+        )
+    };
+
+    run(OnChange::Refuse).unwrap().run_all(true).await.unwrap();
+    // The change is accepted, but its run fails: it is not the last run.
+    let changed = run(OnChange::Accept).unwrap();
+    assert!(changed.run_all(true).await.is_err());
+    let err = run(OnChange::Refuse).err().unwrap();
+    assert!(
+        err.to_string().contains("changed since it last ran"),
+        "{err}"
+    );
+}